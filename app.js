@@ -2,6 +2,87 @@ import { Elysia } from "elysia";
 import { staticPlugin } from "@elysiajs/static";
 import { html } from "@elysiajs/html";
 import { dirname, join } from "path";
+import {
+  chartSettings,
+  escape,
+  parseAnnotations,
+  renderSvg,
+  settingsForUnit,
+} from "./lib/chart.js";
+import { config } from "./lib/config.js";
+import { catchErrors, errorResponse, requestId } from "./lib/errors.js";
+import { renderFeed } from "./lib/feed.js";
+import { negotiate, project } from "./lib/format.js";
+import { reload, startJobs } from "./lib/jobs.js";
+import { withLimits } from "./lib/limits.js";
+import { startModbus } from "./lib/modbus.js";
+import { OVERLAYS, overlayFor } from "./lib/overlays.js";
+import {
+  EINK,
+  EINK_ROTATIONS,
+  brandHeader,
+  brandStyle,
+  dayDataset,
+  jsonLd,
+  pageTitle,
+  renderEink,
+  renderKiosk,
+  renderSeasonality,
+} from "./lib/pages.js";
+import {
+  preferredChart,
+  preferredProfile,
+  preferredStatistics,
+  profileCookie,
+  statisticsCookie,
+} from "./lib/preferences.js";
+import {
+  LEVELS,
+  REGIONS,
+  currentEntry,
+  getHorizon,
+  getPrices,
+  inHourRange,
+  isoDate,
+  loadPrices,
+  parseDate,
+  parseDay,
+  parseHourRange,
+  parsePeriod,
+  priceLevel,
+} from "./lib/prices.js";
+import { inCurrency, parseCurrency } from "./lib/rates.js";
+import { listen } from "./lib/server.js";
+import {
+  defaultRegion,
+  pathRegion,
+  sitePaths,
+  withBase,
+} from "./lib/sites.js";
+import {
+  DEFAULT_STATISTICS,
+  cheapestWindow,
+  dayStatistics,
+  hourly,
+  seasonality,
+  summarize,
+  withPercentiles,
+  withTrends,
+} from "./lib/stats.js";
+import { UNITS, inUnit, preferredUnit, unitCookie } from "./lib/units.js";
+import admin from "./routes/admin.js";
+import api from "./routes/api.js";
+import auth from "./routes/auth.js";
+import events from "./routes/events.js";
+import exporter from "./routes/export.js";
+import integrations from "./routes/integrations.js";
+import itemRoutes from "./routes/items.js";
+import locationRoutes from "./routes/locations.js";
+import mcp from "./routes/mcp.js";
+import profileRoutes from "./routes/profiles.js";
+import report from "./routes/report.js";
+import site from "./routes/site.js";
+import widget from "./routes/widget.js";
 
 let __dirname = dirname(new URL(import.meta.url).pathname);
 __dirname =
@@ -9,19 +90,35 @@ __dirname =
     ? __dirname.replace(/^\/([A-Z]):/, "$1:\\").replace(/\//g, "\\")
     : __dirname;
 
-// Fetch electricity prices
-async function fetchPrices(year, month, day, region) {
-  const url = `https://www.hvakosterstrommen.no/api/v1/prices/${year}/${month.toString().padStart(2, "0")}-${day.toString().padStart(2, "0")}_${region}.json`;
-  const response = await fetch(url);
-  if (!response.ok) {
-    throw new Error(`HTTP error! status: ${response.status}`);
-  }
-  return response.json();
+// `value` as JSON for an inline `<script>`, where a `</script>` in a string
+// (e.g. a threshold name from the chart cookie) would end the script
+function scriptJson(value) {
+  return JSON.stringify(value).replace(/</g, "\\u003c");
 }
 
 // Helper function to render the page template
 function renderPage(data) {
-  const { chart } = data;
+  const {
+    chart,
+    region = "NO2",
+    unit = "ore",
+    statistics = DEFAULT_STATISTICS,
+    settings = chartSettings(),
+    date = null,
+    url = null,
+    dataset = null,
+  } = data;
+  const { label, short } = UNITS[unit];
+  const {
+    grid,
+    padding,
+    labels,
+    thresholds,
+    bands,
+    include_zero,
+    symmetric,
+    max,
+  } = settingsForUnit(settings, unit);
 
   return `<!DOCTYPE html>
 <html>
@@ -29,23 +126,28 @@ function renderPage(data) {
     <meta charset="UTF-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
     <meta name="description" content="Hold styr på strømprisene i Norge.">
-    <title>elektron</title>
-    <link rel="stylesheet" href="/style.css">
-</head>
+    <title>${escape(pageTitle())}</title>
+    <base href="/">
+    <link rel="icon" href="favicon.svg" type="image/svg+xml">
+    <link rel="stylesheet" href="style.css">
+${brandStyle()}${url ? `    <link rel="alternate" type="application/json+oembed" href="oembed?url=${encodeURIComponent(url)}" title="${escape(pageTitle())}">\n` : ""}${dataset ? `    ${jsonLd(dataset)}\n` : ""}</head>
 <body>
     <div id="header">
-        <span id="headerTitle">Strømpriser (øre/kWh) i</span>
+${brandHeader()}        <span id="headerTitle">Strømpriser (${label}) i</span>
         <span id="regionSelector">
             <select class="region-dropdown" id="regionDropdown">
-                <option value="NO1">NO1</option>
-                <option value="NO2" selected>NO2</option>
-                <option value="NO3">NO3</option>
-                <option value="NO4">NO4</option>
-                <option value="NO5">NO5</option>
+${REGIONS.map((zone) => `                <option value="${zone}"${zone === region ? " selected" : ""}>${zone}</option>`).join("\n")}
+            </select>
+        </span>
+        <span id="unitSelector">
+            <select class="region-dropdown" id="unitDropdown">
+${Object.entries(UNITS).map(([key, value]) => `                <option value="${key}"${key === unit ? " selected" : ""}>${value.label}</option>`).join("\n")}
             </select>
         </span>
     </div>
 
+    <div id="banner" class="banner" style="display: none;"></div>
+
     <div id="dateNavigation" style="display: none;">
         <button class="nav-button" id="prevButton">◀ Forrige</button>
         <div id="currentDate"></div>
@@ -53,18 +155,10 @@ function renderPage(data) {
     </div>
 
     <div id="thresholdControls" style="display: none;">
-        <label class="threshold-checkbox threshold-zero">
-            <input type="checkbox" id="threshold0" />
-            <span>0 øre</span>
-        </label>
-        <label class="threshold-checkbox threshold-fifty">
-            <input type="checkbox" id="threshold50" />
-            <span>Norgespris</span>
-        </label>
-        <label class="threshold-checkbox threshold-ninety">
-            <input type="checkbox" id="threshold75" />
-            <span>75 øre</span>
-        </label>
+${thresholds.map((threshold, i) => `        <label class="threshold-checkbox" style="color: ${threshold.color};">
+            <input type="checkbox" id="threshold${i}" checked />
+            <span>${escape(threshold.name)}</span>
+        </label>`).join("\n")}
     </div>
 
     <div class="loading" id="loading">Laster laster laster...</div>
@@ -77,36 +171,106 @@ function renderPage(data) {
     <div id="statistics" style="display: none;"></div>
 
     <script>
-        let chartData = ${JSON.stringify(chart)};
-        let currentDate = new Date();
-        let currentRegion = 'NO2';
-        let thresholdStates = {
-            zero: true,
-            fifty: true,
-            seventyFive: true
-        };
+        let chartData = ${scriptJson(chart)};
+        // Today, or the day linked to with ?date=
+        let currentDate = ${date ? `new Date(${date.getFullYear()}, ${date.getMonth()}, ${date.getDate()})` : "new Date()"};
+        let currentRegion = '${region}';
+        // Wholesale EUR/MWh hides the øre-based thresholds
+        const currentUnit = '${unit}';
+        const unitLabel = '${label}';
+        const unitShort = '${short}';
+        // Chosen with ?stats=, e.g. ?stats=median,current,remaining
+        const statistics = '${statistics.join(",")}';
+        // From the chart settings (see api/v1/chart-config), each shown
+        // while its checkbox is ticked
+        const chartSettings = ${scriptJson({ grid, padding, labels, bands, include_zero, symmetric, max })};
+        const thresholds = ${scriptJson(thresholds)};
+        let thresholdStates = thresholds.map(() => true);
+
+        // A y-axis or tooltip price; øre as kr with "nok" labels
+        function priceLabel(value) {
+            return chartSettings.labels === 'nok' ? (value / 100).toFixed(2) : value.toFixed(1);
+        }
+        const labelShort = chartSettings.labels === 'nok' ? 'kr' : unitShort;
+
+        // The step series and y-axis of a day, from the server with the
+        // enabled thresholds kept in view
+        async function fetchSeries(dateString) {
+            const include = thresholds
+                .filter((threshold, i) => thresholdStates[i])
+                .map(threshold => threshold.value);
+            const response = await fetch('api/v1/step-series?region=' + currentRegion +
+                '&date=' + dateString + '&unit=' + currentUnit + '&include=' + include.join(','));
+            if (!response.ok) throw new Error(response.statusText);
+            return response.json();
+        }
+
+        // Without the API, as on the generated static site: the prices alone,
+        // with the same axis policy as the server
+        function localSeries(dailyData) {
+            const points = dailyData.map(item => ({ hour: item.hour, price: item.price }));
+            points.push({ hour: points[points.length - 1].hour + 1, price: points[points.length - 1].price });
+            const prices = points.map(item => item.price);
+            if (chartSettings.include_zero) prices.push(0);
+            let min = Math.min(...prices);
+            let max = Math.max(...prices);
+            if (chartSettings.symmetric) {
+                max = Math.max(Math.abs(min), Math.abs(max));
+                min = -max;
+            }
+            const padding = max > min ? (max - min) * chartSettings.padding : Math.max(Math.abs(min * 0.1), 1);
+            const axis = { min: min - padding, max: max + padding };
+            if (chartSettings.max != null && chartSettings.max > axis.min) {
+                axis.max = chartSettings.max;
+                if (chartSettings.symmetric && axis.max > 0) axis.min = -axis.max;
+            }
+            axis.ticks = Array.from({ length: chartSettings.grid + 1 },
+                (_, i) => axis.min + (axis.max - axis.min) * i / chartSettings.grid);
+            const { cheap, expensive } = chartSettings.bands;
+            const bands = [
+                { from: axis.min, to: cheap, color: '#008E00' },
+                { from: expensive, to: axis.max, color: '#CC0000' },
+            ].filter(band => band.from != null && band.to != null)
+                .map(band => ({ ...band, from: Math.max(band.from, axis.min), to: Math.min(band.to, axis.max) }))
+                .filter(band => band.from < band.to);
+            return { points, axis, bands };
+        }
+
+        let graphs = 0;
 
         // dataObject is expected to be the full chartData array here.
-        function graphPrice(dataObject, targetDate = null) {
+        async function graphPrice(dataObject, targetDate = null) {
+            const drawn = ++graphs;
             const canvas = document.getElementById('priceGraph');
             const ctx = canvas.getContext('2d');
 
-            const parent = canvas.parentElement;
-            const dpr = window.devicePixelRatio || 1;
-            canvas.width = parent.clientWidth * dpr;
-            canvas.height = parent.clientHeight * dpr;
-            ctx.setTransform(1, 0, 0, 1, 0, 0); // Reset transform before scaling
-            ctx.scale(dpr, dpr);
-
             // Use the target date or fall back to today
             const dateToUse = targetDate || new Date();
             const offset = dateToUse.getTimezoneOffset();
             const adjustedDate = new Date(dateToUse.getTime() - offset * 60 * 1000);
             const dateString = adjustedDate.toISOString().split('T')[0];
 
-            // Filter the data for the specified date and prepare price and hour arrays
+            // Filter the data for the specified date
             let dailyData = dataObject.filter(item => item.time.startsWith(dateString));
-            if (dailyData.length === 0) {
+            let series = null;
+            if (dailyData.length > 0) {
+                try {
+                    series = await fetchSeries(dateString);
+                } catch (err) {
+                    series = localSeries(dailyData);
+                }
+            }
+            // A newer redraw (toggled threshold, resize) has taken over
+            if (drawn !== graphs) return;
+
+            const parent = canvas.parentElement;
+            const dpr = window.devicePixelRatio || 1;
+            canvas.width = parent.clientWidth * dpr;
+            canvas.height = parent.clientHeight * dpr;
+            ctx.setTransform(1, 0, 0, 1, 0, 0); // Reset transform before scaling
+            ctx.scale(dpr, dpr);
+
+            if (!series || series.points.length === 0) {
                 ctx.clearRect(0, 0, canvas.width / dpr, canvas.height / dpr);
                 ctx.font = '14px JetBrainsMono, "JetBrains Mono", monospace';
                 ctx.fillStyle = '#1D1C1A';
@@ -119,72 +283,39 @@ function renderPage(data) {
                 return;
             }
 
-            // Step graph: add a final point at last hour + 1 with the same value and correct hour label
-            let stepData = dailyData.map(item => ({ hour: item.hour, price: item.price }));
-            const last = stepData[stepData.length - 1];
-            // Add a new hour label for the last value
-            stepData.push({ hour: last.hour + 1, price: last.price });
-
-            const prices = stepData.map(item => item.price);
+            // Step graph: the final point repeats the last price at the hour after
+            const stepData = series.points;
             const hours = stepData.map(item => item.hour);
+            const paddedMin = series.axis.min;
+            const paddedMax = series.axis.max;
 
             const margin = { top: 30, right: 30, bottom: 40, left: 60 };
             const graphWidth = canvas.width / dpr - margin.left - margin.right;
             const graphHeight = canvas.height / dpr - margin.top - margin.bottom;
 
-            const maxPrice = Math.max(...prices);
-            const minPrice = Math.min(...prices);
-
-            let visualMin = minPrice;
-            let visualMax = maxPrice;
-
-            if (thresholdStates.zero) {
-                visualMin = Math.min(visualMin, 0);
-                visualMax = Math.max(visualMax, 0);
-            }
-
-            if (thresholdStates.fifty) {
-                visualMin = Math.min(visualMin, 50);
-                visualMax = Math.max(visualMax, 50);
-            }
-
-            if (thresholdStates.seventyFive) {
-                visualMin = Math.min(visualMin, 75);
-                visualMax = Math.max(visualMax, 75);
-            }
-
-            let priceRangeAdjusted = visualMax - visualMin;
-            let paddedMin, paddedMax;
+            ctx.clearRect(0, 0, canvas.width / dpr, canvas.height / dpr);
 
-            if (priceRangeAdjusted === 0) {
-                if (visualMin === 0 && visualMax === 0) {
-                    paddedMin = -1;
-                    paddedMax = 1;
-                } else {
-                    const padding = Math.max(Math.abs(visualMin * 0.1), 1);
-                    paddedMin = visualMin - padding;
-                    paddedMax = visualMax + padding;
-                }
-            } else {
-                paddedMin = visualMin - (priceRangeAdjusted * 0.1);
-                paddedMax = visualMax + (priceRangeAdjusted * 0.1);
-            }
+            // Shaded cheap and expensive bands, behind everything else
+            const toY = price => margin.top + graphHeight - ((price - paddedMin) / (paddedMax - paddedMin)) * graphHeight;
+            series.bands.forEach(band => {
+                ctx.globalAlpha = 0.1;
+                ctx.fillStyle = band.color;
+                ctx.fillRect(margin.left, toY(band.to), graphWidth, toY(band.from) - toY(band.to));
+                ctx.globalAlpha = 1;
+            });
 
-            ctx.clearRect(0, 0, canvas.width / dpr, canvas.height / dpr);
             ctx.font = '12px JetBrainsMono, "JetBrains Mono", monospace';
             ctx.fillStyle = '#000000';
             ctx.textAlign = 'right';
             ctx.textBaseline = 'middle';
 
             // Draw Y-axis labels
-            const yAxisTicks = 6;
-            for (let i = 0; i <= yAxisTicks; i++) {
-                const value = paddedMin + (paddedMax - paddedMin) * i / yAxisTicks;
-                const y = margin.top + graphHeight - (graphHeight * i / yAxisTicks);
+            series.axis.ticks.forEach((value, i) => {
+                const y = margin.top + graphHeight - (graphHeight * i / (series.axis.ticks.length - 1));
 
                 // Y-axis labels
-                ctx.fillText(value.toFixed(1), margin.left - 10, y);
-            }
+                ctx.fillText(priceLabel(value), margin.left - 10, y);
+            });
 
             // Draw X-axis labels with responsive spacing
             ctx.textAlign = 'center';
@@ -217,7 +348,11 @@ function renderPage(data) {
                 }
             }
 
-            // Draw step price line
+            // Draw step price line, cut off at the edges of the graph
+            ctx.save();
+            ctx.beginPath();
+            ctx.rect(margin.left - 2, margin.top - 2, graphWidth + 4, graphHeight + 4);
+            ctx.clip();
             for (let i = 0; i < stepData.length - 1; i++) {
                 const x1 = margin.left + (graphWidth / xTickDenominator) * i;
                 const x2 = margin.left + (graphWidth / xTickDenominator) * (i + 1);
@@ -238,6 +373,7 @@ function renderPage(data) {
                 ctx.lineWidth = 2;
                 ctx.stroke();
             }
+            ctx.restore();
 
             // Draw threshold lines
             drawThresholdLines(ctx, margin, graphWidth, graphHeight, paddedMin, paddedMax);
@@ -283,7 +419,7 @@ function renderPage(data) {
                 hoverCtx.textAlign = 'left';
 
                 // Tooltip
-                const text = hour.toString().padStart(2, '0') + ':00 - ' + price.toFixed(1) + ' øre';
+                const text = hour.toString().padStart(2, '0') + ':00 - ' + priceLabel(price) + ' ' + labelShort;
                 const textMetrics = hoverCtx.measureText(text);
                 let textX = xStep + 10;
                 let textY = yStep - 15;
@@ -327,14 +463,8 @@ function renderPage(data) {
         }
 
         function drawThresholdLines(ctx, margin, graphWidth, graphHeight, paddedMin, paddedMax) {
-            const thresholds = [
-                { value: 0, name: '0 øre', color: '#CC0000', enabled: thresholdStates.zero },
-                { value: 50, name: 'Norgespris', color: '#008E00', enabled: thresholdStates.fifty },
-                { value: 75, name: '75 øre', color: '#CC0000', enabled: thresholdStates.seventyFive }
-            ];
-
-            thresholds.forEach(threshold => {
-                if (threshold.enabled && threshold.value >= paddedMin && threshold.value <= paddedMax) {
+            thresholds.forEach((threshold, i) => {
+                if (thresholdStates[i] && threshold.value >= paddedMin && threshold.value <= paddedMax) {
                     const y = margin.top + graphHeight - ((threshold.value - paddedMin) / (paddedMax - paddedMin)) * graphHeight;
 
                     ctx.beginPath();
@@ -355,9 +485,8 @@ function renderPage(data) {
         }
 
         function updateThresholdStates() {
-            thresholdStates.zero = document.getElementById('threshold0').checked;
-            thresholdStates.fifty = document.getElementById('threshold50').checked;
-            thresholdStates.seventyFive = document.getElementById('threshold75').checked;
+            thresholdStates = thresholds.map((threshold, i) =>
+                document.getElementById('threshold' + i).checked);
 
             if (chartData) {
                 graphPrice(chartData, currentDate);
@@ -367,6 +496,16 @@ function renderPage(data) {
         function updateRegion() {
             currentRegion = document.getElementById('regionDropdown').value;
             loadData(currentDate);
+            subscribe();
+        }
+
+        // Re-render when a new hour starts and when tomorrow's prices land
+        let events = null;
+        function subscribe() {
+            if (events) events.close();
+            events = new EventSource('events?region=' + currentRegion);
+            events.addEventListener('tick', () => loadData(currentDate));
+            events.addEventListener('prices', () => loadData(currentDate));
         }
 
         async function loadData(date = null) {
@@ -384,9 +523,9 @@ function renderPage(data) {
             thresholdControls.style.display = 'none';
 
             try {
-                let url = '/prices';
+                let url = 'prices?region=' + currentRegion + '&unit=' + currentUnit;
                 if (date) {
-                    url = '/prices/' + date.getFullYear() + '/' + (date.getMonth() + 1) + '/' + date.getDate() + '/' + currentRegion;
+                    url = 'prices/' + date.getFullYear() + '/' + (date.getMonth() + 1) + '/' + date.getDate() + '/' + currentRegion + '?unit=' + currentUnit;
                 }
 
                 const response = await fetch(url, {
@@ -414,6 +553,7 @@ function renderPage(data) {
 
                 chartData = priceData;
                 setTimeout(() => graphPrice(chartData, date), 100); // Allow DOM to update
+                showBanner(date);
 
             } catch (err) {
                 loading.style.display = 'none';
@@ -423,29 +563,66 @@ function renderPage(data) {
         }
 
         function displayData(priceData, date = null) {
-            const prices = priceData.map(item => item.price);
-            const avgPrice = prices.reduce((a, b) => a + b, 0) / prices.length;
-            const maxPrice = Math.max(...prices);
-            const minPrice = Math.min(...prices);
-
             const displayDate = date || new Date();
             const dateStr = displayDate.getDate().toString().padStart(2, '0') + '-' +
                         (displayDate.getMonth() + 1).toString().padStart(2, '0') + '-' +
                         displayDate.getFullYear();
-            const headerTitle = 'Strømpriser (øre/kWh) den ' + dateStr + ' i';
+            const headerTitle = 'Strømpriser (' + unitLabel + ') den ' + dateStr + ' i';
             document.getElementById('headerTitle').textContent = headerTitle;
 
-            const statisticsElement = document.getElementById('statistics');
-            statisticsElement.innerHTML =
-                '<span>Min.: ' + minPrice.toFixed(1) + '</span>' +
-                '<span>Gjn.: ' + avgPrice.toFixed(1) + '</span>' +
-                '<span>Maks: ' + maxPrice.toFixed(1) + '</span>';
+            showStatistics(priceData, displayDate);
 
             // Update date navigation
             document.getElementById('currentDate').textContent = dateStr;
             updateNavigationButtons();
         }
 
+        // The chosen statistics from the API; min./avg./max. of the day where
+        // there is no API, as on the generated static site
+        async function showStatistics(priceData, date) {
+            const day = date.getFullYear() + '-' +
+                (date.getMonth() + 1).toString().padStart(2, '0') + '-' +
+                date.getDate().toString().padStart(2, '0');
+            let stats;
+            try {
+                const response = await fetch('api/v1/day-stats?region=' + currentRegion +
+                    '&date=' + day + '&unit=' + currentUnit + '&stats=' + statistics);
+                if (!response.ok) throw new Error(response.statusText);
+                stats = (await response.json()).stats;
+            } catch (err) {
+                const prices = priceData.map(item => item.price);
+                stats = [
+                    { label: 'Min.', value: Math.min(...prices) },
+                    { label: 'Gjn.', value: prices.reduce((a, b) => a + b, 0) / prices.length },
+                    { label: 'Maks', value: Math.max(...prices) },
+                ];
+            }
+            document.getElementById('statistics').innerHTML = stats.map((item) =>
+                '<span>' + item.label + ': ' + item.value.toFixed(1) +
+                (item.unit ? ' ' + item.unit : '') + '</span>').join('');
+        }
+
+        // How tomorrow compares with today, once its prices are published
+        async function showBanner(date) {
+            const banner = document.getElementById('banner');
+            banner.style.display = 'none';
+            if (date && date.toDateString() !== new Date().toDateString()) return;
+            try {
+                const response = await fetch('api/v1/tomorrow-delta?region=' + currentRegion);
+                if (!response.ok) return;
+                const delta = await response.json();
+                const percent = delta.change_percent;
+                const change = percent === null || Math.abs(percent) < 0.5
+                    ? 'omtrent som i dag'
+                    : Math.abs(percent).toFixed(0) + ' % ' + (percent < 0 ? 'lavere' : 'høyere') + ' enn i dag';
+                banner.textContent = 'I morgen: snittet er ' + change + ', ' +
+                    delta.cheaper_hours + ' timer billigere og ' + delta.dearer_hours + ' dyrere.';
+                banner.style.display = 'block';
+            } catch (err) {
+                // No banner without the API, as on the generated static site
+            }
+        }
+
         function updateNavigationButtons() {
             const prevButton = document.getElementById('prevButton');
             const nextButton = document.getElementById('nextButton');
@@ -488,19 +665,20 @@ function renderPage(data) {
             document.getElementById('nextButton').addEventListener('click', () => navigateDate(1));
 
             // Add event listeners for threshold checkboxes
-            document.getElementById('threshold0').addEventListener('change', updateThresholdStates);
-            document.getElementById('threshold50').addEventListener('change', updateThresholdStates);
-            document.getElementById('threshold75').addEventListener('change', updateThresholdStates);
+            thresholds.forEach((threshold, i) => {
+                document.getElementById('threshold' + i).addEventListener('change', updateThresholdStates);
+            });
 
             // Add event listener for region dropdown
             document.getElementById('regionDropdown').addEventListener('change', updateRegion);
 
-            // Set default checkbox states
-            document.getElementById('threshold0').checked = true;
-            document.getElementById('threshold50').checked = true;
-            document.getElementById('threshold75').checked = true;
+            // The server remembers the unit in a cookie
+            document.getElementById('unitDropdown').addEventListener('change', (event) => {
+                window.location.search = '?unit=' + event.target.value;
+            });
 
-            loadData();
+            loadData(${date ? "currentDate" : ""});
+            subscribe();
         });
 
         // Debounced resize handler for better performance
@@ -517,6 +695,67 @@ function renderPage(data) {
 `;
 }
 
+// A day's prices with their percentiles within the day and the 30 days up to
+// and including it, and their trends from the hour before and from yesterday
+async function priceDay(date, region) {
+  const chart = await getPrices(date, region);
+  const from = new Date(date);
+  from.setDate(from.getDate() - 29);
+  const yesterday = new Date(date);
+  yesterday.setDate(yesterday.getDate() - 1);
+  return withTrends(
+    withPercentiles(chart, loadPrices(region, from, date)),
+    loadPrices(region, yesterday, yesterday),
+  );
+}
+
+// The page for `region` in the unit and with the statistics of the request,
+// remembering an explicitly chosen `?unit=`, `?stats=` and `?profile=` (an
+// empty one forgets it) in cookies, and showing `?date=` rather than today
+// if given. The day's prices, if they can be had, go into its structured
+// data
+async function page(request, query, region) {
+  const unit = preferredUnit(request, query);
+  const statistics = preferredStatistics(request, query);
+  const profile = preferredProfile(request, query);
+  const headers = new Headers({ "content-type": "text/html; charset=utf-8" });
+  if (query.unit === unit) headers.append("set-cookie", unitCookie(unit));
+  if (query.stats === statistics.join(",")) {
+    headers.append("set-cookie", statisticsCookie(statistics));
+  }
+  if (query.profile === "") headers.append("set-cookie", profileCookie(null));
+  if (profile && query.profile === profile.name) {
+    headers.append("set-cookie", profileCookie(profile.name));
+  }
+  const settings = preferredChart(request);
+  const date = parseDate(query.date);
+  const day = date || new Date();
+  let stats = null;
+  try {
+    stats = summarize((await getPrices(day, region)).map((item) => item.price));
+  } catch (error) {
+    // Described without the figures
+  }
+  const dataset = dayDataset({
+    region,
+    date: isoDate(day),
+    url: request.url,
+    stats,
+  });
+  return new Response(
+    renderPage({
+      region,
+      unit,
+      statistics,
+      settings,
+      date,
+      url: request.url,
+      dataset,
+    }),
+    { headers },
+  );
+}
+
 const elektron = new Elysia()
   .use(
     staticPlugin({
@@ -525,7 +764,157 @@ const elektron = new Elysia()
     }),
   )
   .use(html())
-  .get("/", () => renderPage({}))
+  // Pages served through Home Assistant ingress, under a path of its own
+  .onAfterHandle(({ request, response }) => withBase(request, response))
+  // Unknown routes, invalid requests and uncaught errors
+  .onError(({ code, error, request }) => {
+    if (code === "NOT_FOUND") {
+      return errorResponse(request, 404, "Hmm. Her var det ingenting.");
+    }
+    if (code === "VALIDATION" || code === "PARSE") {
+      return errorResponse(request, 400, error.message);
+    }
+    console.error(`[${requestId(request)}]`, error);
+    return errorResponse(request, 500, "Noe gikk galt. :-(");
+  })
+  .use(site)
+  .use(events)
+  .use(widget)
+  .use(exporter)
+  .use(api)
+  .use(auth)
+  .use(admin)
+  .use(locationRoutes)
+  .use(profileRoutes)
+  .use(report)
+  .use(integrations)
+  .use(itemRoutes)
+  .use(mcp)
+  .get("/", ({ request, query }) =>
+    page(
+      request,
+      query,
+      defaultRegion(request, {}, preferredProfile(request, query)),
+    ),
+  )
+  .get("/stats", ({ query, request }) => {
+    const region = defaultRegion(request, query);
+    const period = parsePeriod(query);
+    if (period.message) {
+      return Response.json({ message: period.message }, { status: 400 });
+    }
+    const hours = parseHourRange(query);
+    if (hours?.message) {
+      return Response.json({ message: hours.message }, { status: 400 });
+    }
+
+    return renderSeasonality(
+      region,
+      seasonality(
+        inHourRange(loadPrices(region, period.from, period.to), hours),
+      ),
+    );
+  })
+  // Full-screen page for wall-mounted tablets, laid out with `?hours=` (the
+  // cheapest window), `?chart=0`, `?theme=dark`, `?scale=` and `?qr=1`
+  .get("/kiosk", async ({ query, request }) => {
+    const region = defaultRegion(request, query);
+    const hours = query.hours ? parseFloat(query.hours) : 3;
+    if (!(hours > 0 && hours <= 24)) {
+      return Response.json(
+        { message: "hours must be between 0 and 24" },
+        { status: 400 },
+      );
+    }
+    const theme = query.theme || "light";
+    if (!["light", "dark"].includes(theme)) {
+      return Response.json(
+        { message: "theme must be light or dark" },
+        { status: 400 },
+      );
+    }
+    const scale = query.scale ? parseFloat(query.scale) : 1;
+    if (!(scale >= 0.5 && scale <= 3)) {
+      return Response.json(
+        { message: "scale must be between 0.5 and 3" },
+        { status: 400 },
+      );
+    }
+
+    const now = new Date();
+    let horizon = [];
+    try {
+      horizon = await getHorizon(region, now);
+    } catch (error) {
+      // Shown as no data, and retried on the next tick
+    }
+    const current = currentEntry(horizon, now);
+    const today = horizon
+      .filter((item) => item.time.slice(0, 10) === current?.time.slice(0, 10))
+      .map((item) => item.price);
+    return renderKiosk({
+      region,
+      current,
+      next: current && horizon[horizon.indexOf(current) + 1],
+      level: current && priceLevel(current.price, today),
+      window: cheapestWindow(horizon, hours, now),
+      hours,
+      chart: query.chart !== "0",
+      theme,
+      scale,
+      qr: query.qr === "1",
+    });
+  })
+  // Grayscale page for 7.5" e-paper displays, turned by `?rotate=` degrees
+  .get("/eink", async ({ query, request }) => {
+    const region = defaultRegion(request, query);
+    const rotate = query.rotate ? parseInt(query.rotate) : 0;
+    if (!EINK_ROTATIONS.includes(rotate)) {
+      return Response.json(
+        { message: `rotate must be one of: ${EINK_ROTATIONS.join(", ")}` },
+        { status: 400 },
+      );
+    }
+    const hours = query.hours ? parseFloat(query.hours) : 3;
+    if (!(hours > 0 && hours <= 24)) {
+      return Response.json(
+        { message: "hours must be between 0 and 24" },
+        { status: 400 },
+      );
+    }
+
+    const now = new Date();
+    let horizon = [];
+    try {
+      horizon = await getHorizon(region, now);
+    } catch (error) {
+      // Shown as no data until the next refresh
+    }
+    const current = currentEntry(horizon, now);
+    const today = horizon.filter(
+      (item) => item.time.slice(0, 10) === current?.time.slice(0, 10),
+    );
+    const next = current && horizon[horizon.indexOf(current) + 1];
+    const refresh = next
+      ? Math.max(60, Math.ceil((new Date(next.time) - now) / 1000))
+      : 900;
+
+    return renderEink({
+      region,
+      current,
+      stats: summarize(today.map((item) => item.price)),
+      window: cheapestWindow(horizon, hours, now),
+      hours,
+      svg: renderSvg(today, {
+        width: EINK.width - 40,
+        height: 280,
+        annotations: ["now", "extremes"],
+        now,
+      }),
+      refresh,
+      rotate,
+    });
+  })
   .get("/fonts/:filename", async ({ params }) => {
     try {
       const { filename } = params;
@@ -562,29 +951,29 @@ const elektron = new Elysia()
       return new Response("Font not found", { status: 404 });
     }
   })
-  .get("/prices", async () => {
+  .get("/prices", async ({ query, request }) => {
+    const hours = parseHourRange(query);
+    if (hours?.message) {
+      return Response.json({ message: hours.message }, { status: 400 });
+    }
+    const currency = parseCurrency(query);
+    if (currency?.message) {
+      return Response.json({ message: currency.message }, { status: 400 });
+    }
+
     try {
-      const now = new Date();
-      const data = await fetchPrices(
-        now.getFullYear(),
-        now.getMonth() + 1,
-        now.getDate(),
-        "NO2",
+      let chart = inUnit(
+        await priceDay(new Date(), defaultRegion(request, query)),
+        preferredUnit(request, query),
       );
+      if (currency) chart = inCurrency(chart, currency);
 
-      const chart = data.map((item) => {
-        // Extract hour from UTC time string without timezone conversion
-        const hour = parseInt(item.time_start.split("T")[1].split(":")[0]);
-        return {
-          hour,
-          price: item.NOK_per_kWh * 100.0,
-          time: item.time_start,
-          price_nok: item.NOK_per_kWh,
-          price_eur: item.EUR_per_kWh,
-        };
-      });
+      const projected = project(inHourRange(chart, hours), query);
+      if (projected.message) {
+        return Response.json({ message: projected.message }, { status: 400 });
+      }
 
-      return Response.json(chart);
+      return negotiate(request, projected.data);
     } catch (error) {
       return Response.json(
         { message: "Finner ikke noe data. :-(" },
@@ -592,77 +981,202 @@ const elektron = new Elysia()
       );
     }
   })
-  .get("/prices/:year/:month/:day/:region", async ({ params }) => {
-    try {
-      const { year, month, day, region } = params;
-
-      // Validation
-      const yearNum = parseInt(year);
-      const monthNum = parseInt(month);
-      const dayNum = parseInt(day);
-
-      // Check for NaN values (invalid numeric inputs)
-      if (isNaN(yearNum) || isNaN(monthNum) || isNaN(dayNum)) {
-        return Response.json(
-          { message: "Year, month, and day must be valid numbers" },
-          { status: 400 },
-        );
-      }
+  .get(
+    "/prices/:year/:month/:day/:region",
+    async ({ params, query, request }) => {
+      try {
+        const { year, month, day, region } = params;
+
+        const parsed = parseDay(year, month, day, region);
+        if (parsed.message) {
+          return Response.json({ message: parsed.message }, { status: 400 });
+        }
+        const hours = parseHourRange(query);
+        if (hours?.message) {
+          return Response.json({ message: hours.message }, { status: 400 });
+        }
+        const currency = parseCurrency(query);
+        if (currency?.message) {
+          return Response.json({ message: currency.message }, { status: 400 });
+        }
 
-      if (yearNum < 2020 || yearNum > 2030) {
-        return Response.json(
-          { message: "Year must be between 2020 and 2030" },
-          { status: 400 },
-        );
-      }
-      if (monthNum < 1 || monthNum > 12) {
-        return Response.json(
-          { message: "Month must be between 1 and 12" },
-          { status: 400 },
+        let chart = inUnit(
+          await priceDay(parsed.date, region),
+          preferredUnit(request, query),
         );
+        if (currency) chart = inCurrency(chart, currency);
+
+        const projected = project(inHourRange(chart, hours), query);
+        if (projected.message) {
+          return Response.json({ message: projected.message }, { status: 400 });
+        }
+
+        return negotiate(request, projected.data);
+      } catch (error) {
+        return Response.json({ message: "Noe gikk galt." }, { status: 500 });
       }
+    },
+  )
+  .get(
+    "/chart/:year/:month/:day/:region",
+    async ({ params, query, request }) => {
+      try {
+        const { year, month, day } = params;
+        const region = params.region.replace(/\.svg$/, "");
+
+        const parsed = parseDay(year, month, day, region);
+        if (parsed.message) {
+          return Response.json({ message: parsed.message }, { status: 400 });
+        }
+        if (query.overlay && !Object.hasOwn(OVERLAYS, query.overlay)) {
+          return Response.json(
+            {
+              message: `overlay must be one of: ${Object.keys(OVERLAYS).join(", ")}`,
+            },
+            { status: 400 },
+          );
+        }
+        // e.g. ?annotate=extremes,now,stats
+        const annotations = parseAnnotations(query.annotate || "");
+        if (annotations.message) {
+          return Response.json(annotations, { status: 400 });
+        }
+
+        const unit = preferredUnit(request, query);
+        const chart = inUnit(await getPrices(parsed.date, region), unit);
+        const statistics = annotations.includes("stats")
+          ? dayStatistics(chart, preferredStatistics(request, query))
+          : [];
+
+        // A failing overlay source shouldn't take the chart down with it
+        let overlay = null;
+        if (query.overlay) {
+          overlay = await overlayFor(
+            query.overlay,
+            parsed.date,
+            region,
+            chart,
+          ).catch(() => null);
+        }
+
+        // The øre thresholds are meaningless in EUR/MWh
+        const settings = settingsForUnit(preferredChart(request), unit);
 
-      // Validate day for the given month and year
-      const daysInMonth = new Date(yearNum, monthNum, 0).getDate();
-      if (dayNum < 1 || dayNum > daysInMonth) {
-        return Response.json(
-          {
-            message: `Day must be between 1 and ${daysInMonth} for month ${monthNum}`,
-          },
-          { status: 400 },
+        return new Response(
+          renderSvg(chart, { overlay, annotations, statistics, ...settings }),
+          { headers: { "content-type": "image/svg+xml; charset=utf-8" } },
         );
+      } catch (error) {
+        return Response.json({ message: "Noe gikk galt." }, { status: 500 });
       }
+    },
+  )
+  .get("/feed/:region", async ({ params, request }) => {
+    const region = params.region.replace(/\.xml$/, "");
+    if (!REGIONS.includes(region)) {
+      return Response.json(
+        { message: "Region must be NO1-NO5" },
+        { status: 400 },
+      );
+    }
 
-      if (!["NO1", "NO2", "NO3", "NO4", "NO5"].includes(region)) {
-        return Response.json(
-          { message: "Region must be NO1-NO5" },
-          { status: 400 },
-        );
+    // Today, and tomorrow once the day-ahead prices are published
+    const days = [];
+    for (const offset of [0, 1]) {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      try {
+        days.push({ date, chart: await getPrices(date, region) });
+      } catch (error) {
+        // Not published yet
       }
+    }
 
-      const data = await fetchPrices(yearNum, monthNum, dayNum, region);
-
-      const chart = data.map((item) => {
-        // Extract hour from UTC time string without timezone conversion
-        const hour = parseInt(item.time_start.split("T")[1].split(":")[0]);
-        return {
-          hour,
-          price: item.NOK_per_kWh * 100.0,
-          time: item.time_start,
-          price_nok: item.NOK_per_kWh,
-          price_eur: item.EUR_per_kWh,
-        };
-      });
+    return new Response(renderFeed(region, days, new URL(request.url).origin), {
+      headers: { "content-type": "application/atom+xml; charset=utf-8" },
+    });
+  })
+  // Today's (or `?day=tomorrow`'s) hours for ESPHome and other small devices,
+  // as exactly 24 bytes: byte N is the level of hour N among the day's hours,
+  // 0 very cheap, 1 cheap, 2 normal, 3 expensive and 4 very expensive, or 255
+  // when the hour has no price (the skipped hour of the spring DST change)
+  .get("/schedule.bin", async ({ query, request }) => {
+    const region = defaultRegion(request, query);
+    const date = new Date();
+    if (query.day === "tomorrow") {
+      date.setDate(date.getDate() + 1);
+    } else if (query.day && query.day !== "today") {
+      return Response.json(
+        { message: "day must be today or tomorrow" },
+        { status: 400 },
+      );
+    }
 
-      return Response.json(chart);
+    let hours;
+    try {
+      hours = hourly(await getPrices(date, region));
     } catch (error) {
-      return Response.json({ message: "Noe gikk galt." }, { status: 500 });
+      return Response.json(
+        { message: "Finner ikke noe data. :-(" },
+        { status: 404 },
+      );
+    }
+    const prices = hours.map((item) => item.price);
+    const schedule = new Uint8Array(24).fill(255);
+    for (const item of hours) {
+      schedule[item.hour] = LEVELS.indexOf(priceLevel(item.price, prices));
     }
+    return new Response(schedule, {
+      headers: { "content-type": "application/octet-stream" },
+    });
   });
 
+// The page with a zone pre-selected, e.g. `/no4` or a configured `/hytta`.
+// Static routes so they don't shadow the public files; new paths in
+// `sites.paths` need a restart
+for (const path of sitePaths()) {
+  elektron.get(`/${path}`, ({ request, query }) =>
+    page(request, query, pathRegion(path)),
+  );
+}
+
 export default elektron;
 
+// Listen and start the background tasks
+export function serve(port = null) {
+  if (config.stateless && !config.redis?.url) {
+    throw new Error("Stateless mode needs redis.url for the shared state");
+  }
+  const servers = listen(
+    withLimits(catchErrors((request) => elektron.handle(request))),
+    port,
+  );
+  // Log rather than crash on a promise nobody awaits, e.g. in a job
+  process.on("unhandledRejection", (error) => {
+    console.error("Unhandled rejection:", error);
+  });
+  startJobs();
+  // Not restarted on reload, so changing its settings requires a restart
+  if (config.modbus) {
+    const modbus = startModbus();
+    console.log(`modbus://${modbus.hostname}:${modbus.port}`);
+  }
+  process.on("SIGHUP", () => {
+    try {
+      reload();
+      console.log("Reloaded configuration");
+    } catch (error) {
+      console.error(`Reload failed: ${error.message}`);
+    }
+  });
+  for (const server of servers) {
+    const host = server.hostname.includes(":")
+      ? `[${server.hostname}]`
+      : server.hostname;
+    console.log(`${server.protocol}://${host}:${server.port}`);
+  }
+}
+
 if (import.meta.main) {
-  elektron.listen(3000);
-  console.log(`http://${elektron.server?.hostname}:${elektron.server?.port}`);
+  serve();
 }
@@ -0,0 +1,99 @@
+#!/usr/bin/env bun
+// Benchmarks of the hot paths on synthetic prices:
+//
+//   bun run bench/bench.js [--filter name] [--save file]
+//   bun run bench/bench.js --compare file [--tolerance 0.25]
+//
+// `--compare` exits with 1 if a benchmark got more than `--tolerance`
+// (relative) slower than in the saved results
+import { parseArgs } from "util";
+
+// Before the config is loaded, so nothing is fetched or stored for real
+process.env.ELEKTRON_SYNTHETIC = "1";
+
+const { values } = parseArgs({
+  options: {
+    filter: { type: "string" },
+    save: { type: "string" },
+    compare: { type: "string" },
+    tolerance: { type: "string", default: "0.25" },
+  },
+});
+
+const { renderSvg } = await import("../lib/chart.js");
+const { negotiate } = await import("../lib/format.js");
+const { getPrices } = await import("../lib/prices.js");
+const { cheapestWindow, seasonality, summarize, withPercentiles } =
+  await import("../lib/stats.js");
+const { default: elektron } = await import("../app.js");
+
+const date = new Date(2025, 0, 15);
+const day = await getPrices(date, "NO2");
+const year = [];
+for (let offset = 0; offset < 365; offset++) {
+  const past = new Date(date);
+  past.setDate(past.getDate() - offset);
+  year.unshift(...(await getPrices(past, "NO2")));
+}
+const request = (path, accept = "application/json") =>
+  new Request(`http://localhost${path}`, { headers: { accept } });
+
+const BENCHMARKS = {
+  "json/day": () => negotiate(request("/prices"), day).text(),
+  "json/year": () => negotiate(request("/prices"), year).text(),
+  "msgpack/year": () =>
+    negotiate(request("/prices", "application/msgpack"), year).arrayBuffer(),
+  "stats/summarize": () => summarize(year.map((item) => item.price)),
+  "stats/cheapest-window": () => cheapestWindow(year, 3),
+  "stats/percentiles": () => withPercentiles(day, year.slice(-30 * 24)),
+  "stats/seasonality": () => seasonality(year),
+  "chart/svg": () => renderSvg(day),
+  "http/prices": () => elektron.handle(request("/prices?region=NO2")),
+  "http/chart": () =>
+    elektron.handle(request("/chart/2025/1/15/NO2.svg", "image/svg+xml")),
+};
+
+// Mean time per run (µs), over runs adding up to about half a second after
+// a warm-up
+async function measure(run) {
+  for (let i = 0; i < 20; i++) await run();
+  let runs = 0;
+  const started = Bun.nanoseconds();
+  while (Bun.nanoseconds() - started < 5e8) {
+    await run();
+    runs++;
+  }
+  return (Bun.nanoseconds() - started) / runs / 1000;
+}
+
+const baseline = values.compare
+  ? JSON.parse(await Bun.file(values.compare).text())
+  : {};
+const tolerance = parseFloat(values.tolerance);
+const results = {};
+const regressions = [];
+
+for (const [name, run] of Object.entries(BENCHMARKS)) {
+  if (values.filter && !name.includes(values.filter)) continue;
+  const micros = await measure(run);
+  results[name] = Math.round(micros * 10) / 10;
+
+  let line = `${name.padEnd(24)}${micros.toFixed(1).padStart(12)} µs`;
+  if (baseline[name]) {
+    const change = micros / baseline[name] - 1;
+    line += `  ${change >= 0 ? "+" : ""}${(change * 100).toFixed(0)} %`;
+    if (change > tolerance) {
+      regressions.push(name);
+      line += "  REGRESSION";
+    }
+  }
+  console.log(line);
+}
+
+if (values.save) {
+  await Bun.write(values.save, JSON.stringify(results, null, 2) + "\n");
+}
+if (regressions.length > 0) {
+  console.error(`Slower than the baseline: ${regressions.join(", ")}`);
+  process.exit(1);
+}
@@ -0,0 +1,136 @@
+#!/usr/bin/env bun
+import { parseArgs } from "util";
+import { reloadConfig } from "./lib/config.js";
+import { generate } from "./lib/generate.js";
+import { tui } from "./lib/tui.js";
+
+const [command = "serve", ...args] = process.argv.slice(2);
+
+switch (command) {
+  case "serve": {
+    const { values } = parseArgs({
+      args,
+      options: { synthetic: { type: "boolean", default: false } },
+    });
+    if (values.synthetic) {
+      // Kept across reloads
+      process.env.ELEKTRON_SYNTHETIC = "1";
+      reloadConfig();
+    }
+    const { serve } = await import("./app.js");
+    serve();
+    break;
+  }
+  case "tui":
+    await tui(args[0]);
+    break;
+  case "generate": {
+    const { values } = parseArgs({
+      args,
+      options: {
+        out: { type: "string", default: "./site" },
+        "base-url": { type: "string", default: "http://localhost" },
+      },
+    });
+    const { default: elektron } = await import("./app.js");
+    await generate(elektron, { out: values.out, baseUrl: values["base-url"] });
+    break;
+  }
+  case "backup": {
+    const { values } = parseArgs({
+      args,
+      options: {
+        out: { type: "string", default: "elektron-backup.tar.zst" },
+      },
+    });
+    const { backupFormat, createBackup } = await import("./lib/backup.js");
+    await Bun.write(values.out, await createBackup(backupFormat(values.out)));
+    console.log(`Backed up to ${values.out}`);
+    break;
+  }
+  case "restore": {
+    const { values, positionals } = parseArgs({
+      args,
+      options: { force: { type: "boolean", default: false } },
+      allowPositionals: true,
+    });
+    if (!positionals[0]) {
+      console.error("Usage: elektron restore <file> [--force]");
+      process.exit(1);
+    }
+    const { restoreBackup } = await import("./lib/backup.js");
+    try {
+      const file = Bun.file(positionals[0]);
+      const restored = await restoreBackup(
+        new Uint8Array(await file.arrayBuffer()),
+        { force: values.force },
+      );
+      console.log(`Restored ${restored.join(", ")}`);
+    } catch (error) {
+      console.error(error.message);
+      process.exit(1);
+    }
+    break;
+  }
+  case "import": {
+    const { values, positionals } = parseArgs({
+      args,
+      options: {
+        region: { type: "string" },
+        location: { type: "string" },
+        "eur-nok": { type: "string" },
+      },
+      allowPositionals: true,
+    });
+    const [format, path] = positionals;
+    if (!format || !path) {
+      console.error(
+        "Usage: elektron import <tibber | elhub | entsoe> <file> " +
+          "[--location name] [--region NO1 [--eur-nok rate]]",
+      );
+      process.exit(1);
+    }
+    const { importFile } = await import("./lib/importers.js");
+    try {
+      const result = importFile(format, await Bun.file(path).text(), {
+        region: values.region,
+        location: values.location,
+        eur_nok: values["eur-nok"] && parseFloat(values["eur-nok"]),
+      });
+      console.log(
+        `Imported ${result.rows} ${result.kind} rows ` +
+          `from ${result.from} to ${result.to}`,
+      );
+    } catch (error) {
+      console.error(error.message);
+      process.exit(1);
+    }
+    break;
+  }
+  case "check": {
+    const { runChecks } = await import("./lib/check.js");
+    const results = await runChecks();
+    for (const { name, ok, detail } of results) {
+      console.log(`${ok ? "✓" : "✗"} ${name}: ${detail}`);
+    }
+    const failed = results.filter((result) => !result.ok).length;
+    console.log(failed > 0 ? `${failed} check(s) failed` : "All good");
+    if (failed > 0) process.exit(1);
+    break;
+  }
+  case "mcp": {
+    // Model Context Protocol over stdio for local AI assistants
+    const { serveStdio } = await import("./lib/mcp.js");
+    await serveStdio();
+    break;
+  }
+  default:
+    console.error(`Unknown command: ${command}`);
+    console.error(
+      "Usage: elektron [serve [--synthetic] | tui [region] | " +
+        "generate [--out dir] [--base-url url] | " +
+        "backup [--out file] | restore <file> [--force] | " +
+        "import <format> <file> | check | mcp]",
+    );
+    process.exit(1);
+}
@@ -0,0 +1,79 @@
+import { config } from "./config.js";
+import { formatDate, formatHour, formatOre } from "./locale.js";
+import { notify } from "./notify.js";
+import { isoDate, loadPrices, onStored } from "./prices.js";
+import { average, standardDeviation } from "./stats.js";
+
+// Fewer samples than this per hour of day make the deviation meaningless
+const MIN_SAMPLES = 7;
+
+export function anomalySettings(overrides = {}) {
+  return {
+    threshold: 3,
+    window: 30,
+    notify: false,
+    ...config.anomalies,
+    ...overrides,
+  };
+}
+
+// Intervals of `chart` deviating more than `threshold` standard deviations from
+// the same hour of day over the `window` days before `date`
+export function detectAnomalies(region, date, chart, { threshold, window }) {
+  const to = new Date(date);
+  to.setDate(to.getDate() - 1);
+  const from = new Date(date);
+  from.setDate(from.getDate() - window);
+
+  const byHour = new Map();
+  for (const item of loadPrices(region, from, to)) {
+    if (!byHour.has(item.hour)) byHour.set(item.hour, []);
+    byHour.get(item.hour).push(item.price);
+  }
+
+  const anomalies = [];
+  for (const item of chart) {
+    const history = byHour.get(item.hour) || [];
+    if (history.length < MIN_SAMPLES) continue;
+
+    const mean = average(history);
+    const deviation = standardDeviation(history);
+    if (deviation === 0) continue;
+
+    const score = (item.price - mean) / deviation;
+    if (Math.abs(score) > threshold) {
+      anomalies.push({
+        time: item.time,
+        hour: item.hour,
+        price: item.price,
+        mean,
+        deviation,
+        score,
+      });
+    }
+  }
+  return anomalies;
+}
+
+// Announce spikes in newly stored days, if `anomalies.notify` is enabled
+onStored(async (region, chart) => {
+  const settings = anomalySettings();
+  if (!settings.notify || chart.length === 0) return;
+
+  const date = new Date(chart[0].time.slice(0, 10) + "T00:00");
+  const anomalies = detectAnomalies(region, date, chart, settings);
+  if (anomalies.length === 0) return;
+
+  const hours = anomalies
+    .map(
+      (item) =>
+        `${formatHour(item.hour)} (${formatOre(item.price)} øre)`,
+    )
+    .join(", ");
+  await notify({
+    event: "anomaly",
+    title: `Uvanlige priser i ${region}`,
+    message: `Uvanlige priser i ${region} den ${formatDate(isoDate(date))}: ${hours}`,
+    data: { region, date: isoDate(date), anomalies },
+  });
+});
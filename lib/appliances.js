@@ -0,0 +1,32 @@
+import { config } from "./config.js";
+import { cheapestWindow, windowAt } from "./stats.js";
+
+// Configured appliances by name, e.g. `{ "dryer": { "kwh": 2.5 } }`: energy
+// per run (kWh) and how long a run takes (`hours`, defaulting to the task of
+// the same name, or one hour), assumed to be drawn evenly
+export function appliances() {
+  return Object.fromEntries(
+    Object.entries(config.appliances || {}).map(([name, appliance]) => [
+      name,
+      { hours: config.tasks[name] ?? 1, ...appliance },
+    ]),
+  );
+}
+
+// What a run is, for `appliance`: `{ start, end, average, cost }` with the
+// average price (øre/kWh) and the cost (NOK)
+function run(window, appliance) {
+  return window && { ...window, cost: (window.average * appliance.kwh) / 100 };
+}
+
+// A run of `appliance` starting at `start` against the cheapest one still
+// possible at `now`, both within `chart` (with `price` the price to pay);
+// `{ start, cheapest, savings }` or null if neither fits before the prices
+// run out
+export function applianceCost(chart, appliance, start, now = new Date()) {
+  const { hours } = appliance;
+  const chosen = run(windowAt(chart, hours, start), appliance);
+  const cheapest = run(cheapestWindow(chart, hours, now), appliance);
+  if (!chosen || !cheapest) return null;
+  return { start: chosen, cheapest, savings: chosen.cost - cheapest.cost };
+}
@@ -0,0 +1,60 @@
+import { config } from "./config.js";
+import { currentEntry, getHorizon, getPrices } from "./prices.js";
+import { cheapestWindow, summarize } from "./stats.js";
+
+const clock = (time) => `klokka ${time.slice(11, 13)}`;
+const ore = (price) => `${Math.round(price)} øre`;
+
+// Spoken-friendly answers, by intent; each gets `{ region, hours }` and now
+export const INTENTS = {
+  // "Strømmen koster nå 87 øre, billigst klokka 03"
+  current: async ({ region }, now) => {
+    const chart = await getPrices(now, region);
+    const current = currentEntry(chart, now);
+    if (!current) return "Jeg finner ingen strømpris for nå.";
+    const cheapest = chart
+      .filter((item) => item.time >= current.time)
+      .reduce((a, b) => (b.price < a.price ? b : a));
+    return cheapest === current
+      ? `Strømmen koster nå ${ore(current.price)}, og det er det billigste resten av dagen.`
+      : `Strømmen koster nå ${ore(current.price)}, billigst ${clock(cheapest.time)} med ${ore(cheapest.price)}.`;
+  },
+  // When to start something running `hours` hours
+  cheapest: async ({ region, hours }, now) => {
+    const window = cheapestWindow(await getHorizon(region, now), hours, now);
+    if (!window) return "Jeg finner ingen priser fremover.";
+    const start = Date.parse(window.start) <= now ? "nå" : clock(window.start);
+    return `Billigst er det å starte ${start}, med snittpris ${ore(window.average)} de neste ${hours} timene.`;
+  },
+  tomorrow: async ({ region }, now) => {
+    const date = new Date(now);
+    date.setDate(date.getDate() + 1);
+    let chart;
+    try {
+      chart = await getPrices(date, region);
+    } catch (error) {
+      return (
+        "Morgendagens priser er ikke klare ennå. " +
+        "De kommer vanligvis rundt klokka 13."
+      );
+    }
+    const { avg } = summarize(chart.map((item) => item.price));
+    const cheapest = chart.reduce((a, b) => (b.price < a.price ? b : a));
+    const dearest = chart.reduce((a, b) => (b.price > a.price ? b : a));
+    return `I morgen koster strømmen i snitt ${ore(avg)}, billigst ${clock(cheapest.time)} og dyrest ${clock(dearest.time)}.`;
+  },
+};
+
+// The answer to `intent` (default current) for a zone; hours default to the
+// duration of `task` (from `tasks`) or three
+export async function answer(
+  intent,
+  { region, hours, task },
+  now = new Date(),
+) {
+  const handler = INTENTS[intent || "current"];
+  if (!handler) {
+    throw new Error(`intent must be one of: ${Object.keys(INTENTS).join(", ")}`);
+  }
+  return handler({ region, hours: hours || config.tasks[task] || 3 }, now);
+}
@@ -0,0 +1,44 @@
+import { db } from "./db.js";
+
+// Record something the automation did: `source` (e.g. `rule:varmtvann`,
+// `charger` or `water_heater`), the `action` taken, the `reason` it was
+// taken and its `result` (`ok`, `dry_run` or `error`, with the `error`)
+export function recordAction({
+  source,
+  action,
+  reason = null,
+  result = "ok",
+  error = null,
+}) {
+  db()
+    .query(
+      `INSERT INTO audit_log (time, source, action, reason, result, error)
+       VALUES (?, ?, ?, ?, ?, ?)`,
+    )
+    .run(new Date().toISOString(), source, action, reason, result, error);
+}
+
+// Run `act`, recording the action with its outcome; errors are rethrown
+export async function audited(entry, act) {
+  try {
+    const result = await act();
+    recordAction({ ...entry, result: result === "dry_run" ? result : "ok" });
+    return result;
+  } catch (error) {
+    recordAction({ ...entry, result: "error", error: error.message });
+    throw error;
+  }
+}
+
+// Recorded actions, newest first: at most `limit`, optionally only from
+// sources starting with `source` and taken at or after `since` (ISO time)
+export function auditLog({ limit = 100, source = null, since = null } = {}) {
+  return db()
+    .query(
+      `SELECT time, source, action, reason, result, error FROM audit_log
+       WHERE (?1 IS NULL OR source LIKE ?1 || '%')
+         AND (?2 IS NULL OR time >= ?2)
+       ORDER BY time DESC LIMIT ?3`,
+    )
+    .all(source, since, limit);
+}
@@ -0,0 +1,50 @@
+import { timingSafeEqual } from "crypto";
+import { config } from "./config.js";
+import { wantsHtml } from "./errors.js";
+import { oidcSettings, sessionUser } from "./oidc.js";
+import { basePath } from "./sites.js";
+
+// Whether the request carries the admin token, compared in constant time
+function bearer(request) {
+  if (!config.admin?.token) return false;
+  const expected = Buffer.from(`Bearer ${config.admin.token}`);
+  const given = Buffer.from(request.headers.get("authorization") || "");
+  return expected.length === given.length && timingSafeEqual(expected, given);
+}
+
+// Browsers are sent to the login, everyone else gets a 401
+function unauthorized(request) {
+  if (oidcSettings() && wantsHtml(request)) {
+    const url = new URL(request.url);
+    const next = encodeURIComponent(url.pathname + url.search);
+    return Response.redirect(
+      new URL(`${basePath(request)}auth/login?next=${next}`, url).href,
+      303,
+    );
+  }
+  return Response.json({ message: "Unauthorized" }, { status: 401 });
+}
+
+// `beforeHandle` guard requiring `Authorization: Bearer <admin.token>` or an
+// OpenID Connect login; everything behind it is disabled until either is
+// configured
+export function authorize({ request }) {
+  const oidc = oidcSettings();
+  if (!config.admin?.token && !oidc) {
+    return Response.json(
+      { message: "Admin is not configured (admin.token or oidc)" },
+      { status: 403 },
+    );
+  }
+  if (bearer(request) || (oidc && sessionUser(oidc, request))) return;
+  return unauthorized(request);
+}
+
+// `beforeHandle` guard for personal data (consumption and what it cost):
+// open until OpenID Connect is configured, then only for logged in users and
+// the admin token
+export function authenticate({ request }) {
+  const oidc = oidcSettings();
+  if (!oidc || bearer(request) || sessionUser(oidc, request)) return;
+  return unauthorized(request);
+}
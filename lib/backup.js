@@ -0,0 +1,138 @@
+import { existsSync } from "fs";
+import { mkdtemp, readFile, rm, writeFile } from "fs/promises";
+import { tmpdir } from "os";
+import { basename, join } from "path";
+import { gunzipSync, gzipSync } from "zlib";
+import { CONFIG_PATH, config } from "./config.js";
+import { db } from "./db.js";
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+// Names of the files inside a backup
+const DATABASE = "elektron.sqlite";
+const CONFIG = "elektron.json";
+
+// Minimal ustar archive of `{ name, data }` regular files
+function tar(files) {
+  const blocks = [];
+  for (const file of files) {
+    const header = new Uint8Array(512);
+    const field = (offset, length, value) =>
+      header.set(encoder.encode(value).slice(0, length), offset);
+    const octal = (value, length) =>
+      value.toString(8).padStart(length - 1, "0") + "\0";
+
+    field(0, 100, file.name);
+    field(100, 8, octal(0o644, 8));
+    field(108, 8, octal(0, 8));
+    field(116, 8, octal(0, 8));
+    field(124, 12, octal(file.data.length, 12));
+    field(136, 12, octal(Math.floor(Date.now() / 1000), 12));
+    field(148, 8, "        ");
+    field(156, 1, "0");
+    field(257, 8, "ustar\x0000");
+    const checksum = header.reduce((sum, byte) => sum + byte, 0);
+    field(148, 8, checksum.toString(8).padStart(6, "0") + "\0 ");
+
+    const padding = (512 - (file.data.length % 512)) % 512;
+    blocks.push(header, file.data, new Uint8Array(padding));
+  }
+  blocks.push(new Uint8Array(1024));
+  return Buffer.concat(blocks);
+}
+
+function untar(archive) {
+  const files = [];
+  for (let offset = 0; offset + 512 <= archive.length; ) {
+    const header = archive.subarray(offset, offset + 512);
+    if (header.every((byte) => byte === 0)) break;
+    const text = (start, length) =>
+      decoder
+        .decode(header.subarray(start, start + length))
+        .replace(/\0.*$/s, "");
+    const size = parseInt(text(124, 12).trim(), 8);
+    const start = offset + 512;
+    if (text(156, 1) === "0" || text(156, 1) === "") {
+      files.push({
+        name: text(0, 100),
+        data: archive.subarray(start, start + size),
+      });
+    }
+    offset = start + Math.ceil(size / 512) * 512;
+  }
+  return files;
+}
+
+function compress(archive, format) {
+  if (format === "tar") return archive;
+  if (format === "gz") return gzipSync(archive);
+  if (!Bun.zstdCompressSync) {
+    throw new Error("zstd needs a newer Bun; use .tar.gz instead");
+  }
+  return Bun.zstdCompressSync(archive);
+}
+
+// Decompress by magic number: zstd, gzip or a plain tar
+function decompress(bytes) {
+  if (bytes[0] === 0x28 && bytes[1] === 0xb5 && bytes[2] === 0x2f) {
+    if (!Bun.zstdDecompressSync) {
+      throw new Error("zstd needs a newer Bun; use .tar.gz instead");
+    }
+    return Buffer.from(Bun.zstdDecompressSync(bytes));
+  }
+  if (bytes[0] === 0x1f && bytes[1] === 0x8b) return gunzipSync(bytes);
+  return Buffer.from(bytes);
+}
+
+// `tar`, `gz` or `zst` from a file name, defaulting to zstd
+export function backupFormat(path) {
+  if (path.endsWith(".tar")) return "tar";
+  if (path.endsWith(".gz") || path.endsWith(".tgz")) return "gz";
+  return "zst";
+}
+
+// Archive of a consistent snapshot of the database and the config file
+export async function createBackup(format = "zst") {
+  const directory = await mkdtemp(join(tmpdir(), "elektron-"));
+  try {
+    const snapshot = join(directory, DATABASE);
+    // A consistent copy, even while the server is writing
+    db().query("VACUUM INTO ?").run(snapshot);
+    const files = [{ name: DATABASE, data: await readFile(snapshot) }];
+    if (existsSync(CONFIG_PATH)) {
+      files.push({ name: CONFIG, data: await readFile(CONFIG_PATH) });
+    }
+    return compress(tar(files), format);
+  } finally {
+    await rm(directory, { recursive: true, force: true });
+  }
+}
+
+// Write the database and config of a backup to the configured paths; refuses
+// to overwrite existing files unless `force`. Run it while the server is down
+export async function restoreBackup(bytes, { force = false } = {}) {
+  const files = untar(decompress(bytes));
+  const targets = { [DATABASE]: config.database, [CONFIG]: CONFIG_PATH };
+  const found = files.filter((file) => targets[basename(file.name)]);
+  if (!found.some((file) => basename(file.name) === DATABASE)) {
+    throw new Error("Not an elektron backup (no database in the archive)");
+  }
+
+  for (const file of found) {
+    const target = targets[basename(file.name)];
+    if (existsSync(target) && !force) {
+      throw new Error(`${target} exists; use --force to overwrite it`);
+    }
+  }
+  for (const file of found) {
+    const target = targets[basename(file.name)];
+    if (target === config.database) {
+      // Stale WAL files would be replayed on top of the restored database
+      await rm(`${target}-wal`, { force: true });
+      await rm(`${target}-shm`, { force: true });
+    }
+    await writeFile(target, file.data);
+  }
+  return found.map((file) => targets[basename(file.name)]);
+}
@@ -0,0 +1,77 @@
+import { costedConsumption } from "./cost.js";
+import { db } from "./db.js";
+import { formatKroner } from "./locale.js";
+import { locations } from "./locations.js";
+import { notify } from "./notify.js";
+import { isoDate } from "./prices.js";
+import { capacityCharge, monthlyPeak, tariffSettings } from "./tariffs.js";
+import { taxSettings } from "./tax.js";
+
+// Projected cost (NOK) of the current month for a location with a `budget`:
+// the energy cost so far extrapolated by the average day, plus the capacity
+// charge for the peak so far
+export async function projectMonth(name, location, now = new Date()) {
+  const from = new Date(now.getFullYear(), now.getMonth(), 1);
+  const daysInMonth = new Date(
+    now.getFullYear(),
+    now.getMonth() + 1,
+    0,
+  ).getDate();
+
+  const hours = await costedConsumption(name, location, from, now);
+  const energy = hours.reduce((sum, row) => sum + row.cost, 0);
+  const days = new Set(hours.map((row) => row.time.slice(0, 10))).size;
+
+  const tariff = tariffSettings(location.tariff);
+  const capacity = tariff
+    ? capacityCharge(monthlyPeak(hours), tariff) *
+      (1 + taxSettings(location.region).vat)
+    : 0;
+
+  const projected = days > 0 ? (energy / days) * daysInMonth + capacity : null;
+  return {
+    location: name,
+    month: isoDate(from).slice(0, 7),
+    budget: location.budget ?? null,
+    days,
+    days_in_month: daysInMonth,
+    cost_so_far: energy,
+    capacity,
+    projected,
+    over:
+      projected !== null && location.budget != null
+        ? projected > location.budget
+        : null,
+  };
+}
+
+// Notify (once per location and month) when a projection exceeds the budget
+export async function checkBudgets(now = new Date()) {
+  for (const [name, location] of Object.entries(locations())) {
+    if (!location.budget || !location.consumption) continue;
+
+    const projection = await projectMonth(name, location, now);
+    if (!projection.over) continue;
+
+    const alerted = db()
+      .query("SELECT 1 FROM budget_alerts WHERE location = ? AND month = ?")
+      .get(name, projection.month);
+    if (alerted) continue;
+
+    db()
+      .query(
+        "INSERT INTO budget_alerts (location, month, projected, time) " +
+          "VALUES (?, ?, ?, ?)",
+      )
+      .run(name, projection.month, projection.projected, now.toISOString());
+    await notify({
+      event: "budget",
+      title: `Strømbudsjettet for ${name} sprekker`,
+      message:
+        `Anslått kostnad for ${projection.month} er ` +
+        `${formatKroner(projection.projected, 0)} kr, budsjettet er ` +
+        `${formatKroner(location.budget, 0)} kr.`,
+      data: projection,
+    });
+  }
+}
@@ -0,0 +1,235 @@
+import { recordAction } from "./audit.js";
+import { config } from "./config.js";
+import { db } from "./db.js";
+import { formatOre } from "./locale.js";
+import { automationPaused, deviceOverride } from "./overrides.js";
+import { getHorizon, getPrices } from "./prices.js";
+import { sharedGet, sharedSet } from "./redis.js";
+import { durations } from "./stats.js";
+
+const tokens = new Map();
+
+// Bearer token for `type`, refreshed shortly before it expires
+async function token(type, login) {
+  const cached = tokens.get(type);
+  if (cached && Date.now() < cached.expires) return cached.token;
+
+  const { token, expiresIn } = await login();
+  tokens.set(type, {
+    token,
+    expires: Date.now() + (expiresIn - 60) * 1000,
+  });
+  return token;
+}
+
+async function send(url, token) {
+  const response = await fetch(url, {
+    method: "POST",
+    headers: { authorization: `Bearer ${token}` },
+  });
+  if (!response.ok) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+}
+
+// Cloud APIs, selected by `charger.type`; each can `pause` and `resume`
+export const CHARGERS = {
+  zaptec: async (options, action) => {
+    const bearer = await token("zaptec", async () => {
+      const response = await fetch("https://api.zaptec.com/oauth/token", {
+        method: "POST",
+        body: new URLSearchParams({
+          grant_type: "password",
+          username: options.username,
+          password: options.password,
+        }),
+      });
+      if (!response.ok) {
+        throw new Error(`HTTP error! status: ${response.status}`);
+      }
+      const body = await response.json();
+      return { token: body.access_token, expiresIn: body.expires_in };
+    });
+
+    // 506: stop charging (final), 507: resume charging
+    const command = action === "pause" ? 506 : 507;
+    await send(
+      `https://api.zaptec.com/api/chargers/${options.charger_id}/sendCommand/${command}`,
+      bearer,
+    );
+  },
+  easee: async (options, action) => {
+    const bearer = await token("easee", async () => {
+      const response = await fetch(
+        "https://api.easee.com/api/accounts/login",
+        {
+          method: "POST",
+          headers: { "content-type": "application/json" },
+          body: JSON.stringify({
+            userName: options.username,
+            password: options.password,
+          }),
+        },
+      );
+      if (!response.ok) {
+        throw new Error(`HTTP error! status: ${response.status}`);
+      }
+      const body = await response.json();
+      return { token: body.accessToken, expiresIn: body.expiresIn };
+    });
+
+    await send(
+      `https://api.easee.com/api/chargers/${options.charger_id}/commands/${action}_charging`,
+      bearer,
+    );
+  },
+};
+
+export function chargerSettings() {
+  return {
+    region: "NO2",
+    hours: config.tasks.ev,
+    plug_in: 17, // The car is plugged in from this hour...
+    ready_by: 7, // ...and must be charged by this hour
+    dry_run: true, // Log the actions without sending them
+    ...config.charger,
+  };
+}
+
+// The charging session (plug_in to ready_by) that is ongoing or next, with
+// the cheapest intervals covering `hours` hours marked `charge`
+export function chargingPlan(chart, settings, now = new Date()) {
+  const sessions = [];
+  let session = null;
+  for (const item of chart) {
+    if (session && item.hour === settings.ready_by) {
+      sessions.push(session);
+      session = null;
+    }
+    if (!session && item.hour === settings.plug_in) session = [];
+    if (session) session.push(item);
+  }
+  // The end of the session may not be published yet
+  if (session) sessions.push(session);
+
+  const lengths = new Map();
+  const current = sessions.find((items) => {
+    durations(items).forEach((hours, i) => lengths.set(items[i], hours));
+    const last = items[items.length - 1];
+    return Date.parse(last.time) + lengths.get(last) * 3600000 > now;
+  });
+  if (!current) return null;
+
+  const charge = new Set();
+  let covered = 0;
+  for (const item of [...current].sort((a, b) => a.price - b.price)) {
+    if (covered >= settings.hours - 1e-9) break;
+    charge.add(item);
+    covered += lengths.get(item);
+  }
+
+  return current.map((item) => ({
+    hour: item.hour,
+    time: item.time,
+    price: item.price,
+    charge: charge.has(item),
+  }));
+}
+
+// Yesterday's prices too, for a session that started before midnight
+async function planPrices(region, now) {
+  const yesterday = new Date(now);
+  yesterday.setDate(yesterday.getDate() - 1);
+  const horizon = await getHorizon(region, now);
+  try {
+    return [...(await getPrices(yesterday, region)), ...horizon];
+  } catch (error) {
+    return horizon;
+  }
+}
+
+export async function currentPlan(now = new Date()) {
+  const settings = chargerSettings();
+  return chargingPlan(await planPrices(settings.region, now), settings, now);
+}
+
+// Actions taken (or skipped in dry-run mode), newest first
+export function chargerLog(limit = 50) {
+  return db()
+    .query(
+      "SELECT time, type, action, dry_run, error FROM charger_actions " +
+        "ORDER BY time DESC LIMIT ?",
+    )
+    .all(limit)
+    .map((row) => ({ ...row, dry_run: row.dry_run === 1 }));
+}
+
+// Stateless replicas take turns running the charger job and don't share a
+// database, so the last action is also kept in the shared store
+async function record(type, action, dryRun, error = null) {
+  const time = new Date().toISOString();
+  db()
+    .query(
+      "INSERT INTO charger_actions (time, type, action, dry_run, error) " +
+        "VALUES (?, ?, ?, ?, ?)",
+    )
+    .run(time, type, action, dryRun ? 1 : 0, error);
+  if (config.stateless) {
+    await sharedSet("charger:last", { time, type, action, error });
+  }
+}
+
+// The last action sent (or attempted), by any replica
+async function lastAction() {
+  if (config.stateless) return sharedGet("charger:last");
+  const [last] = chargerLog(1);
+  return last;
+}
+
+// Pause or resume the charger when the plan (or a manual override) says
+// otherwise than last time, recording why in the audit log; left alone while
+// the automation is paused
+export async function updateCharger(now = new Date()) {
+  const settings = chargerSettings();
+  const send = CHARGERS[settings.type];
+  if (!send) throw new Error(`Unknown charger type: ${settings.type}`);
+  if (await automationPaused(now)) return;
+
+  const forced = await deviceOverride("charger", now);
+  const plan = forced ? null : await currentPlan(now);
+  const active = plan?.findLast((item) => Date.parse(item.time) <= now);
+  const action = (forced ? forced.on : active?.charge) ? "resume" : "pause";
+
+  const last = await lastAction();
+  if (last?.action === action && !last.error) return;
+
+  let reason = "outside the charging session";
+  if (forced) {
+    const state = forced.on ? "on" : "off";
+    reason = `forced ${state} until ${forced.until || "cleared"}`;
+  } else if (active) {
+    reason =
+      `${active.charge ? "one" : "not one"} of the cheapest ` +
+      `${settings.hours} h before ${settings.ready_by}:00, at ` +
+      `${formatOre(active.price)} øre/kWh`;
+  }
+  const entry = {
+    source: "charger",
+    action: `${settings.type} ${action}`,
+    reason,
+  };
+  if (settings.dry_run) {
+    await record(settings.type, action, true);
+    recordAction({ ...entry, result: "dry_run" });
+    return;
+  }
+  try {
+    await send(settings, action);
+    await record(settings.type, action, false);
+    recordAction(entry);
+  } catch (error) {
+    await record(settings.type, action, false, error.message);
+    recordAction({ ...entry, result: "error", error: error.message });
+    throw error;
+  }
+}
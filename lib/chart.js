@@ -0,0 +1,409 @@
+import { config } from "./config.js";
+import { formatNumber } from "./locale.js";
+import { maximum, minimum } from "./stats.js";
+import { HOUR } from "./time.js";
+import { UNITS } from "./units.js";
+
+const FONT = 'JetBrainsMono, "JetBrains Mono", monospace';
+
+export const THRESHOLDS = [
+  { value: 0, name: "0 øre", color: "#CC0000" },
+  { value: 50, name: "Norgespris", color: "#008E00" },
+  { value: 75, name: "75 øre", color: "#CC0000" },
+];
+
+// How the chart is drawn on the page and as SVG
+export function chartSettings(overrides = {}) {
+  return {
+    grid: 6, // Intervals between the y-axis labels
+    padding: 0.1, // Share of the price range added above and below
+    // The y-axis always shows 0, is as deep below 0 as it is high above it,
+    // or has a fixed top (in the chart's unit) with anything above cut off
+    include_zero: false,
+    symmetric: false,
+    max: null,
+    labels: "ore", // Or "nok" to label prices in øre as kr
+    thresholds: THRESHOLDS, // In øre; only 0 is kept for other units
+    // In øre: prices below `cheap` and above `expensive` are shaded
+    bands: { cheap: null, expensive: null },
+    ...config.chart,
+    ...overrides,
+  };
+}
+
+// What is wrong with chart setting `overrides`, or null if nothing is
+export function validateChartSettings(overrides) {
+  if (!overrides || typeof overrides !== "object" || Array.isArray(overrides)) {
+    return "chart settings must be an object";
+  }
+  const unknown = Object.keys(overrides).filter(
+    (key) => !(key in chartSettings()),
+  );
+  if (unknown.length > 0) return `unknown chart settings: ${unknown}`;
+
+  const { grid, padding, labels, thresholds, bands } = overrides;
+  for (const name of ["include_zero", "symmetric"]) {
+    if (overrides[name] !== undefined && typeof overrides[name] !== "boolean") {
+      return `${name} must be true or false`;
+    }
+  }
+  if (overrides.max != null && !Number.isFinite(overrides.max)) {
+    return "max must be a number or null";
+  }
+  if (grid !== undefined && !(Number.isInteger(grid) && grid >= 1)) {
+    return "grid must be a positive integer";
+  }
+  if (grid > 20) return "grid must be at most 20";
+  if (padding !== undefined && !(padding >= 0 && padding <= 1)) {
+    return "padding must be between 0 and 1";
+  }
+  if (labels !== undefined && !["ore", "nok"].includes(labels)) {
+    return "labels must be ore or nok";
+  }
+  if (thresholds !== undefined) {
+    if (!Array.isArray(thresholds) || thresholds.length > 10) {
+      return "thresholds must be a list of at most 10";
+    }
+    for (const threshold of thresholds) {
+      if (!Number.isFinite(threshold?.value)) {
+        return "each threshold needs a numeric value";
+      }
+      if (typeof threshold.name !== "string" || threshold.name.length > 40) {
+        return "each threshold needs a name of at most 40 characters";
+      }
+      if (!/^#[0-9a-fA-F]{6}$/.test(threshold.color)) {
+        return "each threshold needs a color like #CC0000";
+      }
+    }
+  }
+  if (bands !== undefined) {
+    if (!bands || typeof bands !== "object") {
+      return "bands must be { cheap, expensive }";
+    }
+    for (const name of ["cheap", "expensive"]) {
+      if (bands[name] != null && !Number.isFinite(bands[name])) {
+        return `bands.${name} must be a number or null`;
+      }
+    }
+    const { cheap, expensive } = bands;
+    if (cheap != null && expensive != null && expensive < cheap) {
+      return "bands.expensive must not be below bands.cheap";
+    }
+  }
+  return null;
+}
+
+// `settings` for a chart in `unit`: other units than øre keep only the 0
+// threshold and no bands or fixed top, and their prices aren't labelled as kr
+export function settingsForUnit(settings, unit) {
+  if (unit === "ore") return settings;
+  return {
+    ...settings,
+    labels: "ore",
+    thresholds: settings.thresholds
+      .filter((threshold) => threshold.value === 0)
+      .map((threshold) => ({ ...threshold, name: `0 ${UNITS[unit].short}` })),
+    bands: { cheap: null, expensive: null },
+    max: null,
+  };
+}
+
+const BAND_COLORS = { cheap: "#008E00", expensive: "#CC0000" };
+
+// The shaded `{ name, from, to, color }` regions of `bands` within `axis`
+export function chartBands(bands, axis) {
+  const regions = [
+    { name: "cheap", from: axis.min, to: bands?.cheap },
+    { name: "expensive", from: bands?.expensive, to: axis.max },
+  ];
+  return regions
+    .filter((band) => band.from != null && band.to != null)
+    .map((band) => ({
+      ...band,
+      from: Math.max(band.from, axis.min),
+      to: Math.min(band.to, axis.max),
+      color: BAND_COLORS[band.name],
+    }))
+    .filter((band) => band.from < band.to);
+}
+
+// A y-axis label: øre as kr with `labels` "nok", otherwise as is
+export function axisLabel(value, labels = "ore") {
+  return labels === "nok"
+    ? formatNumber(value / 100, 2)
+    : formatNumber(value, 1);
+}
+
+export function escape(text) {
+  return String(text)
+    .replace(/&/g, "&amp;")
+    .replace(/</g, "&lt;")
+    .replace(/>/g, "&gt;")
+    .replace(/"/g, "&quot;");
+}
+
+// What the step chart draws: each interval's `{ hour, price }` followed by the
+// last price again at the hour after (where the last step ends), and the
+// y-axis with `grid` + 1 labels. The axis spans the prices and the values
+// to `include` (0 and the threshold lines shown), padded by `padding` of
+// that range, and the `bands` to shade are clipped to it. `include_zero`,
+// `symmetric` and `max` are the axis policy of `chartSettings()`
+export function stepSeries(
+  chart,
+  include = [],
+  {
+    grid = 6,
+    padding = 0.1,
+    bands = null,
+    include_zero = false,
+    symmetric = false,
+    max: top = null,
+  } = {},
+) {
+  if (chart.length === 0) return { points: [], axis: null, bands: [] };
+
+  const points = chart.map((item) => ({ hour: item.hour, price: item.price }));
+  const last = points[points.length - 1];
+  points.push({ hour: last.hour + 1, price: last.price });
+
+  const values = [...points.map((item) => item.price), ...include];
+  if (include_zero) values.push(0);
+  let low = minimum(values);
+  let high = maximum(values);
+  if (symmetric) {
+    high = Math.max(Math.abs(low), Math.abs(high));
+    low = -high;
+  }
+  const range = high - low;
+  // A flat day gets some room around its price
+  const room = range > 0 ? range * padding : Math.max(Math.abs(low * 0.1), 1);
+  let min = low - room;
+  let max = high + room;
+  // A fixed top, unless the whole day is above it
+  if (top != null && top > min) {
+    max = top;
+    if (symmetric && top > 0) min = -top;
+  }
+  const axis = {
+    min,
+    max,
+    ticks: Array.from(
+      { length: grid + 1 },
+      (_, i) => min + ((max - min) * i) / grid,
+    ),
+  };
+  return { points, axis, bands: chartBands(bands, axis) };
+}
+
+// Secondary series scaled to the full height of the graph, labelled top right
+function renderOverlay(overlay, length, x, margin, graphHeight) {
+  const known = overlay.values.filter((value) => value !== null);
+  if (known.length === 0) return [];
+
+  const low = minimum(known);
+  const high = maximum(known);
+  const range = high - low || 1;
+  const y = (value) =>
+    margin.top + graphHeight - ((value - low) / range) * graphHeight * 0.9;
+
+  let path = "";
+  overlay.values.forEach((value, i) => {
+    if (value === null || i + 1 >= length) return;
+    const command = path === "" || overlay.values[i - 1] === null ? "M" : "L";
+    path += `${command}${x(i).toFixed(1)},${y(value).toFixed(1)} H${x(i + 1).toFixed(1)} `;
+  });
+
+  const color = overlay.color || "#666666";
+  return [
+    `<path d="${path.trim()}" fill="none" stroke="${color}" stroke-width="1.5" stroke-dasharray="4 3"/>`,
+    `<text x="${x(length - 1).toFixed(1)}" y="${margin.top - 12}" text-anchor="end" font-size="11" fill="${color}">${escape(overlay.label)} (${formatNumber(low, 0)}–${formatNumber(high, 0)})</text>`,
+  ];
+}
+
+// What can be drawn on top of the SVG chart with `?annotate=`
+export const ANNOTATIONS = {
+  extremes: "markers at the day's min. and max.",
+  now: "a line at the current time",
+  stats: "the statistics line below the chart",
+};
+
+// Comma-separated annotation names (`?annotate=extremes,now`); the names, or
+// `{ message }` naming an unknown one
+export function parseAnnotations(text) {
+  const names = text.split(",").filter(Boolean);
+  if (names.some((name) => !ANNOTATIONS[name])) {
+    return {
+      message: `annotate must be among: ${Object.keys(ANNOTATIONS).join(", ")}`,
+    };
+  }
+  return names;
+}
+
+// Position of `now` on the x-axis in intervals from the start of `chart`, or
+// null if it is another day
+function nowPosition(chart, now) {
+  const starts = chart.map((item) => new Date(item.time).getTime());
+  const last = starts.length - 1;
+  const length = last > 0 ? starts[last] - starts[last - 1] : HOUR;
+  const ends = [...starts.slice(1), starts[last] + length];
+  const index = starts.findIndex((start, i) => start <= now && now < ends[i]);
+  if (index === -1) return null;
+  return index + (now - starts[index]) / (ends[index] - starts[index]);
+}
+
+// Render the daily step chart as SVG, mirroring the canvas chart on the page
+// `overlay` is an optional `{ label, color, values }` series (one value per
+// interval, null where missing) drawn dashed on its own scale. `grid`,
+// `padding`, `labels`, `thresholds`, `bands` and the axis policy default to
+// `chartSettings()`; what is beyond a fixed `max` is cut off.
+// `annotations` are among `ANNOTATIONS`, with the "now" line at `now` and
+// the `statistics` (`[{ label, value, unit }]`) as the stats line
+export function renderSvg(
+  chart,
+  {
+    width = 800,
+    height = 400,
+    overlay = null,
+    annotations = [],
+    now = new Date(),
+    statistics = [],
+    ...options
+  } = {},
+) {
+  const {
+    grid,
+    padding,
+    labels,
+    thresholds,
+    bands,
+    include_zero,
+    symmetric,
+    max,
+  } = { ...chartSettings(), ...options };
+  const stats = annotations.includes("stats") && statistics.length > 0;
+  const margin = { top: 30, right: 30, bottom: stats ? 60 : 40, left: 60 };
+  const graphWidth = width - margin.left - margin.right;
+  const graphHeight = height - margin.top - margin.bottom;
+
+  const parts = [
+    `<svg xmlns="http://www.w3.org/2000/svg" width="${width}" height="${height}" viewBox="0 0 ${width} ${height}" font-family='${FONT}' font-size="12">`,
+    `<rect width="${width}" height="${height}" fill="#ffffff"/>`,
+  ];
+
+  if (chart.length === 0) {
+    parts.push(
+      `<text x="${width / 2}" y="${height / 2}" text-anchor="middle" font-size="14" fill="#1D1C1A">Hmm. Ingen data.</text>`,
+      "</svg>",
+    );
+    return parts.join("\n");
+  }
+
+  const series = stepSeries(
+    chart,
+    thresholds.map((threshold) => threshold.value),
+    { grid, padding, bands, include_zero, symmetric, max },
+  );
+  const { points: stepData, axis } = series;
+  const paddedMin = axis.min;
+  const paddedMax = axis.max;
+
+  const xStep = graphWidth / (stepData.length - 1);
+  const x = (i) => margin.left + xStep * i;
+  const y = (price) =>
+    margin.top +
+    graphHeight -
+    ((price - paddedMin) / (paddedMax - paddedMin)) * graphHeight;
+
+  // Shaded cheap and expensive bands, behind everything else
+  for (const band of series.bands) {
+    parts.push(
+      `<rect x="${margin.left}" y="${y(band.to).toFixed(1)}" width="${graphWidth}" height="${(y(band.from) - y(band.to)).toFixed(1)}" fill="${band.color}" fill-opacity="0.1"/>`,
+    );
+  }
+
+  // Y-axis labels
+  for (const value of axis.ticks) {
+    const ty = y(value);
+    parts.push(
+      `<text x="${margin.left - 10}" y="${ty.toFixed(1)}" text-anchor="end" dominant-baseline="middle">${axisLabel(value, labels)}</text>`,
+    );
+  }
+
+  // X-axis labels, thinned out when the intervals are dense
+  const labelStep = xStep < 30 ? (xStep < 15 ? 4 : 2) : 1;
+  for (let i = 0; i < stepData.length; i++) {
+    if (i % labelStep === 0 || i === stepData.length - 1) {
+      parts.push(
+        `<text x="${x(i).toFixed(1)}" y="${margin.top + graphHeight + 10}" text-anchor="middle" dominant-baseline="hanging">${stepData[i].hour.toString().padStart(2, "0")}</text>`,
+      );
+    }
+  }
+
+  // Step price line, cut off at the edges of the graph
+  parts.push(
+    `<clipPath id="graph"><rect x="${margin.left - 2}" y="${margin.top - 2}" width="${graphWidth + 4}" height="${graphHeight + 4}"/></clipPath>`,
+  );
+  let path = `M${x(0).toFixed(1)},${y(stepData[0].price).toFixed(1)}`;
+  for (let i = 1; i < stepData.length; i++) {
+    path += ` H${x(i).toFixed(1)} V${y(stepData[i].price).toFixed(1)}`;
+  }
+  parts.push(
+    `<path d="${path}" fill="none" stroke="#1D1C1A" stroke-width="2" clip-path="url(#graph)"/>`,
+  );
+
+  if (overlay) {
+    parts.push(
+      ...renderOverlay(overlay, stepData.length, x, margin, graphHeight),
+    );
+  }
+
+  // Threshold lines within the axis
+  for (const threshold of thresholds) {
+    if (threshold.value < paddedMin || threshold.value > paddedMax) continue;
+    const ty = y(threshold.value);
+    parts.push(
+      `<line x1="${margin.left}" y1="${ty.toFixed(1)}" x2="${margin.left + graphWidth}" y2="${ty.toFixed(1)}" stroke="${threshold.color}" stroke-width="2"/>`,
+      `<text x="${margin.left + 5}" y="${(ty - 10).toFixed(1)}" font-size="11" fill="${threshold.color}" dominant-baseline="middle">${escape(threshold.name)}</text>`,
+    );
+  }
+
+  // Markers in the middle of the (first) cheapest and dearest interval
+  if (annotations.includes("extremes")) {
+    const prices = chart.map((item) => item.price);
+    for (const [label, price, dy] of [
+      ["Min.", minimum(prices), 16],
+      ["Maks", maximum(prices), -10],
+    ]) {
+      const mx = x(prices.indexOf(price) + 0.5);
+      const my = Math.max(y(price), margin.top);
+      parts.push(
+        `<circle cx="${mx.toFixed(1)}" cy="${my.toFixed(1)}" r="4" fill="#1D1C1A"/>`,
+        `<text x="${mx.toFixed(1)}" y="${(my + dy).toFixed(1)}" text-anchor="middle" font-size="11" fill="#1D1C1A">${label} ${axisLabel(price, labels)}</text>`,
+      );
+    }
+  }
+
+  const position = annotations.includes("now") ? nowPosition(chart, now) : null;
+  if (position !== null) {
+    const nx = x(position).toFixed(1);
+    parts.push(
+      `<line x1="${nx}" y1="${margin.top}" x2="${nx}" y2="${margin.top + graphHeight}" stroke="#1D1C1A" stroke-width="1.5" stroke-dasharray="4 3"/>`,
+      `<text x="${nx}" y="${margin.top - 6}" text-anchor="middle" font-size="11" fill="#1D1C1A">Nå</text>`,
+    );
+  }
+
+  if (stats) {
+    const line = statistics
+      .map(
+        (item) =>
+          `${item.label}: ${formatNumber(item.value, 1)}${item.unit ? ` ${item.unit}` : ""}`,
+      )
+      .join("   ");
+    parts.push(
+      `<text x="${width / 2}" y="${height - 12}" text-anchor="middle">${escape(line)}</text>`,
+    );
+  }
+
+  parts.push("</svg>");
+  return parts.join("\n");
+}
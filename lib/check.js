@@ -0,0 +1,135 @@
+import { existsSync } from "fs";
+import nodemailer from "nodemailer";
+import { CONFIG_PATH, config, loadConfig } from "./config.js";
+import { db } from "./db.js";
+import { NOTIFIERS } from "./notify.js";
+import { fetchPrices } from "./prices.js";
+import { redis } from "./redis.js";
+import { validateRule } from "./rules.js";
+import { tariffSettings } from "./tariffs.js";
+
+// Options each notifier type can't do without
+const REQUIRED = {
+  webhook: ["url"],
+  discord: ["url"],
+  slack: ["url"],
+  pushover: ["token", "user"],
+  gotify: ["url", "token"],
+  smtp: ["host", "from", "to"],
+};
+
+async function checkConfig() {
+  if (!existsSync(CONFIG_PATH)) return `no ${CONFIG_PATH}, using the defaults`;
+  loadConfig();
+  return `${CONFIG_PATH} is valid JSON`;
+}
+
+async function checkDatabase() {
+  const version = db().query("PRAGMA user_version").get().user_version;
+  const { days } = db()
+    .query("SELECT COUNT(DISTINCT substr(time, 1, 10)) AS days FROM prices")
+    .get();
+  return `${config.database} at schema version ${version}, ${days} days stored`;
+}
+
+async function checkUpstream() {
+  if (config.synthetic) return "skipped, serving synthetic prices";
+  const today = new Date();
+  const started = Date.now();
+  await fetchPrices(
+    today.getFullYear(),
+    today.getMonth() + 1,
+    today.getDate(),
+    "NO2",
+  );
+  return `today's prices fetched in ${Date.now() - started} ms`;
+}
+
+async function checkTariffs() {
+  const named = [
+    ["tariff", config.tariff],
+    ...Object.entries(config.locations || {}).map(([name, location]) => [
+      `locations.${name}.tariff`,
+      location.tariff,
+    ]),
+  ].filter(([, tariff]) => tariff);
+  for (const [name, tariff] of named) {
+    try {
+      tariffSettings(tariff);
+    } catch (error) {
+      throw new Error(`${name}: ${error.message}`);
+    }
+  }
+  return named.length > 0 ? `${named.length} valid` : "none configured";
+}
+
+async function checkRules() {
+  const rules = config.rules || [];
+  for (const rule of rules) {
+    const problem = validateRule(rule);
+    if (problem) throw new Error(`${rule.name || "unnamed"}: ${problem}`);
+  }
+  return rules.length > 0 ? `${rules.length} valid` : "none configured";
+}
+
+async function checkNotifier(options) {
+  if (!NOTIFIERS[options.type]) {
+    throw new Error(`unknown type ${options.type}`);
+  }
+  const missing = REQUIRED[options.type].filter((key) => !options[key]);
+  if (missing.length > 0) throw new Error(`missing ${missing.join(", ")}`);
+  // The webhooks can't be checked without posting to them
+  if (options.type !== "smtp") return "configured";
+
+  const transport = nodemailer.createTransport({
+    host: options.host,
+    port: options.port || 587,
+    secure: options.secure ?? options.port === 465,
+    auth: options.username && {
+      user: options.username,
+      pass: options.password,
+    },
+  });
+  await transport.verify();
+  return `logged in to ${options.host}`;
+}
+
+async function checkRedis() {
+  await redis().send("PING", []);
+  return `${config.redis.url} answers`;
+}
+
+async function checkPdf() {
+  if (!Bun.which(config.pdf.command)) {
+    throw new Error(`${config.pdf.command} not found`);
+  }
+  return config.pdf.command;
+}
+
+// Check the configuration and everything it points at; `{ name, ok, detail }`
+// per check, in order
+export async function runChecks() {
+  const checks = [
+    ["config", checkConfig],
+    ["database", checkDatabase],
+    ["upstream", checkUpstream],
+    ["tariffs", checkTariffs],
+    ["rules", checkRules],
+    ...(config.notifiers || []).map((options, i) => [
+      `notifier ${i + 1} (${options.type})`,
+      () => checkNotifier(options),
+    ]),
+    ...(config.redis?.url ? [["redis", checkRedis]] : []),
+    ...(config.pdf?.command ? [["pdf", checkPdf]] : []),
+  ];
+
+  const results = [];
+  for (const [name, check] of checks) {
+    try {
+      results.push({ name, ok: true, detail: await check() });
+    } catch (error) {
+      results.push({ name, ok: false, detail: error.message });
+    }
+  }
+  return results;
+}
@@ -0,0 +1,46 @@
+import { config } from "./config.js";
+
+const API = "https://api.electricitymap.org/v3/carbon-intensity";
+const TTL = 30 * 60 * 1000;
+const HOUR = 60 * 60 * 1000;
+
+const cache = new Map();
+
+// Hourly carbon intensity (gCO₂eq/kWh) for `region` from Electricity Maps,
+// keyed by the start of the hour (ms); history plus forecast where available
+export async function carbonIntensity(region) {
+  const { token } = config.co2 || {};
+  if (!token) throw new Error("co2.token is not configured");
+
+  const cached = cache.get(region);
+  if (cached && Date.now() - cached.fetched < TTL) return cached.intensity;
+
+  const intensity = new Map();
+  for (const kind of ["history", "forecast"]) {
+    const response = await fetch(`${API}/${kind}?zone=NO-${region}`, {
+      headers: { "auth-token": token },
+    });
+    if (!response.ok) {
+      // The forecast requires a paid plan; history alone is fine
+      if (kind === "forecast") continue;
+      throw new Error(`HTTP error! status: ${response.status}`);
+    }
+
+    const body = await response.json();
+    for (const item of body[kind] || []) {
+      const hour = Math.floor(Date.parse(item.datetime) / HOUR) * HOUR;
+      if (!intensity.has(hour)) intensity.set(hour, item.carbonIntensity);
+    }
+  }
+
+  cache.set(region, { intensity, fetched: Date.now() });
+  return intensity;
+}
+
+// `chart` with each interval's carbon intensity (null where unknown)
+export function withIntensity(chart, intensity) {
+  return chart.map((item) => {
+    const hour = Math.floor(Date.parse(item.time) / HOUR) * HOUR;
+    return { ...item, co2: intensity.get(hour) ?? null };
+  });
+}
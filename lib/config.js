@@ -0,0 +1,55 @@
+import { existsSync, readFileSync } from "fs";
+
+// Options of a Home Assistant add-on, which keeps its state in `/data`
+const ADDON_OPTIONS = "/data/options.json";
+export const ADDON = !process.env.ELEKTRON_CONFIG && existsSync(ADDON_OPTIONS);
+
+export const CONFIG_PATH =
+  process.env.ELEKTRON_CONFIG || (ADDON ? ADDON_OPTIONS : "elektron.json");
+
+const DEFAULTS = {
+  database: ADDON ? "/data/elektron.sqlite" : "elektron.sqlite",
+  // Default durations (hours) for `/api/v1/recommendation?task=`
+  tasks: {
+    laundry: 2,
+    dishwasher: 3,
+    dryer: 1.5,
+    ev: 6,
+  },
+  // Days back the nightly job fills in missing prices for
+  backfill_days: 30,
+};
+
+function merge(base, override) {
+  const merged = { ...base };
+  for (const [key, value] of Object.entries(override || {})) {
+    merged[key] =
+      value && typeof value === "object" && !Array.isArray(value)
+        ? merge(base[key] || {}, value)
+        : value;
+  }
+  return merged;
+}
+
+// Read the JSON config file (if any) on top of the defaults
+export function loadConfig(path = CONFIG_PATH) {
+  const defaults = {
+    ...DEFAULTS,
+    // Serve generated prices instead of fetching them, for benchmarks and
+    // demos (`elektron serve --synthetic`)
+    synthetic: Boolean(process.env.ELEKTRON_SYNTHETIC),
+  };
+  if (!existsSync(path)) return merge(defaults, {});
+  return merge(defaults, JSON.parse(readFileSync(path, "utf8")));
+}
+
+export const config = loadConfig();
+
+// Re-read the config file into `config` in place, so every module sees the
+// new values; a broken file throws and leaves the current config as it was
+export function reloadConfig(path = CONFIG_PATH) {
+  const next = loadConfig(path);
+  for (const key of Object.keys(config)) delete config[key];
+  Object.assign(config, next);
+  return config;
+}
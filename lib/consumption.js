@@ -0,0 +1,75 @@
+import { db } from "./db.js";
+import { isoDate } from "./prices.js";
+import { formatLocal } from "./time.js";
+
+// Parse uploaded hourly consumption: a JSON array of `{ time, kwh }` or CSV
+// with `time,kwh` columns; null if it can't be read
+export function parseConsumption(body) {
+  let rows = body;
+  if (typeof body === "string") {
+    const lines = body.trim().split(/\r?\n/);
+    if (/^time[,;]/i.test(lines[0])) lines.shift();
+    rows = lines.map((line) => {
+      const [time, kwh] = line.split(/[,;]/);
+      return { time, kwh: parseFloat(kwh) };
+    });
+  }
+  if (!Array.isArray(rows)) return null;
+
+  const parsed = rows.map((row) => ({
+    time: Date.parse(row.time),
+    kwh: Number(row.kwh),
+  }));
+  if (parsed.some((row) => isNaN(row.time) || !Number.isFinite(row.kwh))) {
+    return null;
+  }
+  return parsed.map((row) => ({ time: formatLocal(row.time), kwh: row.kwh }));
+}
+
+export function storeConsumption(location, rows) {
+  const insert = db().prepare(
+    `INSERT INTO consumption (location, time, kwh) VALUES (?, ?, ?)
+     ON CONFLICT (location, time) DO UPDATE SET kwh = excluded.kwh`,
+  );
+  db().transaction(() => {
+    for (const row of rows) insert.run(location, row.time, row.kwh);
+  })();
+}
+
+// Consumption sources, selected by `consumption.type` of a location; each
+// returns `[{ time, kwh }]` from `from` to `to` (inclusive local dates)
+export const SOURCES = {
+  // Uploaded with `POST /locations/:name/consumption`
+  upload: async (options, location, from, to) => {
+    const end = new Date(to);
+    end.setDate(end.getDate() + 1);
+    return db()
+      .query(
+        `SELECT time, kwh FROM consumption
+         WHERE location = ? AND time >= ? AND time < ?
+         ORDER BY time`,
+      )
+      .all(location, isoDate(from), isoDate(end));
+  },
+  // A JSON endpoint returning `[{ time, kwh }]` for `?from=&to=`
+  url: async (options, location, from, to) => {
+    const url = new URL(options.url);
+    url.searchParams.set("from", isoDate(from));
+    url.searchParams.set("to", isoDate(to));
+    const response = await fetch(url, { headers: options.headers });
+    if (!response.ok) {
+      throw new Error(`HTTP error! status: ${response.status}`);
+    }
+    return parseConsumption(await response.json()) || [];
+  },
+};
+
+// Consumption for a location, or null if it has no consumption source
+export async function loadConsumption(name, location, from, to) {
+  if (!location.consumption) return null;
+  const source = SOURCES[location.consumption.type];
+  if (!source) {
+    throw new Error(`Unknown consumption source: ${location.consumption.type}`);
+  }
+  return source(location.consumption, name, from, to);
+}
@@ -0,0 +1,134 @@
+import { config } from "./config.js";
+import { db } from "./db.js";
+import { notify } from "./notify.js";
+import {
+  REGIONS,
+  fetchPrices,
+  forgetCached,
+  isoDate,
+  loadPrices,
+  storePrices,
+  toChart,
+} from "./prices.js";
+
+export function correctionSettings(overrides = {}) {
+  return {
+    // Days back from tomorrow to compare with upstream
+    days: 3,
+    notify: false,
+    ...config.corrections,
+    ...overrides,
+  };
+}
+
+// Refetch an already stored day and store (and record) any intervals whose
+// upstream price has changed since; returns the recorded corrections
+export async function recheckDay(date, region) {
+  const stored = new Map(
+    loadPrices(region, date, date).map((item) => [item.time, item]),
+  );
+  if (stored.size === 0) return [];
+
+  const fresh = toChart(
+    await fetchPrices(
+      date.getFullYear(),
+      date.getMonth() + 1,
+      date.getDate(),
+      region,
+    ),
+  );
+  const detected = new Date().toISOString();
+  const corrections = fresh.flatMap((item) => {
+    const old = stored.get(item.time);
+    if (
+      !old ||
+      (Math.abs(old.price_nok - item.price_nok) < 1e-9 &&
+        Math.abs(old.price_eur - item.price_eur) < 1e-9)
+    ) {
+      return [];
+    }
+    return [
+      {
+        region,
+        time: item.time,
+        detected,
+        old_nok: old.price_nok,
+        new_nok: item.price_nok,
+        old_eur: old.price_eur,
+        new_eur: item.price_eur,
+      },
+    ];
+  });
+  if (corrections.length === 0) return [];
+
+  const insert = db().prepare(
+    `INSERT INTO corrections
+       (region, time, detected, old_nok, new_nok, old_eur, new_eur)
+     VALUES (?, ?, ?, ?, ?, ?, ?)`,
+  );
+  db().transaction(() => {
+    for (const row of corrections) {
+      insert.run(
+        row.region,
+        row.time,
+        row.detected,
+        row.old_nok,
+        row.new_nok,
+        row.old_eur,
+        row.new_eur,
+      );
+    }
+  })();
+  storePrices(region, fresh);
+  forgetCached(date, region);
+  return corrections;
+}
+
+// Compare the last `days` stored days (up to tomorrow) of every region with
+// upstream, announcing corrections if `corrections.notify` is enabled
+export async function recheckRecent() {
+  const settings = correctionSettings();
+  const failed = [];
+  for (const region of REGIONS) {
+    const date = new Date();
+    date.setDate(date.getDate() + 1);
+    for (let day = 0; day < settings.days; day++) {
+      let corrections;
+      try {
+        corrections = await recheckDay(date, region);
+      } catch (error) {
+        failed.push(`${region} ${isoDate(date)}`);
+        corrections = [];
+      }
+
+      if (corrections.length > 0 && settings.notify) {
+        await notify({
+          event: "correction",
+          title: `Korrigerte priser i ${region}`,
+          message:
+            `${corrections.length} priser for ${region} den ` +
+            `${isoDate(date)} er endret i etterkant.`,
+          data: { region, date: isoDate(date), corrections },
+        });
+      }
+      date.setDate(date.getDate() - 1);
+    }
+  }
+  if (failed.length > 0) {
+    throw new Error(`Could not recheck ${failed.join(", ")}`);
+  }
+}
+
+// Recorded corrections for `region` to intervals from `from` to `to`
+export function loadCorrections(region, from, to) {
+  const end = new Date(to);
+  end.setDate(end.getDate() + 1);
+  return db()
+    .query(
+      `SELECT time, detected, old_nok, new_nok, old_eur, new_eur
+       FROM corrections
+       WHERE region = ? AND time >= ? AND time < ?
+       ORDER BY time, detected`,
+    )
+    .all(region, isoDate(from), isoDate(end));
+}
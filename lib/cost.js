@@ -0,0 +1,82 @@
+import { loadConsumption } from "./consumption.js";
+import { loadPrices } from "./prices.js";
+import { gridRate, tariffSettings } from "./tariffs.js";
+import { elavgift, taxSettings } from "./tax.js";
+
+// Price per interval as it ends up on the invoice (øre/kWh): spot plus
+// elavgift and the nettleie energy rate (when a tariff is configured), with
+// VAT on top of all of them
+export function fullPrices(chart, region, tariff = tariffSettings()) {
+  const settings = taxSettings(region);
+  return chart.map((item) => {
+    const spot = item.price;
+    const tax = elavgift(item.time, settings);
+    const grid = tariff ? gridRate(item.time, tariff) : 0;
+    const vat = (spot + tax + grid) * settings.vat;
+    return {
+      hour: item.hour,
+      time: item.time,
+      spot,
+      elavgift: tax,
+      grid,
+      vat,
+      total: spot + tax + grid + vat,
+    };
+  });
+}
+
+// Cost (NOK) of each hour of `consumption` at the average `prices` (from
+// `fullPrices`) of the intervals within it; hours without prices are skipped
+export function consumptionCost(consumption, prices) {
+  const starts = prices.map((item) => Date.parse(item.time));
+  return consumption.flatMap((row) => {
+    const start = Date.parse(row.time);
+    const within = prices.filter(
+      (item, i) => starts[i] >= start && starts[i] < start + 3600000,
+    );
+    if (within.length === 0) return [];
+    const price =
+      within.reduce((sum, item) => sum + item.total, 0) / within.length;
+    return [{ ...row, price, cost: (row.kwh * price) / 100 }];
+  });
+}
+
+// A location's consumption from `from` to `to` joined with its invoice prices
+export async function costedConsumption(name, location, from, to) {
+  const consumption = await loadConsumption(name, location, from, to);
+  const prices = fullPrices(
+    loadPrices(location.region, from, to),
+    location.region,
+    tariffSettings(location.tariff),
+  );
+  return consumptionCost(consumption, prices);
+}
+
+// Per month: what the consumption from `consumptionCost` cost against the
+// same energy spread evenly over the same hours, i.e. what load shifting saved
+export function savingsByMonth(hours) {
+  const months = new Map();
+  for (const row of hours) {
+    const month = row.time.slice(0, 7);
+    if (!months.has(month)) months.set(month, []);
+    months.get(month).push(row);
+  }
+
+  return [...months].map(([month, rows]) => {
+    const kwh = rows.reduce((sum, row) => sum + row.kwh, 0);
+    const actual = rows.reduce((sum, row) => sum + row.cost, 0);
+    const flatPrice =
+      rows.reduce((sum, row) => sum + row.price, 0) / rows.length;
+    const flat = (kwh * flatPrice) / 100;
+    return {
+      month,
+      hours: rows.length,
+      kwh,
+      actual,
+      flat,
+      savings: flat - actual,
+      // Negative when more was used in the expensive hours than on average
+      percent: flat > 0 ? ((flat - actual) / flat) * 100 : null,
+    };
+  });
+}
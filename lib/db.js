@@ -0,0 +1,157 @@
+import { Database } from "bun:sqlite";
+import { config } from "./config.js";
+
+// Applied in order; the index of the next migration is stored as `user_version`
+const MIGRATIONS = [
+  `CREATE TABLE prices (
+    region TEXT NOT NULL,
+    time TEXT NOT NULL,
+    price_nok REAL NOT NULL,
+    price_eur REAL NOT NULL,
+    PRIMARY KEY (region, time)
+  )`,
+  `CREATE TABLE records (
+    region TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    value REAL NOT NULL,
+    time TEXT NOT NULL,
+    PRIMARY KEY (region, kind)
+  )`,
+  `CREATE TABLE reservoirs (
+    area TEXT NOT NULL,
+    date TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    week INTEGER NOT NULL,
+    filling REAL NOT NULL,
+    filling_twh REAL,
+    capacity_twh REAL,
+    PRIMARY KEY (area, date)
+  )`,
+  `CREATE TABLE temperatures (
+    location TEXT NOT NULL,
+    time TEXT NOT NULL,
+    temperature REAL NOT NULL,
+    PRIMARY KEY (location, time)
+  )`,
+  `CREATE TABLE charger_actions (
+    time TEXT NOT NULL,
+    type TEXT NOT NULL,
+    action TEXT NOT NULL,
+    dry_run INTEGER NOT NULL,
+    error TEXT
+  )`,
+  `CREATE TABLE consumption (
+    location TEXT NOT NULL,
+    time TEXT NOT NULL,
+    kwh REAL NOT NULL,
+    PRIMARY KEY (location, time)
+  )`,
+  `CREATE TABLE budget_alerts (
+    location TEXT NOT NULL,
+    month TEXT NOT NULL,
+    projected REAL NOT NULL,
+    time TEXT NOT NULL,
+    PRIMARY KEY (location, month)
+  )`,
+  `CREATE TABLE corrections (
+    region TEXT NOT NULL,
+    time TEXT NOT NULL,
+    detected TEXT NOT NULL,
+    old_nok REAL NOT NULL,
+    new_nok REAL NOT NULL,
+    old_eur REAL NOT NULL,
+    new_eur REAL NOT NULL,
+    PRIMARY KEY (region, time, detected)
+  )`,
+  // Prices keyed by zone, interval start and resolution (minutes), with where
+  // and when they were fetched. Existing rows get 15 minutes if another
+  // interval starts within the same hour, and 60 otherwise
+  `CREATE TABLE prices_v2 (
+    region TEXT NOT NULL,
+    time TEXT NOT NULL,
+    resolution INTEGER NOT NULL,
+    price_nok REAL NOT NULL,
+    price_eur REAL NOT NULL,
+    source TEXT NOT NULL,
+    fetched_at TEXT NOT NULL,
+    PRIMARY KEY (region, time, resolution)
+  );
+  INSERT INTO prices_v2
+    SELECT region, time,
+      CASE WHEN EXISTS (
+        SELECT 1 FROM prices other
+        WHERE other.region = prices.region
+          AND substr(other.time, 1, 13) = substr(prices.time, 1, 13)
+          AND other.time <> prices.time
+      ) THEN 15 ELSE 60 END,
+      price_nok, price_eur, 'hvakosterstrommen', strftime('%Y-%m-%dT%H:%M:%SZ')
+    FROM prices;
+  DROP TABLE prices;
+  ALTER TABLE prices_v2 RENAME TO prices`,
+  `CREATE TABLE daily_prices (
+    region TEXT NOT NULL,
+    date TEXT NOT NULL,
+    average REAL NOT NULL,
+    min REAL NOT NULL,
+    max REAL NOT NULL,
+    intervals INTEGER NOT NULL,
+    PRIMARY KEY (region, date)
+  )`,
+  `CREATE TABLE exchange_rates (
+    date TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    rate REAL NOT NULL,
+    PRIMARY KEY (date, currency)
+  )`,
+  `CREATE TABLE profiles (
+    name TEXT PRIMARY KEY,
+    region TEXT,
+    tariff TEXT,
+    location TEXT
+  )`,
+  `CREATE TABLE audit_log (
+    time TEXT NOT NULL,
+    source TEXT NOT NULL,
+    action TEXT NOT NULL,
+    reason TEXT,
+    result TEXT NOT NULL,
+    error TEXT
+  );
+  CREATE INDEX audit_log_time ON audit_log (time)`,
+  `CREATE TABLE overrides (
+    target TEXT PRIMARY KEY,
+    state TEXT NOT NULL,
+    until TEXT
+  )`,
+  `CREATE TABLE secrets (
+    name TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+  )`,
+];
+
+function migrate(database) {
+  const { user_version } = database.query("PRAGMA user_version").get();
+  for (let version = user_version; version < MIGRATIONS.length; version++) {
+    database.transaction(() => {
+      database.exec(MIGRATIONS[version]);
+      database.exec(`PRAGMA user_version = ${version + 1}`);
+    })();
+  }
+}
+
+let database = null;
+
+// The shared database handle, opened and migrated on first use
+export function db() {
+  if (!database) {
+    // Stateless replicas only cache what they fetch; the shared state is in
+    // Redis and the replica can be replaced at any time. Synthetic prices
+    // mustn't end up next to real ones
+    const path =
+      config.stateless || config.synthetic ? ":memory:" : config.database;
+    database = new Database(path, { create: true });
+    database.exec("PRAGMA journal_mode = WAL");
+    migrate(database);
+  }
+  return database;
+}
@@ -0,0 +1,77 @@
+import { renderError } from "./pages.js";
+
+const TITLES = {
+  400: "Ugyldig forespørsel",
+  403: "Ingen tilgang",
+  404: "Finner ikke siden",
+  500: "Noe gikk galt",
+  502: "Ugyldig svar",
+  503: "Opptatt",
+  504: "Tidsavbrudd",
+};
+
+// Browsers get an HTML page; the API and other clients get problem+json
+export function wantsHtml(request) {
+  const url = new URL(request.url);
+  return (
+    !url.pathname.startsWith("/api/") &&
+    (request.headers.get("accept") || "").includes("text/html")
+  );
+}
+
+const ids = new WeakMap();
+
+// ID matching a request's log lines to its response: the client's or proxy's
+// `X-Request-Id`, or a new one
+export function requestId(request) {
+  if (!ids.has(request)) {
+    const given = request.headers.get("x-request-id");
+    ids.set(request, given ? given.slice(0, 64) : crypto.randomUUID());
+  }
+  return ids.get(request);
+}
+
+// Error response for `request` (RFC 9457 problem details outside browsers)
+export function errorResponse(request, status, detail) {
+  const title = TITLES[status] || TITLES[500];
+  if (wantsHtml(request)) {
+    return new Response(renderError(status, title, detail), {
+      status,
+      headers: { "content-type": "text/html; charset=utf-8" },
+    });
+  }
+
+  return Response.json(
+    {
+      type: "about:blank",
+      title,
+      status,
+      detail,
+      instance: new URL(request.url).pathname,
+      request_id: requestId(request),
+    },
+    { status, headers: { "content-type": "application/problem+json" } },
+  );
+}
+
+// Wrap a fetch handler so nothing it throws resets the connection: errors
+// become logged 500s, and every response carries its `X-Request-Id`
+export function catchErrors(handle) {
+  return async (request) => {
+    let response;
+    try {
+      response = await handle(request);
+    } catch (error) {
+      const { pathname } = new URL(request.url);
+      console.error(`[${requestId(request)}] ${request.method} ${pathname}`);
+      console.error(error);
+      response = errorResponse(request, 500, "Noe gikk galt. :-(");
+    }
+    try {
+      response.headers.set("x-request-id", requestId(request));
+    } catch (error) {
+      // Immutable headers, e.g. of a proxied response
+    }
+    return response;
+  };
+}
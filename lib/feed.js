@@ -0,0 +1,38 @@
+import { escape } from "./chart.js";
+import { formatDate, formatOre } from "./locale.js";
+import { pageTitle } from "./pages.js";
+import { isoDate } from "./prices.js";
+import { summarize } from "./stats.js";
+
+// Atom feed with one entry per day of prices, newest first
+export function renderFeed(region, days, baseUrl = "") {
+  const available = days
+    .filter(({ chart }) => chart.length > 0)
+    .sort((a, b) => b.date - a.date);
+
+  const entries = available.map(({ date, chart }) => {
+      const { min, avg, max } = summarize(chart.map((item) => item.price));
+      const day = isoDate(date);
+      const [year, month, dayOfMonth] = day.split("-");
+      const path = `${year}/${parseInt(month)}/${parseInt(dayOfMonth)}/${region}`;
+      return `  <entry>
+    <id>urn:elektron:${region}:${day}</id>
+    <title>Strømpriser ${formatDate(day)} i ${region}</title>
+    <updated>${chart[0].time}</updated>
+    <link href="${escape(baseUrl)}/prices/${path}"/>
+    <summary>Min.: ${formatOre(min)} Gjn.: ${formatOre(avg)} Maks: ${formatOre(max)} (øre/kWh)</summary>
+    <content type="html">${escape(`<img src="${baseUrl}/chart/${path}.svg" alt="Strømpriser ${day}"/>`)}</content>
+  </entry>`;
+    });
+
+  const updated = available.length > 0 ? available[0].chart[0].time : null;
+  return `<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <id>urn:elektron:${region}</id>
+  <title>${escape(pageTitle(`strømpriser i ${region}`))}</title>
+  <updated>${updated || new Date().toISOString()}</updated>
+  <link href="${escape(baseUrl)}/"/>
+${entries.join("\n")}
+</feed>
+`;
+}
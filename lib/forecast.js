@@ -0,0 +1,161 @@
+import { config } from "./config.js";
+import { isoDate, loadPrices } from "./prices.js";
+import { average } from "./stats.js";
+import { formatLocal } from "./time.js";
+
+// Days of history behind the level, the hourly profile and the weekday profile
+const LEVEL_DAYS = 14;
+const PROFILE_DAYS = 90;
+
+function groupAverages(chart, key) {
+  const groups = new Map();
+  for (const item of chart) {
+    const group = key(item);
+    if (!groups.has(group)) groups.set(group, []);
+    groups.get(group).push(item.price);
+  }
+  return new Map(
+    [...groups].map(([group, prices]) => [group, average(prices)]),
+  );
+}
+
+const weekday = (item) => new Date(item.time.slice(0, 10)).getUTCDay();
+
+// Naive seasonal estimate for `days` days starting at `from`: the recent price
+// level plus the typical hour-of-day and weekday deviations from stored history
+export function forecastPrices(region, from, days) {
+  const to = new Date(from);
+  to.setDate(to.getDate() - 1);
+  const profileStart = new Date(from);
+  profileStart.setDate(profileStart.getDate() - PROFILE_DAYS);
+  const levelStart = new Date(from);
+  levelStart.setDate(levelStart.getDate() - LEVEL_DAYS);
+
+  const history = loadPrices(region, profileStart, to);
+  const recent = history.filter((item) => item.time >= isoDate(levelStart));
+  if (recent.length === 0) return [];
+
+  const level = average(recent.map((item) => item.price));
+  const overall = average(history.map((item) => item.price));
+  const hours = groupAverages(history, (item) => item.hour);
+  const weekdays = groupAverages(history, weekday);
+
+  const chart = [];
+  for (let offset = 0; offset < days; offset++) {
+    for (let hour = 0; hour < 24; hour++) {
+      const start = new Date(from);
+      start.setDate(start.getDate() + offset);
+      start.setHours(hour, 0, 0, 0);
+
+      const price =
+        level +
+        (hours.get(hour) ?? overall) -
+        overall +
+        (weekdays.get(start.getDay()) ?? overall) -
+        overall;
+      chart.push({
+        hour,
+        price,
+        time: formatLocal(start),
+        price_nok: price / 100,
+        price_eur: null,
+        source: "forecast",
+      });
+    }
+  }
+  return chart;
+}
+
+// The first day without published prices in `chart`
+export function firstUnpublished(chart) {
+  const last = chart[chart.length - 1];
+  const date = new Date(last.time.slice(0, 10) + "T00:00");
+  date.setDate(date.getDate() + 1);
+  return date;
+}
+
+// Conversion of the provider's unit to øre/kWh
+const UNITS = {
+  "øre/kWh": 1,
+  "NOK/kWh": 100,
+  "NOK/MWh": 0.1,
+};
+
+const providerCache = new Map();
+const PROVIDER_TTL = 60 * 60 * 1000;
+
+// Prices from the configured external forecast provider (`forecast.provider`):
+// a JSON list (or `{ prices: [...] }`) of `{ time, price }` in `unit`, fetched
+// from `url` with `{region}` substituted and cached for an hour
+export async function providerForecast(region) {
+  const provider = config.forecast?.provider;
+  if (!provider?.url) return [];
+
+  const cached = providerCache.get(region);
+  if (cached && Date.now() - cached.fetched < PROVIDER_TTL) return cached.chart;
+
+  const response = await fetch(provider.url.replace("{region}", region), {
+    headers: provider.headers || {},
+  });
+  if (!response.ok) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+
+  const body = await response.json();
+  const factor = UNITS[provider.unit || "NOK/kWh"] ?? 100;
+  const chart = (Array.isArray(body) ? body : body.prices || []).map(
+    (item) => {
+      const time = formatLocal(item.time ?? item.time_start ?? item.start);
+      const price = item.price * factor;
+      return {
+        hour: parseInt(time.split("T")[1]),
+        price,
+        time,
+        price_nok: price / 100,
+        price_eur: null,
+        source: "forecast",
+        provider: provider.name || new URL(provider.url).hostname,
+      };
+    },
+  );
+
+  providerCache.set(region, { chart, fetched: Date.now() });
+  return chart;
+}
+
+// Forecast for `days` days from `from`: the provider's prices where it has
+// them, the naive seasonal estimate after that
+export async function forecast(region, from, days) {
+  const until = new Date(from);
+  until.setDate(until.getDate() + days);
+
+  let external = [];
+  try {
+    external = (await providerForecast(region)).filter((item) => {
+      const time = new Date(item.time);
+      return time >= from && time < until;
+    });
+  } catch (error) {
+    console.error(`Forecast provider failed: ${error.message}`);
+  }
+
+  const covered = external.length
+    ? Date.parse(external[external.length - 1].time)
+    : -Infinity;
+  return [
+    ...external,
+    ...forecastPrices(region, from, days).filter(
+      (item) => Date.parse(item.time) > covered,
+    ),
+  ];
+}
+
+// `chart` (tagged as day-ahead) followed by `days` days of forecast
+export async function extendHorizon(region, chart, days) {
+  const published = chart.map((item) => ({ source: "day-ahead", ...item }));
+  if (days <= 0 || chart.length === 0) return published;
+  return [
+    ...published,
+    ...(await forecast(region, firstUnpublished(chart), days)),
+  ];
+}
@@ -0,0 +1,67 @@
+import { encode as encodeMsgpack } from "@msgpack/msgpack";
+import { encode as encodeCbor } from "cbor-x";
+
+const MSGPACK = [
+  "application/msgpack",
+  "application/x-msgpack",
+  "application/vnd.msgpack",
+];
+const CBOR = ["application/cbor"];
+
+// Serialize `data` as JSON, MessagePack or CBOR depending on the `Accept` header
+export function negotiate(request, data, init = {}) {
+  const accept = (request.headers.get("accept") || "").toLowerCase();
+  const headers = { ...init.headers, vary: "accept" };
+
+  if (MSGPACK.some((type) => accept.includes(type))) {
+    return new Response(encodeMsgpack(data), {
+      ...init,
+      headers: { ...headers, "content-type": "application/msgpack" },
+    });
+  }
+  if (CBOR.some((type) => accept.includes(type))) {
+    return new Response(encodeCbor(data), {
+      ...init,
+      headers: { ...headers, "content-type": "application/cbor" },
+    });
+  }
+  return Response.json(data, { ...init, headers });
+}
+
+export const FIELDS = [
+  "hour",
+  "price",
+  "time",
+  "price_nok",
+  "price_eur",
+  "percentile_day",
+  "percentile_30d",
+  "trend_previous",
+  "trend_yesterday",
+  "currency",
+  "price_currency",
+];
+
+// Apply `?fields=hour,price` and `?compact=true` to a price list; `{ data }` or `{ message }`
+export function project(chart, query) {
+  const fields = query.fields ? query.fields.split(",") : FIELDS;
+  const unknown = fields.filter((field) => !FIELDS.includes(field));
+  if (unknown.length > 0) {
+    return {
+      message: `Unknown field(s): ${unknown.join(", ")}. Available: ${FIELDS.join(", ")}`,
+    };
+  }
+
+  // Rows in the order the fields were requested
+  if (query.compact === "true") {
+    return { data: chart.map((item) => fields.map((field) => item[field])) };
+  }
+  if (!query.fields) {
+    return { data: chart };
+  }
+  return {
+    data: chart.map((item) =>
+      Object.fromEntries(fields.map((field) => [field, item[field]])),
+    ),
+  };
+}
@@ -0,0 +1,58 @@
+import { cp, mkdir, writeFile } from "fs/promises";
+import { dirname, join } from "path";
+import { REGIONS } from "./prices.js";
+
+const PUBLIC = join(dirname(new URL(import.meta.url).pathname), "..", "public");
+
+// Render today's and tomorrow's pages, JSON, charts and feeds to static files
+export async function generate(
+  app,
+  { out = "./site", baseUrl = "http://localhost" } = {},
+) {
+  const paths = [
+    "/",
+    "/prices",
+    "/favicon.svg",
+    "/favicon.ico",
+    "/robots.txt",
+    "/sitemap.xml",
+  ];
+
+  for (const offset of [0, 1]) {
+    const date = new Date();
+    date.setDate(date.getDate() + offset);
+    const day = `${date.getFullYear()}/${date.getMonth() + 1}/${date.getDate()}`;
+    for (const region of REGIONS) {
+      paths.push(`/prices/${day}/${region}`, `/chart/${day}/${region}.svg`);
+    }
+  }
+  for (const region of REGIONS) {
+    paths.push(`/feed/${region}.xml`);
+  }
+
+  let written = 0;
+  for (const path of paths) {
+    const response = await app.handle(new Request(baseUrl + path));
+    if (!response.ok) {
+      // Tomorrow's prices are not published before ~13:00
+      console.warn(`Skipping ${path} (${response.status})`);
+      continue;
+    }
+
+    // `/prices` is also the parent directory of the per-day files, so it is
+    // written as an index file which static hosts serve for the directory
+    const file = join(
+      out,
+      path === "/" || path === "/prices" ? join(path, "index.html") : path,
+    );
+    await mkdir(dirname(file), { recursive: true });
+    await writeFile(file, Buffer.from(await response.arrayBuffer()));
+    written++;
+  }
+
+  // The stylesheet references the fonts under `/fonts/`
+  await cp(PUBLIC, out, { recursive: true });
+  await cp(join(PUBLIC, "font"), join(out, "fonts"), { recursive: true });
+
+  console.log(`Wrote ${written} files to ${out}`);
+}
@@ -0,0 +1,81 @@
+import { isoDate } from "./prices.js";
+
+const API = "https://driftsdata.statnett.no/restapi/ProductionConsumption";
+const TTL = 5 * 60 * 1000;
+
+const SECTIONS = {
+  production: "ProductionData",
+  consumption: "ConsumptionData",
+  net_exchange: "NetExchangeData",
+  hydro: "HydroData",
+  wind: "WindData",
+  thermal: "ThermalData",
+  nuclear: "NuclearData",
+  not_specified: "NotSpecifiedData",
+};
+
+let overview = null;
+const seriesCache = new Map();
+
+// "12 345" (with thin/non-breaking spaces) or "-" to MW
+function megawatts(value) {
+  const number = parseFloat(String(value ?? "").replace(/[\s  ]/g, ""));
+  return Number.isNaN(number) ? null : number;
+}
+
+async function get(url) {
+  const response = await fetch(url);
+  if (!response.ok) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+  return response.json();
+}
+
+// Latest production, consumption and exchange (MW) for `region` from Statnett
+export async function gridOverview(region) {
+  if (!overview || Date.now() - overview.fetched > TTL) {
+    overview = {
+      body: await get(`${API}/GetLatestDetailedOverview`),
+      fetched: Date.now(),
+    };
+  }
+
+  // Each data section has one column per area, in the order of the headers
+  const { body } = overview;
+  const column = (body.Headers || []).findIndex((header) =>
+    String(header.titleTranslationId || header.value).endsWith(region),
+  );
+  if (column === -1) return null;
+
+  const figures = Object.fromEntries(
+    Object.entries(SECTIONS).map(([name, section]) => [
+      name,
+      megawatts(body[section]?.[column]?.value),
+    ]),
+  );
+  return {
+    region,
+    unit: "MW",
+    measured_at: body.MeasuredAt ?? null,
+    ...figures,
+  };
+}
+
+// Norway's hourly production and consumption (MW) on `date`
+export async function gridSeries(date) {
+  const day = isoDate(date);
+  const cached = seriesCache.get(day);
+  if (cached && Date.now() - cached.fetched < TTL) return cached.series;
+
+  const body = await get(`${API}/GetData?From=${day}`);
+  const start = body.StartPointUTC;
+  const tick = body.PeriodTickMs || 3600000;
+  const series = (body.Production || []).map((production, i) => ({
+    time: new Date(start + i * tick).toISOString(),
+    production,
+    consumption: body.Consumption?.[i] ?? null,
+  }));
+
+  seriesCache.set(day, { series, fetched: Date.now() });
+  return series;
+}
@@ -0,0 +1,35 @@
+import { config } from "./config.js";
+import { LEVELS, priceLevel } from "./prices.js";
+
+export function heatPumpSettings(overrides = {}) {
+  return {
+    setpoint: 21, // °C
+    min: 19, // Comfort bounds the suggested setpoint stays within
+    max: 23,
+    step: 1, // Offset per price level away from normal
+    ...config.heat_pump,
+    ...overrides,
+  };
+}
+
+// Suggested setpoint offset per interval: warmer in cheap hours (preheating),
+// cooler in expensive ones, clamped to the comfort bounds
+export function setpointHints(chart, settings) {
+  const prices = chart.map((item) => item.price);
+  const normal = LEVELS.indexOf("normal");
+
+  return chart.map((item) => {
+    const level = priceLevel(item.price, prices);
+    const wanted =
+      settings.setpoint + (normal - LEVELS.indexOf(level)) * settings.step;
+    const setpoint = Math.min(settings.max, Math.max(settings.min, wanted));
+    return {
+      hour: item.hour,
+      time: item.time,
+      price: item.price,
+      level,
+      offset: setpoint - settings.setpoint,
+      setpoint,
+    };
+  });
+}
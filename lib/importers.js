@@ -0,0 +1,153 @@
+import { storeConsumption } from "./consumption.js";
+import { REGIONS, storePrices } from "./prices.js";
+import { rateOn } from "./rates.js";
+import { formatLocal, fromLocal } from "./time.js";
+
+// Split CSV text into rows of fields, honouring quotes; `;` or `,` separated
+function parseCsv(text) {
+  const lines = text.replace(/^\uFEFF/, "").trim().split(/\r?\n/);
+  const separator = lines[0].includes(";") ? ";" : ",";
+  const rows = lines.map((line) => {
+    const fields = [];
+    let field = "";
+    let quoted = false;
+    for (let i = 0; i < line.length; i++) {
+      const char = line[i];
+      if (quoted && char === '"' && line[i + 1] === '"') {
+        field += '"';
+        i++;
+      } else if (char === '"') {
+        quoted = !quoted;
+      } else if (char === separator && !quoted) {
+        fields.push(field.trim());
+        field = "";
+      } else {
+        field += char;
+      }
+    }
+    fields.push(field.trim());
+    return fields;
+  });
+  return { header: rows[0], rows: rows.slice(1), separator };
+}
+
+// A number that may use a decimal comma (in `;` separated files)
+function number(text, separator) {
+  const value = separator === ";" ? text.replace(",", ".") : text;
+  return value === "" ? NaN : Number(value);
+}
+
+// ISO timestamps as they are, and `DD.MM.YYYY HH:MM` as Norwegian local time
+function timestamp(text) {
+  const local = /^(\d{2})\.(\d{2})\.(\d{4})[ T](\d{2}):(\d{2})/.exec(text);
+  const time = local
+    ? fromLocal(`${local[3]}-${local[2]}-${local[1]}T${local[4]}:${local[5]}`)
+    : Date.parse(text);
+  return isNaN(time) ? null : formatLocal(time);
+}
+
+// Index of the first header matching `pattern`, or throw naming `what`
+function column(header, pattern, what) {
+  const index = header.findIndex((name) => pattern.test(name));
+  if (index === -1) throw new Error(`No ${what} column in the CSV header`);
+  return index;
+}
+
+// Consumption rows of a CSV with a start time and a kWh column
+function consumptionCsv(text, timePattern, kwhPattern) {
+  const { header, rows, separator } = parseCsv(text);
+  const time = column(header, timePattern, "start time");
+  const kwh = column(header, kwhPattern, "kWh");
+  return rows.map((row, i) => {
+    const parsed = {
+      time: timestamp(row[time] || ""),
+      kwh: number(row[kwh] || "", separator),
+    };
+    if (!parsed.time || !Number.isFinite(parsed.kwh)) {
+      throw new Error(`Invalid row ${i + 2}: ${row.join(separator)}`);
+    }
+    return parsed;
+  });
+}
+
+// Importers for other tools' exports, by `format`. Each parses the file and
+// stores it as prices (needs `region`, and converts with `eur_nok` or the
+// stored ECB rate of each day) or consumption (needs `location`)
+export const IMPORTERS = {
+  // Tibber's consumption export: `from,to,consumption,...`
+  tibber: {
+    kind: "consumption",
+    parse: (text) => consumptionCsv(text, /^from$/i, /^consumption$/i),
+  },
+  // Elhub's metering values ("måleverdier"): `Fra;Til;KWH 60 Forbruk;...`
+  elhub: {
+    kind: "consumption",
+    parse: (text) =>
+      consumptionCsv(text, /^(fra|from)/i, /(kwh|volum|forbruk)/i),
+  },
+  // ENTSO-E Transparency day-ahead prices:
+  // `"MTU (CET/CEST)","Day-ahead Price [EUR/MWh]",...`
+  entsoe: {
+    kind: "prices",
+    parse: (text, { eurNok }) => {
+      const { header, rows, separator } = parseCsv(text);
+      const mtu = column(header, /^MTU/i, "MTU");
+      const price = column(header, /price/i, "price");
+      return rows.flatMap((row, i) => {
+        // Hours without a price (e.g. the skipped DST hour) are blank
+        if (!row[price] || row[price] === "-") return [];
+        const time = timestamp(row[mtu].split(" - ")[0]);
+        const eurMwh = number(row[price], separator);
+        if (!time || !Number.isFinite(eurMwh)) {
+          throw new Error(`Invalid row ${i + 2}: ${row.join(separator)}`);
+        }
+        const eur = eurMwh / 1000;
+        const rate = eurNok(time);
+        if (!rate) throw new Error(`No EUR/NOK rate for ${time.slice(0, 10)}`);
+        return [
+          {
+            hour: parseInt(time.slice(11, 13)),
+            time,
+            price: eur * rate * 100,
+            price_nok: eur * rate,
+            price_eur: eur,
+          },
+        ];
+      });
+    },
+  },
+};
+
+// Parse and store an export; `{ kind, rows, from, to }` or throws
+export function importFile(format, text, { region, location, eur_nok } = {}) {
+  const importer = IMPORTERS[format];
+  if (!importer) {
+    throw new Error(
+      `format must be one of: ${Object.keys(IMPORTERS).join(", ")}`,
+    );
+  }
+  if (importer.kind === "prices") {
+    if (!REGIONS.includes(region)) throw new Error("Region must be NO1-NO5");
+    if (eur_nok !== undefined && !(eur_nok > 0)) {
+      throw new Error("eur_nok must be a positive EUR/NOK rate");
+    }
+  } else if (!location) {
+    throw new Error("location must be given for consumption imports");
+  }
+
+  const eurNok = (time) =>
+    eur_nok ?? rateOn("NOK", new Date(time.slice(0, 10) + "T00:00"));
+  const rows = importer.parse(text, { eurNok });
+  if (rows.length === 0) throw new Error("The file has no rows");
+  if (importer.kind === "prices") {
+    storePrices(region, rows, format);
+  } else {
+    storeConsumption(location, rows);
+  }
+  return {
+    kind: importer.kind,
+    rows: rows.length,
+    from: rows[0].time,
+    to: rows[rows.length - 1].time,
+  };
+}
@@ -0,0 +1,51 @@
+import { config } from "./config.js";
+import { getPrices } from "./prices.js";
+
+function escapeTag(value) {
+  return String(value).replace(/[ ,=]/g, (c) => `\\${c}`);
+}
+
+// Line protocol for a list of prices, one point per interval (second precision)
+export function toLineProtocol(measurement, region, chart) {
+  return chart
+    .map(
+      (item) =>
+        `${escapeTag(measurement)},region=${escapeTag(region)} ` +
+        `price_nok=${item.price_nok},price_eur=${item.price_eur} ` +
+        Math.floor(Date.parse(item.time) / 1000),
+    )
+    .join("\n");
+}
+
+// Write today's (and tomorrow's, when published) prices to the configured endpoint
+export async function pushPrices() {
+  const { url, token, measurement = "electricity_price" } = config.influx;
+  const regions = config.influx.regions || ["NO2"];
+
+  const lines = [];
+  for (const offset of [0, 1]) {
+    const date = new Date();
+    date.setDate(date.getDate() + offset);
+    for (const region of regions) {
+      try {
+        const chart = await getPrices(date, region);
+        lines.push(toLineProtocol(measurement, region, chart));
+      } catch (error) {
+        // Tomorrow's prices are not published before ~13:00
+      }
+    }
+  }
+  if (lines.length === 0) return;
+
+  const response = await fetch(url, {
+    method: "POST",
+    headers: {
+      "content-type": "text/plain; charset=utf-8",
+      ...(token ? { authorization: `Token ${token}` } : {}),
+    },
+    body: lines.join("\n"),
+  });
+  if (!response.ok) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+}
@@ -0,0 +1,108 @@
+import { checkBudgets } from "./budget.js";
+import { updateCharger } from "./charger.js";
+import { config, reloadConfig } from "./config.js";
+import { recheckRecent } from "./corrections.js";
+import { pushPrices } from "./influx.js";
+import { REGIONS, getPrices, isoDate } from "./prices.js";
+import { refreshRates } from "./rates.js";
+import { compact } from "./retention.js";
+import { runRules } from "./rules.js";
+import { clearJobs, schedule } from "./scheduler.js";
+import { sendSummary, summarySettings } from "./summary.js";
+import { HOUR, MINUTE } from "./time.js";
+import { publishWaterHeater } from "./waterheater.js";
+
+// Fetch (and store) `date` for every region, failing if any region is missing
+async function fetchAll(date) {
+  const missing = [];
+  for (const region of REGIONS) {
+    try {
+      await getPrices(date, region);
+    } catch (error) {
+      missing.push(region);
+    }
+  }
+  if (missing.length > 0) {
+    throw new Error(`No prices for ${missing.join(", ")}`);
+  }
+}
+
+// Tomorrow's prices, as soon as they are published (~13:00)
+async function pollDayAhead() {
+  const tomorrow = new Date();
+  tomorrow.setDate(tomorrow.getDate() + 1);
+  await fetchAll(tomorrow);
+}
+
+// Fill in any days missing from the last `backfill_days` days; a day that
+// can't be fetched doesn't keep the days before it from being filled in
+async function backfill() {
+  const failed = [];
+  const date = new Date();
+  for (let day = 0; day < config.backfill_days; day++) {
+    date.setDate(date.getDate() - 1);
+    try {
+      await fetchAll(date);
+    } catch (error) {
+      failed.push(`${isoDate(date)} (${error.message})`);
+    }
+  }
+  if (failed.length > 0) {
+    throw new Error(`Could not backfill ${failed.join(", ")}`);
+  }
+}
+
+// Register the background jobs enabled by the configuration
+export function startJobs() {
+  clearJobs();
+  schedule("day-ahead", { at: ["13:00", "13:30", "14:00", "15:00"] }, () =>
+    pollDayAhead(),
+  );
+  schedule("backfill", { at: ["03:00"] }, () => backfill());
+  schedule("recheck", { at: ["04:00", "16:00"] }, () => recheckRecent());
+  // The last 90 days, so a stopped install catches up on its own
+  schedule("exchange-rates", { every: 6 * HOUR }, () => refreshRates(true));
+
+  if (Object.values(config.locations || {}).some((item) => item.budget)) {
+    schedule("budget", { at: ["07:00"], exclusive: true }, () =>
+      checkBudgets(),
+    );
+  }
+  if (config.retention) {
+    schedule("compaction", { at: ["03:30"] }, () => compact());
+  }
+  if (config.summary) {
+    schedule(
+      "summary",
+      { at: [summarySettings().at], exclusive: true },
+      () => sendSummary(),
+    );
+  }
+  if (config.rules?.length) {
+    schedule("rules", { every: HOUR, exclusive: true }, () => runRules());
+  }
+  if (config.influx?.url) {
+    schedule("influx", { every: HOUR, exclusive: true }, () => pushPrices());
+  }
+  if (config.charger?.type) {
+    schedule("charger", { every: 15 * MINUTE, exclusive: true }, () =>
+      updateCharger(),
+    );
+  }
+  if (config.mqtt?.url && config.water_heater) {
+    schedule("water-heater", { every: HOUR, exclusive: true }, () =>
+      publishWaterHeater(),
+    );
+  }
+}
+
+// Re-read the config file and reschedule the jobs to match; the database is
+// only opened once, so changing its path requires a restart
+export function reload() {
+  const database = config.database;
+  reloadConfig();
+  if (config.database !== database) {
+    console.warn("The database path changes on the next restart");
+  }
+  startJobs();
+}
@@ -0,0 +1,66 @@
+import { config } from "./config.js";
+import { errorResponse } from "./errors.js";
+
+export function limitSettings(overrides = {}) {
+  return {
+    timeout_seconds: 30, // Longest a request may take before a 504
+    upstream_timeout_seconds: 10, // Longest a price fetch may take
+    max_concurrent: 64, // Requests handled at once before 503s
+    ...config.limits,
+    ...overrides,
+  };
+}
+
+let active = 0;
+
+// Long-polls wait longer than the timeout on purpose (up to their own
+// `?timeout=`), so they aren't cut short
+const LONG_POLLS = ["/api/v1/next-change"];
+
+// Wrap a fetch handler with a global concurrency limit and a per-request
+// timeout, so a hung upstream or a burst of clients can't pile up requests on
+// a small machine. Streams (SSE) count only until their response starts, and
+// a request that timed out counts until its handler is done
+export function withLimits(handle) {
+  return async (request) => {
+    const settings = limitSettings();
+    if (active >= settings.max_concurrent) {
+      const response = errorResponse(
+        request,
+        503,
+        "Serveren er opptatt. Prøv igjen om litt.",
+      );
+      response.headers.set("retry-after", "1");
+      return response;
+    }
+
+    active++;
+    const handled = (async () => {
+      try {
+        return await handle(request);
+      } finally {
+        active--;
+      }
+    })();
+    const { pathname } = new URL(request.url);
+    if (LONG_POLLS.some((path) => pathname.endsWith(path))) return handled;
+
+    let timer;
+    const timeout = new Promise((resolve) => {
+      timer = setTimeout(
+        () => resolve(errorResponse(request, 504, "Det tok for lang tid. :-(")),
+        settings.timeout_seconds * 1000,
+      );
+    });
+    try {
+      return await Promise.race([handled, timeout]);
+    } finally {
+      clearTimeout(timer);
+    }
+  };
+}
+
+// Requests being handled right now
+export function activeRequests() {
+  return active;
+}
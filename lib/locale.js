@@ -0,0 +1,93 @@
+import { config } from "./config.js";
+import { formatLocal } from "./time.js";
+
+// How numbers are written for people: `locale` picks the decimal and
+// thousands separators (a comma and a space for nb-NO), and prices in øre
+// and amounts in kroner get a fixed number of decimals
+export function localeSettings(overrides = {}) {
+  return {
+    locale: "nb-NO",
+    ore_decimals: 1,
+    kroner_decimals: 2,
+    ...config.formatting,
+    ...overrides,
+  };
+}
+
+const formatters = new Map();
+
+function formatter(locale, digits) {
+  const key = `${locale}/${digits}`;
+  if (!formatters.has(key)) {
+    formatters.set(
+      key,
+      new Intl.NumberFormat(locale, {
+        minimumFractionDigits: digits,
+        maximumFractionDigits: digits,
+      }),
+    );
+  }
+  return formatters.get(key);
+}
+
+// `value` with `digits` decimals in the configured locale, or "–" if there
+// is none. What rounds to zero is written without a minus
+export function formatNumber(value, digits = 1) {
+  if (!Number.isFinite(value)) return "–";
+  const rounded = Number(value.toFixed(digits));
+  return formatter(localeSettings().locale, digits).format(rounded || 0);
+}
+
+// A price in øre/kWh, e.g. "12,3"
+export function formatOre(value) {
+  return formatNumber(value, localeSettings().ore_decimals);
+}
+
+// An amount in kroner, e.g. "1 234,50"
+export function formatKroner(value, digits = localeSettings().kroner_decimals) {
+  return formatNumber(value, digits);
+}
+
+// A duration in hours, whole ones without decimals, e.g. "2" or "1,5"
+export function formatHours(hours) {
+  return formatNumber(hours, Number.isInteger(hours) ? 0 : 1);
+}
+
+// A percentage, e.g. "12 %", signed ("+12 %") with `sign`
+export function formatPercent(value, { sign = false, digits = 0 } = {}) {
+  if (!Number.isFinite(value)) return "–";
+  const text = formatNumber(value, digits);
+  return `${sign && Number(value.toFixed(digits)) > 0 ? "+" : ""}${text} %`;
+}
+
+// Norwegian local time of a timestamp (as stored, or anything `Date` takes)
+function local(time) {
+  const stored = /^\d{4}-\d\d-\d\d(T\d\d:\d\d(:\d\d)?([+-]\d\d:\d\d)?)?$/;
+  return typeof time === "string" && stored.test(time)
+    ? time
+    : formatLocal(time);
+}
+
+// Hour of day on the 24-hour clock, e.g. "07:00"
+export function formatHour(hour) {
+  return `${String(hour).padStart(2, "0")}:00`;
+}
+
+// Time of day on the 24-hour clock, e.g. "07:15"
+export function formatClock(time) {
+  return local(time).slice(11, 16);
+}
+
+// A date as DD.MM.YYYY
+export function formatDate(time) {
+  const [year, month, day] = local(time).slice(0, 10).split("-");
+  return `${day}.${month}.${year}`;
+}
+
+// A date and time, e.g. "14.09.2026 07:15"; just the date for a bare date
+export function formatDateTime(time) {
+  const text = local(time);
+  return text.length > 10
+    ? `${formatDate(text)} ${formatClock(text)}`
+    : formatDate(text);
+}
@@ -0,0 +1,15 @@
+import { config } from "./config.js";
+import { REGIONS } from "./prices.js";
+
+// Named locations (e.g. home and hytte) from `locations`, each with a zone,
+// an optional nettleie tariff and an optional consumption source
+export function locations() {
+  return config.locations || {};
+}
+
+// A location by name, or null if it isn't configured with a known zone
+export function getLocation(name) {
+  const location = locations()[name];
+  if (!location || !REGIONS.includes(location.region)) return null;
+  return location;
+}
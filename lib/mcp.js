@@ -0,0 +1,218 @@
+import { createInterface } from "readline";
+import { fullPrices } from "./cost.js";
+import {
+  REGIONS,
+  currentEntry,
+  getHorizon,
+  loadPrices,
+  parsePeriod,
+  priceLevel,
+} from "./prices.js";
+import { cheapestWindow, summarize, windowAt } from "./stats.js";
+import { tariffSettings } from "./tariffs.js";
+import { versionInfo } from "./version.js";
+
+const PROTOCOL_VERSION = "2024-11-05";
+
+const region = {
+  type: "string",
+  enum: REGIONS,
+  description:
+    "Norwegian price zone (NO1 Oslo, NO2 Kristiansand, NO3 Trondheim, " +
+    "NO4 Tromsø, NO5 Bergen); NO2 if not given",
+};
+
+function zone(args) {
+  const value = args.region || "NO2";
+  if (!REGIONS.includes(value)) throw new Error("Region must be NO1-NO5");
+  return value;
+}
+
+function duration(args) {
+  const hours = args.hours ?? 3;
+  if (!(hours > 0 && hours <= 24)) {
+    throw new Error("hours must be between 0 and 24");
+  }
+  return hours;
+}
+
+// Tools offered to Model Context Protocol clients; prices are spot prices in
+// øre/kWh without taxes unless stated otherwise
+export const TOOLS = {
+  current_price: {
+    description:
+      "The spot price right now (øre/kWh, excluding taxes and grid fees) " +
+      "and its level compared to the rest of the day.",
+    inputSchema: { type: "object", properties: { region } },
+    run: async (args) => {
+      const now = new Date();
+      const chart = await getHorizon(zone(args), now);
+      const current = currentEntry(chart, now);
+      if (!current) throw new Error("No price for the current interval");
+      const today = chart.filter(
+        (item) => item.time.slice(0, 10) === current.time.slice(0, 10),
+      );
+      return {
+        time: current.time,
+        price: current.price,
+        level: priceLevel(
+          current.price,
+          today.map((item) => item.price),
+        ),
+      };
+    },
+  },
+  price_stats: {
+    description:
+      "Minimum, average and maximum spot price (øre/kWh) over a period " +
+      "of stored history, e.g. 7d, 30d, 12w, 1y, week or month.",
+    inputSchema: {
+      type: "object",
+      properties: {
+        region,
+        period: { type: "string", description: "Defaults to 30d" },
+      },
+    },
+    run: async (args) => {
+      const period = parsePeriod({ period: args.period }, "30d");
+      if (period.message) throw new Error(period.message);
+      const stats = summarize(
+        loadPrices(zone(args), period.from, period.to).map(
+          (item) => item.price,
+        ),
+      );
+      if (!stats) throw new Error("No stored prices for the period");
+      return stats;
+    },
+  },
+  cheapest_window: {
+    description:
+      "When to start something running for a number of hours (e.g. " +
+      "charging the car or the dishwasher) to pay the least, among the " +
+      "published prices from now on.",
+    inputSchema: {
+      type: "object",
+      properties: {
+        region,
+        hours: { type: "number", description: "Defaults to 3" },
+      },
+    },
+    run: async (args) => {
+      const now = new Date();
+      const hours = duration(args);
+      const chart = await getHorizon(zone(args), now);
+      const best = cheapestWindow(chart, hours, now);
+      if (!best) throw new Error("Not enough published prices ahead");
+      return { hours, ...best, starting_now: windowAt(chart, hours, now) };
+    },
+  },
+  cost_estimate: {
+    description:
+      "Estimated cost (NOK, including elavgift, grid energy rate and VAT) " +
+      "of using a number of kWh spread evenly over some hours, starting " +
+      "now and in the cheapest window.",
+    inputSchema: {
+      type: "object",
+      properties: {
+        region,
+        kwh: { type: "number", description: "Energy used in total" },
+        hours: { type: "number", description: "Defaults to 3" },
+      },
+      required: ["kwh"],
+    },
+    run: async (args) => {
+      if (!(args.kwh > 0)) throw new Error("kwh must be a positive number");
+      const now = new Date();
+      const hours = duration(args);
+      const name = zone(args);
+      const invoice = fullPrices(
+        await getHorizon(name, now),
+        name,
+        tariffSettings(),
+      ).map((item) => ({ ...item, price: item.total }));
+
+      const cost = (window) =>
+        window && {
+          start: window.start,
+          end: window.end,
+          nok: (args.kwh * window.average) / 100,
+        };
+      return {
+        kwh: args.kwh,
+        hours,
+        now: cost(windowAt(invoice, hours, now)),
+        cheapest: cost(cheapestWindow(invoice, hours, now)),
+      };
+    },
+  },
+};
+
+function result(id, value) {
+  return { jsonrpc: "2.0", id, result: value };
+}
+
+function error(id, code, message) {
+  return { jsonrpc: "2.0", id, error: { code, message } };
+}
+
+// Answer one JSON-RPC message; null for notifications, which get no reply
+export async function handleMessage(message) {
+  const { id, method, params = {} } = message || {};
+  if (id === undefined || id === null) return null;
+
+  switch (method) {
+    case "initialize":
+      return result(id, {
+        protocolVersion: PROTOCOL_VERSION,
+        capabilities: { tools: {} },
+        serverInfo: { name: "elektron", version: versionInfo().version },
+      });
+    case "ping":
+      return result(id, {});
+    case "tools/list":
+      return result(id, {
+        tools: Object.entries(TOOLS).map(([name, tool]) => ({
+          name,
+          description: tool.description,
+          inputSchema: tool.inputSchema,
+        })),
+      });
+    case "tools/call": {
+      const tool = TOOLS[params.name];
+      if (!tool) return error(id, -32602, `Unknown tool: ${params.name}`);
+      try {
+        const value = await tool.run(params.arguments || {});
+        return result(id, {
+          content: [{ type: "text", text: JSON.stringify(value, null, 2) }],
+        });
+      } catch (failure) {
+        // Tool failures are results the model can read, not protocol errors
+        return result(id, {
+          content: [{ type: "text", text: failure.message }],
+          isError: true,
+        });
+      }
+    }
+    default:
+      return error(id, -32601, `Method not found: ${method}`);
+  }
+}
+
+// Serve MCP over stdin/stdout, one JSON-RPC message per line
+export async function serveStdio() {
+  const lines = createInterface({ input: process.stdin });
+  for await (const line of lines) {
+    if (!line.trim()) continue;
+    let message;
+    try {
+      message = JSON.parse(line);
+    } catch (failure) {
+      process.stdout.write(
+        JSON.stringify(error(null, -32700, "Parse error")) + "\n",
+      );
+      continue;
+    }
+    const reply = await handleMessage(message);
+    if (reply) process.stdout.write(JSON.stringify(reply) + "\n");
+  }
+}
@@ -0,0 +1,147 @@
+import { config } from "./config.js";
+import { currentEntry, getHorizon } from "./prices.js";
+import { hourly, summarize } from "./stats.js";
+import { HOUR } from "./time.js";
+
+export function modbusSettings(overrides = {}) {
+  return {
+    host: "0.0.0.0",
+    port: 5020, // 502 needs root
+    region: "NO2",
+    scale: 10, // Register value per øre/kWh, i.e. 0.1 øre resolution
+    ...config.modbus,
+    ...overrides,
+  };
+}
+
+// Register value of a missing price
+const MISSING = -32768;
+
+// Start of the 24 registers holding today's hourly prices
+const HOURS = 10;
+
+// Register map (holding and input registers alike, signed 16-bit):
+//   0  current price      5  today's minimum
+//   1  next hour's price  6  today's average
+//   2  current hour       7  today's maximum
+//   3  scale              10-33  today's price per hour (00-23)
+//   4  1 when prices are available
+export async function registers(
+  settings = modbusSettings(),
+  now = new Date(),
+) {
+  const values = new Array(HOURS + 24).fill(0);
+  values[3] = settings.scale;
+  const price = (value) =>
+    value === undefined
+      ? MISSING
+      : Math.max(-32767, Math.min(32767, Math.round(value * settings.scale)));
+
+  const chart = await getHorizon(settings.region, now);
+  const current = currentEntry(chart, now);
+  if (!current) {
+    values.fill(MISSING, HOURS);
+    return values;
+  }
+
+  const today = chart.filter(
+    (item) => item.time.slice(0, 10) === current.time.slice(0, 10),
+  );
+  const hours = hourly(today);
+  const hour = hours.find((item) => item.hour === current.hour);
+  const start = Date.parse(hour.time) + HOUR;
+  const next = chart.filter((item) => {
+    const time = Date.parse(item.time);
+    return time >= start && time < start + HOUR;
+  });
+  const { min, avg, max } = summarize(today.map((item) => item.price));
+
+  values[0] = price(current.price);
+  values[1] = price(summarize(next.map((item) => item.price))?.avg);
+  values[2] = current.hour;
+  values[4] = 1;
+  values[5] = price(min);
+  values[6] = price(avg);
+  values[7] = price(max);
+  for (let i = 0; i < 24; i++) {
+    values[HOURS + i] = price(hours.find((item) => item.hour === i)?.price);
+  }
+  return values;
+}
+
+// Modbus exception response with the transaction id of `header`
+function exception(header, unit, code, error) {
+  const frame = new DataView(new ArrayBuffer(9));
+  frame.setUint16(0, header.getUint16(0));
+  frame.setUint16(4, 3);
+  frame.setUint8(6, unit);
+  frame.setUint8(7, code | 0x80);
+  frame.setUint8(8, error);
+  return new Uint8Array(frame.buffer);
+}
+
+// Answer one Modbus TCP request frame (MBAP header + PDU)
+async function respond(request, settings) {
+  const header = new DataView(request.buffer, request.byteOffset);
+  const unit = header.getUint8(6);
+  const code = header.getUint8(7);
+  // Read holding registers (3) and read input registers (4) only
+  if (code !== 3 && code !== 4) return exception(header, unit, code, 1);
+  if (request.length < 12) return exception(header, unit, code, 3);
+
+  const address = header.getUint16(8);
+  const count = header.getUint16(10);
+  if (count < 1 || count > 125) return exception(header, unit, code, 3);
+  if (address + count > HOURS + 24) return exception(header, unit, code, 2);
+
+  let values;
+  try {
+    values = await registers(settings);
+  } catch (error) {
+    return exception(header, unit, code, 4);
+  }
+
+  const frame = new DataView(new ArrayBuffer(9 + 2 * count));
+  frame.setUint16(0, header.getUint16(0));
+  frame.setUint16(4, 3 + 2 * count);
+  frame.setUint8(6, unit);
+  frame.setUint8(7, code);
+  frame.setUint8(8, 2 * count);
+  for (let i = 0; i < count; i++) {
+    frame.setInt16(9 + 2 * i, values[address + i]);
+  }
+  return new Uint8Array(frame.buffer);
+}
+
+// Modbus TCP server exposing the prices of `config.modbus.region` as
+// registers, for PLCs and the like; started with the server when
+// `config.modbus` is set
+export function startModbus(settings = modbusSettings()) {
+  return Bun.listen({
+    hostname: settings.host,
+    port: settings.port,
+    socket: {
+      open(socket) {
+        socket.data = new Uint8Array(0);
+      },
+      async data(socket, chunk) {
+        // Frames may be split across or share TCP segments
+        const buffer = new Uint8Array(socket.data.length + chunk.length);
+        buffer.set(socket.data);
+        buffer.set(chunk, socket.data.length);
+        const frames = [];
+        let offset = 0;
+        while (buffer.length - offset >= 7) {
+          const length = 6 + ((buffer[offset + 4] << 8) | buffer[offset + 5]);
+          if (buffer.length - offset < length) break;
+          frames.push(buffer.slice(offset, offset + length));
+          offset += length;
+        }
+        socket.data = buffer.slice(offset);
+        for (const frame of frames) {
+          socket.write(await respond(frame, settings));
+        }
+      },
+    },
+  });
+}
@@ -0,0 +1,26 @@
+import mqtt from "mqtt";
+import { config } from "./config.js";
+
+let client = null;
+
+// Shared connection to the broker in `mqtt.url`, opened on first use
+function connect() {
+  if (!client) {
+    const { url, username, password } = config.mqtt;
+    client = mqtt.connect(url, { username, password });
+    client.on("error", (error) =>
+      console.error(`MQTT error: ${error.message}`),
+    );
+  }
+  return client;
+}
+
+// Publish `payload` under `<mqtt.prefix>/<topic>`; objects are sent as JSON
+export function publish(topic, payload, { retain = true } = {}) {
+  if (!config.mqtt?.url) return;
+
+  const prefix = config.mqtt.prefix || "elektron";
+  const message =
+    typeof payload === "string" ? payload : JSON.stringify(payload);
+  connect().publish(`${prefix}/${topic}`, message, { retain });
+}
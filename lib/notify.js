@@ -0,0 +1,199 @@
+import nodemailer from "nodemailer";
+import { escape, renderSvg } from "./chart.js";
+import { config } from "./config.js";
+import { getPrices } from "./prices.js";
+
+const transports = new Map();
+
+// The day's chart of a notification's `chart: { region, date }`, or null
+async function chartFor(notification) {
+  if (!notification.chart) return null;
+  const { region, date } = notification.chart;
+  try {
+    return renderSvg(await getPrices(new Date(`${date}T00:00`), region));
+  } catch (error) {
+    return null;
+  }
+}
+
+// URL of the day's SVG chart on `base_url` (the notifier's, or the global
+// one), or null without either
+function chartLink(notification, options) {
+  const base = options.base_url || config.base_url;
+  if (!base || !notification.chart) return null;
+  const { region, date } = notification.chart;
+  return `${base.replace(/\/$/, "")}/chart/${date.replaceAll("-", "/")}/${region}.svg`;
+}
+
+// Plain-text and HTML bodies of an email, linking to the chart when there is
+// a base URL and embedding it (as the `chart` attachment) when rendered
+function emailBodies(notification, options, svg) {
+  const link = chartLink(notification, options);
+  const text = [notification.message, link].filter(Boolean).join("\n\n");
+
+  const image = svg
+    ? '    <p><img src="cid:chart" alt="Strømpriser" width="800"></p>\n'
+    : "";
+  const anchor = link
+    ? `    <p><a href="${escape(link)}">Se grafen</a></p>\n`
+    : "";
+  const html = `<!DOCTYPE html>
+<html>
+<body style="font-family: 'JetBrains Mono', monospace; color: #1D1C1A;">
+    <h2>${escape(notification.title)}</h2>
+    <p>${escape(notification.message).replaceAll("\n", "<br>")}</p>
+${image}${anchor}</body>
+</html>
+`;
+  return { text, html };
+}
+
+async function postJson(url, body, headers = {}) {
+  const response = await fetch(url, {
+    method: "POST",
+    headers: { "content-type": "application/json", ...headers },
+    body: JSON.stringify(body),
+  });
+  if (!response.ok) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+}
+
+// Notifier implementations, selected by `type` in the `notifiers` config list.
+// Each receives its config entry and `{ title, message, event, data }`, with
+// `chart: { region, date }` for notifications about a day's prices.
+export const NOTIFIERS = {
+  // Generic JSON webhook
+  webhook: async (options, notification) => {
+    await postJson(options.url, notification, options.headers);
+  },
+  // Discord incoming webhook: an embed linking to the chart
+  discord: async (options, notification) => {
+    const link = chartLink(notification, options);
+    await postJson(options.url, {
+      username: options.username || "elektron",
+      embeds: [
+        {
+          title: notification.title,
+          description: notification.message,
+          color: 0x1d1c1a,
+          ...(link && { url: link }),
+        },
+      ],
+    });
+  },
+  // Slack incoming webhook: header, message and a link to the chart
+  slack: async (options, notification) => {
+    const link = chartLink(notification, options);
+    await postJson(options.url, {
+      text: `${notification.title}: ${notification.message}`,
+      blocks: [
+        {
+          type: "header",
+          text: { type: "plain_text", text: notification.title },
+        },
+        {
+          type: "section",
+          text: { type: "mrkdwn", text: notification.message },
+        },
+        ...(link
+          ? [
+              {
+                type: "context",
+                elements: [{ type: "mrkdwn", text: `<${link}|Se grafen>` }],
+              },
+            ]
+          : []),
+      ],
+    });
+  },
+  // Pushover, opening the chart when tapped
+  pushover: async (options, notification) => {
+    const link = chartLink(notification, options);
+    await postJson("https://api.pushover.net/1/messages.json", {
+      token: options.token,
+      user: options.user,
+      title: notification.title,
+      message: notification.message || notification.title,
+      priority: options.priority ?? 0,
+      ...(link && { url: link, url_title: "Se grafen" }),
+    });
+  },
+  // Self-hosted Gotify server at `url` with an application `token`
+  gotify: async (options, notification) => {
+    const link = chartLink(notification, options);
+    await postJson(
+      `${options.url.replace(/\/$/, "")}/message`,
+      {
+        title: notification.title,
+        message: notification.message || notification.title,
+        priority: options.priority ?? 5,
+        ...(link && {
+          extras: { "client::notification": { click: { url: link } } },
+        }),
+      },
+      { "x-gotify-key": options.token },
+    );
+  },
+  // Email over SMTP, with the day's chart when the notification has one
+  smtp: async (options, notification) => {
+    const key = `${options.host}:${options.port}:${options.username}`;
+    if (!transports.has(key)) {
+      transports.set(
+        key,
+        nodemailer.createTransport({
+          host: options.host,
+          port: options.port || 587,
+          secure: options.secure ?? options.port === 465,
+          auth: options.username && {
+            user: options.username,
+            pass: options.password,
+          },
+        }),
+      );
+    }
+
+    const svg = await chartFor(notification);
+    const { text, html } = emailBodies(notification, options, svg);
+    await transports.get(key).sendMail({
+      from: options.from,
+      to: options.to,
+      subject: notification.title,
+      text,
+      html,
+      attachments: svg
+        ? [
+            {
+              filename: `elektron-${notification.chart.date}.svg`,
+              content: svg,
+              contentType: "image/svg+xml",
+              cid: "chart",
+            },
+          ]
+        : [],
+    });
+  },
+};
+
+// Send a notification through every notifier (the configured ones, unless
+// given) that accepts its `event`
+export async function notify(notification, notifiers = config.notifiers) {
+  const accepting = (notifiers || []).filter(
+    (options) => !options.events || options.events.includes(notification.event),
+  );
+
+  await Promise.all(
+    accepting.map(async (options) => {
+      const send = NOTIFIERS[options.type];
+      if (!send) {
+        console.error(`Unknown notifier type: ${options.type}`);
+        return;
+      }
+      try {
+        await send(options, notification);
+      } catch (error) {
+        console.error(`Notifier ${options.type} failed: ${error.message}`);
+      }
+    }),
+  );
+}
@@ -0,0 +1,226 @@
+import { createHash, createHmac, randomBytes, timingSafeEqual } from "crypto";
+import { config } from "./config.js";
+import { db } from "./db.js";
+import { dashboardUrl } from "./sites.js";
+
+// OpenID Connect login, or null if no `oidc.issuer` is configured
+export function oidcSettings(overrides = {}) {
+  const settings = {
+    issuer: null, // e.g. "https://accounts.google.com"
+    client_id: null,
+    client_secret: null,
+    // Where the provider sends the browser back; defaults to
+    // `<dashboard>/auth/callback`, which must be registered with it
+    redirect_uri: null,
+    scopes: "openid email profile",
+    // Emails (or subjects) allowed in; anyone the provider knows if null
+    allowed: null,
+    session_hours: 12,
+    // Key signing the session cookies; defaults to the client secret, or
+    // else a random key kept in the database (so set it for several replicas)
+    session_secret: null,
+    ...config.oidc,
+    ...overrides,
+  };
+  return settings.issuer ? settings : null;
+}
+
+const discovered = new Map();
+
+// The provider's endpoints from its discovery document, fetched once
+async function discover(issuer) {
+  if (!discovered.has(issuer)) {
+    const url = `${issuer.replace(/\/$/, "")}/.well-known/openid-configuration`;
+    const response = await fetch(url);
+    if (!response.ok) {
+      throw new Error(`HTTP error! status: ${response.status}`);
+    }
+    discovered.set(issuer, await response.json());
+  }
+  return discovered.get(issuer);
+}
+
+function base64url(buffer) {
+  return Buffer.from(buffer).toString("base64url");
+}
+
+// A random key for signing cookies, generated once and then kept, for public
+// clients without a secret of their own
+let secret = null;
+
+function storedSecret() {
+  if (!secret) {
+    db()
+      .query("INSERT OR IGNORE INTO secrets (name, value) VALUES ('oidc', ?)")
+      .run(base64url(randomBytes(32)));
+    secret = db()
+      .query("SELECT value FROM secrets WHERE name = 'oidc'")
+      .get().value;
+  }
+  return secret;
+}
+
+function signature(settings, payload) {
+  const key =
+    settings.session_secret || settings.client_secret || storedSecret();
+  return createHmac("sha256", key).update(payload).digest("base64url");
+}
+
+// `value` as JSON with an HMAC, so it can be trusted when it comes back, and
+// its purpose `typ` (`state` or `session`), so one can't pass for the other
+function sign(settings, typ, value) {
+  const payload = base64url(JSON.stringify({ ...value, typ }));
+  return `${payload}.${signature(settings, payload)}`;
+}
+
+// The value of a cookie written by `sign` for `typ`, or null if it was
+// tampered with, has expired or is meant for something else
+function verify(settings, typ, text) {
+  const [payload, mac] = (text || "").split(".");
+  if (!payload || !mac) return null;
+  const expected = Buffer.from(signature(settings, payload));
+  const given = Buffer.from(mac);
+  if (expected.length !== given.length || !timingSafeEqual(expected, given)) {
+    return null;
+  }
+  try {
+    const value = JSON.parse(Buffer.from(payload, "base64url").toString());
+    return value.typ === typ && value.exp > Date.now() / 1000 ? value : null;
+  } catch (error) {
+    return null;
+  }
+}
+
+function cookie(request, name) {
+  const match = new RegExp(`(?:^|;\\s*)${name}=([^;]+)`).exec(
+    request.headers.get("cookie") || "",
+  );
+  return match ? match[1] : null;
+}
+
+function setCookie(request, name, value, seconds) {
+  const secure = new URL(request.url).protocol === "https:" ? "; Secure" : "";
+  return (
+    `${name}=${value}; Path=/; Max-Age=${seconds}; HttpOnly; ` +
+    `SameSite=Lax${secure}`
+  );
+}
+
+function redirectUri(settings, request) {
+  return settings.redirect_uri || `${dashboardUrl(request)}auth/callback`;
+}
+
+// Only paths on this site, so the login can't be used to send people away
+function safeNext(next) {
+  return typeof next === "string" && /^\/(?![/\\])/.test(next) ? next : "/";
+}
+
+// `{ url, cookie }`: the provider's login page to send the browser to, and
+// the state (with PKCE verifier and nonce) to remember until it comes back
+export async function loginRedirect(settings, request, next) {
+  const provider = await discover(settings.issuer);
+  const verifier = base64url(randomBytes(32));
+  const state = {
+    state: base64url(randomBytes(16)),
+    nonce: base64url(randomBytes(16)),
+    verifier,
+    next: safeNext(next),
+    exp: Math.floor(Date.now() / 1000) + 600,
+  };
+
+  const url = new URL(provider.authorization_endpoint);
+  url.search = new URLSearchParams({
+    response_type: "code",
+    client_id: settings.client_id,
+    redirect_uri: redirectUri(settings, request),
+    scope: settings.scopes,
+    state: state.state,
+    nonce: state.nonce,
+    code_challenge: base64url(createHash("sha256").update(verifier).digest()),
+    code_challenge_method: "S256",
+  });
+  return {
+    url: url.href,
+    cookie: setCookie(request, "oidc", sign(settings, "state", state), 600),
+  };
+}
+
+// The claims in the payload of an ID token
+function claims(token) {
+  const [, payload] = (token || "").split(".");
+  if (!payload) throw new Error("No ID token in the response");
+  return JSON.parse(Buffer.from(payload, "base64url").toString());
+}
+
+// Exchange the provider's `?code=` for the user; `{ user, next, cookie }`
+// with the session cookie to set. Throws with what is wrong otherwise
+export async function completeLogin(settings, request, query) {
+  const state = verify(settings, "state", cookie(request, "oidc"));
+  if (!state || state.state !== query.state) {
+    throw new Error("The login expired or was started elsewhere");
+  }
+  if (query.error) throw new Error(query.error_description || query.error);
+
+  const provider = await discover(settings.issuer);
+  const response = await fetch(provider.token_endpoint, {
+    method: "POST",
+    headers: { "content-type": "application/x-www-form-urlencoded" },
+    body: new URLSearchParams({
+      grant_type: "authorization_code",
+      code: query.code || "",
+      redirect_uri: redirectUri(settings, request),
+      client_id: settings.client_id,
+      client_secret: settings.client_secret || "",
+      code_verifier: state.verifier,
+    }),
+  });
+  if (!response.ok) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+
+  // Straight from the token endpoint over TLS, so the signature needn't be
+  // checked (OpenID Connect Core 3.1.3.7); the claims still are
+  const token = claims((await response.json()).id_token);
+  const audience = [token.aud].flat();
+  const now = Date.now() / 1000;
+  if (token.iss !== provider.issuer || !audience.includes(settings.client_id)) {
+    throw new Error("The ID token is for someone else");
+  }
+  if (!(token.exp > now) || token.nonce !== state.nonce) {
+    throw new Error("The ID token has expired or was replayed");
+  }
+  if (
+    settings.allowed &&
+    !settings.allowed.includes(token.email) &&
+    !settings.allowed.includes(token.sub)
+  ) {
+    throw new Error(`${token.email || token.sub} is not allowed in`);
+  }
+
+  const seconds = settings.session_hours * 3600;
+  const user = {
+    sub: token.sub,
+    email: token.email ?? null,
+    name: token.name ?? null,
+    exp: Math.floor(now) + seconds,
+  };
+  return {
+    user,
+    next: state.next,
+    cookie: setCookie(request, "session", sign(settings, "session", user), seconds),
+  };
+}
+
+// The logged in user of `request`, or null
+export function sessionUser(settings, request) {
+  const user = verify(settings, "session", cookie(request, "session"));
+  return typeof user?.sub === "string" ? user : null;
+}
+
+// Cookies logging out: the session and any unfinished login
+export function logoutCookies(request) {
+  return [
+    setCookie(request, "session", "", 0),
+    setCookie(request, "oidc", "", 0),
+  ];
+}
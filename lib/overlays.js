@@ -0,0 +1,36 @@
+import { carbonIntensity } from "./co2.js";
+import { gridSeries } from "./grid.js";
+import { loadTemperatures, refreshTemperatures } from "./weather.js";
+
+const HOUR = 60 * 60 * 1000;
+
+const hourOf = (time) => Math.floor(Date.parse(time) / HOUR) * HOUR;
+
+// Secondary series that can be drawn on top of the price chart
+export const OVERLAYS = {
+  production: { label: "Produksjon NO (MW)", color: "#1F5FAD" },
+  consumption: { label: "Forbruk NO (MW)", color: "#8A5A00" },
+  co2: { label: "gCO₂/kWh", color: "#555555" },
+  temperature: { label: "°C", color: "#AD1F5F" },
+};
+
+// Hourly values for `kind`, keyed by the start of the hour (ms)
+async function hourlyValues(kind, date, region) {
+  if (kind === "co2") return carbonIntensity(region);
+  if (kind === "temperature") {
+    await refreshTemperatures(region).catch(() => {});
+    return loadTemperatures(region, date, date);
+  }
+  return new Map(
+    (await gridSeries(date)).map((item) => [hourOf(item.time), item[kind]]),
+  );
+}
+
+// `{ label, color, values }` aligned with `chart`, for `renderSvg`
+export async function overlayFor(kind, date, region, chart) {
+  const values = await hourlyValues(kind, date, region);
+  return {
+    ...OVERLAYS[kind],
+    values: chart.map((item) => values.get(hourOf(item.time)) ?? null),
+  };
+}
@@ -0,0 +1,129 @@
+import { recordAction } from "./audit.js";
+import { config } from "./config.js";
+import { db } from "./db.js";
+import { forgetShared, sharedGet, sharedSet } from "./redis.js";
+import { hasJob, runJob } from "./scheduler.js";
+
+// Devices the automation switches, which can be forced on or off by hand
+export const DEVICES = ["water_heater", "charger"];
+
+function until(hours, now) {
+  return hours ? new Date(now.getTime() + hours * 3600000).toISOString() : null;
+}
+
+// Stateless replicas don't share a database, so there the overrides are kept
+// in the shared store; whichever replica runs a job sees what another set
+async function set(target, state, hours, now) {
+  const row = { state, until: until(hours, now) };
+  if (config.stateless) return sharedSet(`override:${target}`, row);
+  db()
+    .query(
+      `INSERT INTO overrides (target, state, until) VALUES (?, ?, ?)
+       ON CONFLICT (target) DO UPDATE SET state = excluded.state,
+         until = excluded.until`,
+    )
+    .run(target, row.state, row.until);
+}
+
+async function clear(target) {
+  if (config.stateless) return forgetShared(`override:${target}`);
+  db().query("DELETE FROM overrides WHERE target = ?").run(target);
+}
+
+// `{ state, until }` of `target` while it lasts (no `until`: until cleared),
+// or null
+async function active(target, now) {
+  const row = config.stateless
+    ? await sharedGet(`override:${target}`)
+    : db()
+        .query("SELECT state, until FROM overrides WHERE target = ?")
+        .get(target);
+  if (!row || (row.until && Date.parse(row.until) <= now)) return null;
+  return row;
+}
+
+// The background jobs switching each device
+const JOBS = { water_heater: "water-heater", charger: "charger" };
+
+const expiries = new Map();
+
+// Have `device` follow its override (or its schedule) right away instead of
+// at its job's next run, and again once an override for `hours` runs out
+function apply(device, hours = null) {
+  const job = JOBS[device];
+  clearTimeout(expiries.get(device));
+  expiries.delete(device);
+  if (!hasJob(job)) return;
+
+  runJob(job);
+  if (hours) {
+    expiries.set(
+      device,
+      setTimeout(() => runJob(job), hours * 3600000 + 1000),
+    );
+  }
+}
+
+// Whether `hours` is a valid duration: none (indefinitely) or up to a week
+export function validHours(hours) {
+  return hours == null || (hours > 0 && hours <= 168);
+}
+
+// Stop every rule and device integration for `hours`, or until resumed
+export async function pauseAutomation(hours = null, now = new Date()) {
+  await set("automation", "paused", hours, now);
+  recordAction({
+    source: "manual",
+    action: "pause automation",
+    reason: hours ? `for ${hours} h` : "until resumed",
+  });
+}
+
+export async function resumeAutomation() {
+  await clear("automation");
+  recordAction({ source: "manual", action: "resume automation" });
+}
+
+// `{ until }` while the automation is paused, otherwise null
+export async function automationPaused(now = new Date()) {
+  const row = await active("automation", now);
+  return row && { until: row.until };
+}
+
+// Force `device` on or off for `hours`, or until cleared, whatever the
+// schedule says
+export async function forceDevice(
+  device,
+  on,
+  hours = null,
+  now = new Date(),
+) {
+  await set(device, on ? "on" : "off", hours, now);
+  recordAction({
+    source: "manual",
+    action: `force ${device} ${on ? "on" : "off"}`,
+    reason: hours ? `for ${hours} h` : "until cleared",
+  });
+  apply(device, hours);
+}
+
+export async function clearOverride(device) {
+  await clear(device);
+  recordAction({ source: "manual", action: `clear ${device} override` });
+  apply(device);
+}
+
+// `{ on, until }` while `device` is forced, otherwise null
+export async function deviceOverride(device, now = new Date()) {
+  const row = await active(device, now);
+  return row && { on: row.state === "on", until: row.until };
+}
+
+// Everything overridden right now
+export async function overrideState(now = new Date()) {
+  const devices = {};
+  for (const device of DEVICES) {
+    devices[device] = await deviceOverride(device, now);
+  }
+  return { paused: await automationPaused(now), devices };
+}
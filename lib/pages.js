@@ -0,0 +1,541 @@
+import { escape } from "./chart.js";
+import { config } from "./config.js";
+import {
+  formatClock,
+  formatDate,
+  formatDateTime,
+  formatHour,
+  formatKroner,
+  formatNumber,
+  formatOre,
+  formatPercent,
+} from "./locale.js";
+import { qrPng } from "./qr.js";
+
+export const WEEKDAYS = [
+  "mandag",
+  "tirsdag",
+  "onsdag",
+  "torsdag",
+  "fredag",
+  "lørdag",
+  "søndag",
+];
+
+// schema.org Dataset describing a day of prices in `region` for search
+// engines and link previews, with the day's min., avg. and max. if known
+export function dayDataset({ region, date, url, stats }) {
+  const measure = (name, value) => ({
+    "@type": "PropertyValue",
+    name,
+    value: Math.round(value * 100) / 100,
+    unitText: "øre/kWh",
+  });
+  return {
+    "@context": "https://schema.org",
+    "@type": "Dataset",
+    name: `Strømpriser i ${region} ${date}`,
+    description: `Spotpriser (øre/kWh, uten avgifter) i prisområde ${region} ${date}.`,
+    url,
+    temporalCoverage: date,
+    spatialCoverage: { "@type": "Place", name: `Prisområde ${region}` },
+    isBasedOn: "https://www.hvakosterstrommen.no/strompris-api",
+    creator: { "@type": "Organization", name: brandingSettings().title },
+    isAccessibleForFree: true,
+    distribution: [
+      {
+        "@type": "DataDownload",
+        encodingFormat: "application/json",
+        contentUrl: new URL(
+          `prices/${date.replaceAll("-", "/")}/${region}`,
+          url,
+        ).href,
+      },
+    ],
+    ...(stats && {
+      variableMeasured: [
+        measure("Min.", stats.min),
+        measure("Gjennomsnitt", stats.avg),
+        measure("Maks", stats.max),
+      ],
+    }),
+  };
+}
+
+// `data` as a JSON-LD script element, safe to put in the page
+export function jsonLd(data) {
+  const json = JSON.stringify(data).replace(/</g, "\\u003c");
+  return `<script type="application/ld+json">${json}</script>`;
+}
+
+// How a deployment labels itself, e.g. for a family or an office
+export function brandingSettings(overrides = {}) {
+  return {
+    title: "elektron", // In the title of every page
+    header: null, // Shown first in the page header
+    accent: null, // Colour of the header, chart frame and banners
+    logo: null, // URL of an image shown first in the page header
+    ...config.branding,
+    ...overrides,
+  };
+}
+
+// Title of a page: the deployment's, followed by `suffix` if given
+export function pageTitle(suffix = null) {
+  const { title } = brandingSettings();
+  return suffix ? `${title} – ${suffix}` : title;
+}
+
+// Style element setting the accent colour, if one is configured; only plain
+// colour values, so the config can't close the element
+export function brandStyle() {
+  const { accent } = brandingSettings();
+  if (!accent || !/^[#\w(),.%\s]+$/.test(accent)) return "";
+  return `    <style>:root { --accent: ${accent}; }</style>\n`;
+}
+
+// The logo and header text for the start of the page header, if configured
+export function brandHeader() {
+  const { header, logo } = brandingSettings();
+  return [
+    logo && `<img id="headerLogo" src="${escape(logo)}" alt="">`,
+    header && `<span id="headerBrand">${escape(header)}</span>`,
+  ]
+    .filter(Boolean)
+    .map((element) => `        ${element}\n`)
+    .join("");
+}
+
+// Shared page shell for the server-rendered pages, titled `title` after the
+// deployment's name
+export function layout({ title, header, body }) {
+  return `<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <meta name="description" content="Hold styr på strømprisene i Norge.">
+    <title>${escape(pageTitle(title))}</title>
+    <base href="/">
+    <link rel="icon" href="favicon.svg" type="image/svg+xml">
+    <link rel="stylesheet" href="style.css">
+${brandStyle()}</head>
+<body>
+    <div id="header">
+${brandHeader()}        <span id="headerTitle">${escape(header)}</span>
+    </div>
+${body}
+</body>
+</html>
+`;
+}
+
+function percent(value) {
+  return formatPercent(value, { sign: true });
+}
+
+// Seasonality tables: average price per weekday and per hour of day
+export function renderSeasonality(region, result) {
+  if (!result) {
+    return layout({
+      title: "statistikk",
+      header: `Statistikk for ${region}`,
+      body: '    <div class="error">Hmm. Ingen data.</div>',
+    });
+  }
+
+  const cheapestDay = result.weekdays.reduce((a, b) =>
+    b.average < a.average ? b : a,
+  );
+  const cheapestHour = result.hours.reduce((a, b) =>
+    b.average < a.average ? b : a,
+  );
+
+  const weekdayRows = result.weekdays
+    .map(
+      (item) =>
+        `            <tr><td>${WEEKDAYS[item.weekday - 1]}</td><td>${formatOre(item.average)}</td><td>${percent(item.relative)}</td></tr>`,
+    )
+    .join("\n");
+  const hourRows = result.hours
+    .map(
+      (item) =>
+        `            <tr><td>${formatHour(item.hour)}</td><td>${formatOre(item.average)}</td><td>${percent(item.relative)}</td></tr>`,
+    )
+    .join("\n");
+
+  return layout({
+    title: "statistikk",
+    header: `Statistikk (øre/kWh) for ${region}`,
+    body: `    <div id="statistics">
+        <span>Gjn.: ${formatOre(result.average)}</span>
+        <span>Billigste dag: ${WEEKDAYS[cheapestDay.weekday - 1]} (${percent(cheapestDay.relative)})</span>
+        <span>Billigste time: ${formatHour(cheapestHour.hour)}</span>
+    </div>
+    <table class="stats-table">
+        <thead><tr><th>Ukedag</th><th>Gjn.</th><th>Avvik</th></tr></thead>
+        <tbody>
+${weekdayRows}
+        </tbody>
+    </table>
+    <table class="stats-table">
+        <thead><tr><th>Time</th><th>Gjn.</th><th>Avvik</th></tr></thead>
+        <tbody>
+${hourRows}
+        </tbody>
+    </table>`,
+  });
+}
+
+// Error page in the same style, with a way back to the prices
+export function renderError(status, title, detail) {
+  return layout({
+    title: String(status),
+    header: `${status} ${title}`,
+    body: `    <div class="error">${escape(detail)}</div>
+    <a class="nav-button" href=".">◀ Til strømprisene</a>`,
+  });
+}
+
+function format(value, digits = 1) {
+  return formatNumber(value, digits);
+}
+
+// Overview of every configured location: today's prices and consumption
+export function renderLocations(rows) {
+  if (rows.length === 0) {
+    return layout({
+      title: "steder",
+      header: "Steder",
+      body: '    <div class="error">Ingen steder er satt opp (locations).</div>',
+    });
+  }
+
+  const tableRows = rows
+    .map(
+      (row) =>
+        `            <tr><td>${escape(row.name)}</td><td>${row.region}</td><td>${format(row.current)}</td><td>${format(row.average)}</td><td>${format(row.kwh)}</td><td>${formatKroner(row.cost)}</td></tr>`,
+    )
+    .join("\n");
+
+  return layout({
+    title: "steder",
+    header: "Strømpris med nettleie og avgifter (øre/kWh) i dag",
+    body: `    <table class="stats-table">
+        <thead><tr><th>Sted</th><th>Sone</th><th>Nå</th><th>Gjn.</th><th>kWh</th><th>Kostnad (kr)</th></tr></thead>
+        <tbody>
+${tableRows}
+        </tbody>
+    </table>`,
+  });
+}
+
+const DEVICE_NAMES = {
+  water_heater: "Varmtvannsbereder",
+  charger: "Elbillader",
+};
+
+// Safety valves for the automation: pause it, force a device on or off for a
+// while, dry-run a day and see what was done. Holds no data itself; the
+// controls call the admin API with the login, or the admin token entered
+export function renderAdmin(devices) {
+  const controls = devices
+    .map(
+      (device) => `        <tr><td>${DEVICE_NAMES[device] || device}</td><td id="override-${device}">–</td><td>
+            <button class="nav-button" onclick="force('${device}', true)">På</button>
+            <button class="nav-button" onclick="force('${device}', false)">Av</button>
+            <button class="nav-button" onclick="clearForce('${device}')">Etter plan</button>
+        </td></tr>`,
+    )
+    .join("\n");
+
+  return layout({
+    title: "admin",
+    header: "Styring",
+    body: `    <div class="admin-row">
+        <label>Admin-token <input id="token" type="password" autocomplete="off"></label>
+        <label>Timer <input id="hours" type="number" min="0.5" max="168" step="0.5" value="2"></label>
+    </div>
+    <div class="admin-row">
+        <span id="paused">–</span>
+        <button class="nav-button" onclick="post('admin/automation/pause', { hours: hours() })">Pause</button>
+        <button class="nav-button" onclick="post('admin/automation/resume')">Fortsett</button>
+    </div>
+    <table class="stats-table">
+        <thead><tr><th>Enhet</th><th>Overstyrt</th><th></th></tr></thead>
+        <tbody>
+${controls}
+        </tbody>
+    </table>
+    <div class="admin-row">
+        <input id="simulateDate" type="date">
+        <button class="nav-button" onclick="simulate()">Simuler dagen</button>
+    </div>
+    <pre id="simulation" class="admin-output"></pre>
+    <table class="stats-table">
+        <thead><tr><th>Tid</th><th>Kilde</th><th>Handling</th><th>Hvorfor</th><th>Resultat</th></tr></thead>
+        <tbody id="audit"></tbody>
+    </table>
+    <script>
+        const tokenField = document.getElementById('token');
+        tokenField.value = localStorage.getItem('adminToken') || '';
+        tokenField.addEventListener('change', () => {
+            localStorage.setItem('adminToken', tokenField.value);
+            refresh();
+        });
+        const hours = () => parseFloat(document.getElementById('hours').value) || null;
+        const until = (override) => override.until ? 'til ' + new Date(override.until).toLocaleString('nb-NO') : 'til videre';
+        const text = (value) => String(value ?? '').replace(/[&<>"]/g, (c) => '&#' + c.charCodeAt(0) + ';');
+
+        async function call(path, options = {}) {
+            const headers = { 'content-type': 'application/json' };
+            if (tokenField.value) headers.authorization = 'Bearer ' + tokenField.value;
+            const response = await fetch(path, { ...options, headers });
+            const body = response.status === 204 ? null : await response.json();
+            if (!response.ok) throw new Error(body?.message || response.statusText);
+            return body;
+        }
+
+        async function post(path, body = {}) {
+            try {
+                await call(path, { method: 'POST', body: JSON.stringify(body) });
+            } catch (error) {
+                alert(error.message);
+            }
+            refresh();
+        }
+
+        const force = (device, on) => call('admin/devices/' + device + '/override', {
+            method: 'PUT',
+            body: JSON.stringify({ on, hours: hours() }),
+        }).catch((error) => alert(error.message)).then(refresh);
+        const clearForce = (device) => call('admin/devices/' + device + '/override', { method: 'DELETE' })
+            .catch((error) => alert(error.message)).then(refresh);
+
+        async function simulate() {
+            const date = document.getElementById('simulateDate').value;
+            const output = document.getElementById('simulation');
+            try {
+                output.textContent = JSON.stringify(await call('admin/simulate' + (date ? '?date=' + date : '')), null, 2);
+            } catch (error) {
+                output.textContent = error.message;
+            }
+        }
+
+        async function refresh() {
+            try {
+                const state = await call('admin/automation');
+                document.getElementById('paused').textContent = state.paused ? 'Pauset ' + until(state.paused) : 'Automatikken kjører';
+                for (const [device, override] of Object.entries(state.devices)) {
+                    document.getElementById('override-' + device).textContent =
+                        override ? (override.on ? 'På ' : 'Av ') + until(override) : 'Nei';
+                }
+                const { actions } = await call('admin/audit?limit=20');
+                document.getElementById('audit').innerHTML = actions.map((action) =>
+                    '<tr><td>' + new Date(action.time).toLocaleString('nb-NO') + '</td><td>' + text(action.source) +
+                    '</td><td>' + text(action.action) + '</td><td>' + text(action.reason) + '</td><td>' +
+                    text(action.error || action.result) + '</td></tr>').join('');
+            } catch (error) {
+                document.getElementById('paused').textContent = error.message;
+            }
+        }
+
+        refresh();
+    </script>`,
+  });
+}
+
+// Printable monthly or daily report from `priceReport`, with a QR code of
+// `link` (to the dashboard) if given. Embedded, as PDFs are printed from a
+// file rather than from this server
+export function renderReport(report, link = null) {
+  const rows = [
+    ["Gjennomsnitt", `${format(report.average)} øre/kWh`],
+    [
+      "Laveste",
+      `${format(report.min.price)} øre/kWh (${formatDateTime(report.min.time)})`,
+    ],
+    [
+      "Høyeste",
+      `${format(report.max.price)} øre/kWh (${formatDateTime(report.max.time)})`,
+    ],
+    ["Strømstøtte, snitt", `${format(report.support)} øre/kWh`],
+  ];
+  if (report.previous) {
+    rows.push([
+      `Mot ${report.previous.period}`,
+      `${format(report.previous.average)} øre/kWh (${percent(report.previous.change)})`,
+    ]);
+  }
+  if (report.consumption) {
+    rows.push(
+      ["Forbruk", `${format(report.consumption.kwh)} kWh`],
+      ["Kostnad", `${formatKroner(report.consumption.cost)} kr`],
+      ["Strømstøtte", `${formatKroner(report.consumption.support)} kr`],
+    );
+  }
+
+  const tableRows = rows
+    .map(
+      ([label, value]) =>
+        `            <tr><td>${escape(label)}</td><td>${escape(value)}</td></tr>`,
+    )
+    .join("\n");
+  const place = report.consumption
+    ? `${report.consumption.location} (${report.region})`
+    : report.region;
+  const monthly = report.period.length === 7;
+  const kind = monthly ? "Månedsrapport" : "Dagsrapport";
+  const period = monthly
+    ? report.period.split("-").reverse().join(".")
+    : formatDate(report.period);
+
+  return layout({
+    title: period,
+    header: `${kind} ${period} for ${place}`,
+    body: `    <table class="stats-table">
+        <tbody>
+${tableRows}
+        </tbody>
+    </table>
+${link ? `    <img class="report-qr" src="data:image/png;base64,${qrPng(link, { scale: 4 }).toString("base64")}" alt="${escape(link)}">\n` : ""}    <button class="nav-button" onclick="window.print()">Skriv ut</button>`,
+  });
+}
+
+const LEVEL_NAMES = {
+  very_cheap: "svært billig",
+  cheap: "billig",
+  normal: "normal",
+  expensive: "dyr",
+  very_expensive: "svært dyr",
+};
+
+// Full-screen page for a wall-mounted tablet: the current price in huge
+// digits, today's chart, the next cheapest window and optionally a QR code
+// linking to the dashboard. It reloads whenever the server-sent events say
+// a new interval started or prices changed
+export function renderKiosk({
+  region,
+  current,
+  next,
+  level,
+  window,
+  hours,
+  chart = true,
+  theme = "light",
+  scale = 1,
+  qr = false,
+}) {
+  const day = current?.time.slice(0, 10).split("-").map(Number);
+  const price = current
+    ? `<div class="kiosk-price">${format(current.price)}<span class="kiosk-unit">øre/kWh</span></div>
+    <div class="kiosk-detail">${region} kl. ${formatClock(current.time)} · ${LEVEL_NAMES[level]}${next ? ` · deretter ${format(next.price)}` : ""}</div>`
+    : '<div class="kiosk-detail">Hmm. Ingen data.</div>';
+  const cheapest = window
+    ? `<div class="kiosk-detail">Billigste ${hours} t: ${formatClock(window.start)}–${formatClock(window.end)} (${format(window.average)} øre)</div>`
+    : "";
+  const image =
+    chart && day
+      ? `<img class="kiosk-chart" src="chart/${day.join("/")}/${region}.svg?annotate=now,extremes" alt="Strømpriser i dag">`
+      : "";
+  const link = qr
+    ? `<img class="kiosk-qr" src="qr.png?region=${region}&amp;scale=4" alt="Lenke til strømprisene">`
+    : "";
+
+  return `<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>${escape(pageTitle(region))}</title>
+    <base href="/">
+    <link rel="icon" href="favicon.svg" type="image/svg+xml">
+    <link rel="stylesheet" href="style.css">
+${brandStyle()}</head>
+<body class="kiosk kiosk-${theme}" style="--kiosk-scale: ${scale};">
+    ${price}
+    ${cheapest}
+    ${image}
+    ${link}
+    <script>
+        const events = new EventSource('events?region=${region}');
+        events.addEventListener('tick', () => location.reload());
+        events.addEventListener('prices', () => location.reload());
+    </script>
+</body>
+</html>
+`;
+}
+
+// E-paper panels are 800 × 480; `rotate` turns the layout for panels
+// mounted on their side or upside down
+export const EINK = { width: 800, height: 480 };
+const ROTATIONS = {
+  0: "none",
+  90: `translate(${EINK.height}px, 0) rotate(90deg)`,
+  180: `translate(${EINK.width}px, ${EINK.height}px) rotate(180deg)`,
+  270: `translate(0, ${EINK.width}px) rotate(270deg)`,
+};
+export const EINK_ROTATIONS = Object.keys(ROTATIONS).map(Number);
+
+// Grayscale, static page for 7.5" e-paper browsers and screenshot pipelines:
+// the current price, today's min./avg./max., the cheapest window and `svg`
+// (the day's chart). It asks to be reloaded `refresh` seconds later, when
+// the next interval starts
+export function renderEink({
+  region,
+  current,
+  stats,
+  window,
+  hours,
+  svg,
+  refresh,
+  rotate = 0,
+}) {
+  const sideways = rotate % 180 !== 0;
+  const body = current
+    ? `        <div class="eink-price">${format(current.price)} <span>øre/kWh</span></div>
+        <div class="eink-detail">${region} kl. ${formatClock(current.time)} · min. ${format(stats.min)} · gjn. ${format(stats.avg)} · maks ${format(stats.max)}</div>
+        <div class="eink-detail">${window ? `Billigste ${hours} t: ${formatClock(window.start)}–${formatClock(window.end)} (${format(window.average)} øre)` : ""}</div>
+        ${svg}`
+    : '        <div class="eink-detail">Hmm. Ingen data.</div>';
+
+  return `<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=${sideways ? EINK.height : EINK.width}, height=${sideways ? EINK.width : EINK.height}">
+    <meta http-equiv="refresh" content="${refresh}">
+    <title>${escape(pageTitle(region))}</title>
+    <base href="/">
+    <link rel="stylesheet" href="style.css">
+</head>
+<body class="eink-page">
+    <div class="eink" style="transform: ${ROTATIONS[rotate]};">
+${body}
+    </div>
+</body>
+</html>
+`;
+}
+
+// Bare page with a day's chart and a link to the dashboard, for embedding in
+// an iframe (see /oembed); today's chart marks the current time
+export function renderEmbed({ region, date, link }) {
+  const day = date.split("-").map(Number).join("/");
+  return `<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>${escape(pageTitle(`${region} ${formatDate(date)}`))}</title>
+    <base href="/">
+    <link rel="stylesheet" href="style.css">
+${brandStyle()}</head>
+<body class="embed">
+    <img class="embed-chart" src="chart/${day}/${region}.svg?annotate=now,extremes" alt="Strømpriser i ${region} ${date}">
+    <a class="embed-link" href="${escape(link)}" target="_blank" rel="noopener">Strømpriser i ${region} ${formatDate(date)} – ${escape(brandingSettings().title)}</a>
+</body>
+</html>
+`;
+}
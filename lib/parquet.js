@@ -0,0 +1,212 @@
+// Minimal Parquet writer: one row group, required columns, PLAIN encoding,
+// no compression. Enough for pandas/polars/DuckDB to read price exports.
+
+const TYPES = { INT64: 2, DOUBLE: 5, BYTE_ARRAY: 6 };
+const CONVERTED = { UTF8: 0, TIMESTAMP_MILLIS: 9 };
+
+// Thrift compact protocol field types
+const T = { I32: 5, I64: 6, BINARY: 8, LIST: 9, STRUCT: 12 };
+
+class ThriftWriter {
+  constructor() {
+    this.bytes = [];
+    this.lastField = [0];
+  }
+
+  varint(value) {
+    let n = BigInt(value);
+    while (n >= 0x80n) {
+      this.bytes.push(Number((n & 0x7fn) | 0x80n));
+      n >>= 7n;
+    }
+    this.bytes.push(Number(n));
+  }
+
+  zigzag(value) {
+    const n = BigInt(value);
+    this.varint(n >= 0n ? n << 1n : (-n << 1n) - 1n);
+  }
+
+  field(id, type) {
+    const delta = id - this.lastField[this.lastField.length - 1];
+    if (delta > 0 && delta <= 15) {
+      this.bytes.push((delta << 4) | type);
+    } else {
+      this.bytes.push(type);
+      this.zigzag(id);
+    }
+    this.lastField[this.lastField.length - 1] = id;
+  }
+
+  i32(id, value) {
+    this.field(id, T.I32);
+    this.zigzag(value);
+  }
+
+  i64(id, value) {
+    this.field(id, T.I64);
+    this.zigzag(value);
+  }
+
+  string(id, value) {
+    this.field(id, T.BINARY);
+    this.binary(value);
+  }
+
+  binary(value) {
+    const bytes = new TextEncoder().encode(value);
+    this.varint(bytes.length);
+    this.bytes.push(...bytes);
+  }
+
+  struct(id, write) {
+    this.field(id, T.STRUCT);
+    this.structBody(write);
+  }
+
+  structBody(write) {
+    this.lastField.push(0);
+    write(this);
+    this.bytes.push(0); // stop
+    this.lastField.pop();
+  }
+
+  list(id, type, items, write) {
+    this.field(id, T.LIST);
+    if (items.length < 15) {
+      this.bytes.push((items.length << 4) | type);
+    } else {
+      this.bytes.push(0xf0 | type);
+      this.varint(items.length);
+    }
+    for (const item of items) write(this, item);
+  }
+
+  toBuffer() {
+    return Uint8Array.from(this.bytes);
+  }
+}
+
+function plain(column, values) {
+  if (column.type === "BYTE_ARRAY") {
+    const encoded = values.map((value) => new TextEncoder().encode(value));
+    const buffer = new Uint8Array(
+      encoded.reduce((sum, bytes) => sum + 4 + bytes.length, 0),
+    );
+    const view = new DataView(buffer.buffer);
+    let offset = 0;
+    for (const bytes of encoded) {
+      view.setUint32(offset, bytes.length, true);
+      buffer.set(bytes, offset + 4);
+      offset += 4 + bytes.length;
+    }
+    return buffer;
+  }
+
+  const buffer = new Uint8Array(values.length * 8);
+  const view = new DataView(buffer.buffer);
+  values.forEach((value, i) => {
+    if (column.type === "INT64") view.setBigInt64(i * 8, BigInt(value), true);
+    else view.setFloat64(i * 8, value, true);
+  });
+  return buffer;
+}
+
+// Write `rows` (objects) as Parquet, with `columns` as `{ name, type, converted? }`
+export function writeParquet(columns, rows) {
+  const chunks = [new TextEncoder().encode("PAR1")];
+  let offset = 4;
+  const metadata = [];
+
+  for (const column of columns) {
+    const data = plain(
+      column,
+      rows.map((row) => row[column.name]),
+    );
+
+    const header = new ThriftWriter();
+    header.structBody((w) => {
+      w.i32(1, 0); // DATA_PAGE
+      w.i32(2, data.length);
+      w.i32(3, data.length);
+      w.struct(5, (w) => {
+        w.i32(1, rows.length);
+        w.i32(2, 0); // PLAIN
+        w.i32(3, 3); // RLE
+        w.i32(4, 3); // RLE
+      });
+    });
+    const headerBytes = header.toBuffer();
+
+    metadata.push({
+      column,
+      offset,
+      size: headerBytes.length + data.length,
+    });
+    chunks.push(headerBytes, data);
+    offset += headerBytes.length + data.length;
+  }
+
+  const footer = new ThriftWriter();
+  footer.structBody((w) => {
+    w.i32(1, 1); // version
+    w.list(2, T.STRUCT, [null, ...columns], (w, column) =>
+      w.structBody((w) => {
+        if (column === null) {
+          w.string(4, "schema");
+          w.i32(5, columns.length);
+          return;
+        }
+        w.i32(1, TYPES[column.type]);
+        w.i32(3, 0); // REQUIRED
+        w.string(4, column.name);
+        if (column.converted !== undefined) {
+          w.i32(6, CONVERTED[column.converted]);
+        }
+      }),
+    );
+    w.i64(3, rows.length);
+    w.list(4, T.STRUCT, [null], (w) =>
+      w.structBody((w) => {
+        w.list(1, T.STRUCT, metadata, (w, chunk) =>
+          w.structBody((w) => {
+            w.i64(2, chunk.offset);
+            w.struct(3, (w) => {
+              w.i32(1, TYPES[chunk.column.type]);
+              w.list(2, T.I32, [0, 3], (w, encoding) => w.zigzag(encoding));
+              w.list(3, T.BINARY, [chunk.column.name], (w, name) =>
+                w.binary(name),
+              );
+              w.i32(4, 0); // UNCOMPRESSED
+              w.i64(5, rows.length);
+              w.i64(6, chunk.size);
+              w.i64(7, chunk.size);
+              w.i64(9, chunk.offset);
+            });
+          }),
+        );
+        w.i64(
+          2,
+          metadata.reduce((sum, chunk) => sum + chunk.size, 0),
+        );
+        w.i64(3, rows.length);
+      }),
+    );
+    w.string(6, "elektron");
+  });
+  const footerBytes = footer.toBuffer();
+
+  const length = new Uint8Array(4);
+  new DataView(length.buffer).setUint32(0, footerBytes.length, true);
+  chunks.push(footerBytes, length, new TextEncoder().encode("PAR1"));
+
+  const file = new Uint8Array(
+    chunks.reduce((sum, chunk) => sum + chunk.length, 0),
+  );
+  let position = 0;
+  for (const chunk of chunks) {
+    file.set(chunk, position);
+    position += chunk.length;
+  }
+  return file;
+}
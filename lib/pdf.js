@@ -0,0 +1,55 @@
+import { mkdtemp, readFile, rm, writeFile } from "fs/promises";
+import { tmpdir } from "os";
+import { dirname, join } from "path";
+import { config } from "./config.js";
+
+const STYLE = join(dirname(new URL(import.meta.url).pathname), "..", "public");
+
+// PDF output is off unless `pdf.command` names a headless Chromium (or
+// anything else accepting its `--headless --print-to-pdf` flags)
+export function pdfEnabled() {
+  return Boolean(config.pdf?.command);
+}
+
+// Print a server-rendered page to PDF with the stylesheet inlined, as the
+// renderer reads it from a file rather than from this server
+export async function renderPdf(html) {
+  const style = await readFile(join(STYLE, "style.css"), "utf8");
+  const page = html.replace(
+    '<link rel="stylesheet" href="style.css">',
+    `<style>\n${style}</style>`,
+  );
+
+  const directory = await mkdtemp(join(tmpdir(), "elektron-"));
+  try {
+    const input = join(directory, "report.html");
+    const output = join(directory, "report.pdf");
+    await writeFile(input, page);
+
+    const renderer = Bun.spawn(
+      [
+        config.pdf.command,
+        "--headless",
+        "--disable-gpu",
+        "--no-pdf-header-footer",
+        `--print-to-pdf=${output}`,
+        ...(config.pdf.args || []),
+        `file://${input}`,
+      ],
+      { stdout: "ignore", stderr: "pipe" },
+    );
+    const timeout = setTimeout(
+      () => renderer.kill(),
+      config.pdf.timeout_ms || 30000,
+    );
+    const status = await renderer.exited;
+    clearTimeout(timeout);
+    if (status !== 0) {
+      const stderr = await new Response(renderer.stderr).text();
+      throw new Error(`PDF renderer exited with ${status}: ${stderr.trim()}`);
+    }
+    return await readFile(output);
+  } finally {
+    await rm(directory, { recursive: true, force: true });
+  }
+}
@@ -0,0 +1,60 @@
+import { chartSettings, validateChartSettings } from "./chart.js";
+import { getProfile } from "./profiles.js";
+import { DEFAULT_STATISTICS, parseStatistics } from "./stats.js";
+
+function cookie(request, name) {
+  const match = new RegExp(`(?:^|;\\s*)${name}=([^;]+)`).exec(
+    request.headers.get("cookie") || "",
+  );
+  return match ? decodeURIComponent(match[1]) : null;
+}
+
+// Statistics shown below the chart: `?stats=`, then the `stats` cookie, then
+// min., avg. and max.
+export function preferredStatistics(request, query = {}) {
+  for (const text of [query.stats, cookie(request, "stats")]) {
+    if (!text) continue;
+    const names = parseStatistics(text);
+    if (!names.message) return names;
+  }
+  return DEFAULT_STATISTICS;
+}
+
+// Remember explicitly chosen `?stats=` for a year
+export function statisticsCookie(names) {
+  return `stats=${names.join(",")}; Path=/; Max-Age=31536000; SameSite=Lax`;
+}
+
+// Chart settings with the `chart` cookie's overrides, if they are valid
+export function preferredChart(request) {
+  return chartSettings(chartOverrides(request));
+}
+
+// The overrides of the `chart` cookie, or none if it is missing or invalid
+export function chartOverrides(request) {
+  try {
+    const overrides = JSON.parse(cookie(request, "chart"));
+    return validateChartSettings(overrides) ? {} : overrides;
+  } catch (error) {
+    return {};
+  }
+}
+
+// Remember chart setting overrides for a year; none clears the cookie
+export function chartCookie(overrides) {
+  const value = encodeURIComponent(JSON.stringify(overrides));
+  const age = Object.keys(overrides).length > 0 ? 31536000 : 0;
+  return `chart=${value}; Path=/; Max-Age=${age}; SameSite=Lax`;
+}
+
+// The stored profile named by `?profile=`, then the `profile` cookie, or null
+export function preferredProfile(request, query = {}) {
+  if (query.profile === "") return null;
+  return getProfile(query.profile ?? cookie(request, "profile"));
+}
+
+// Remember an explicitly chosen `?profile=` for a year; none clears the cookie
+export function profileCookie(name) {
+  const age = name ? 31536000 : 0;
+  return `profile=${name ?? ""}; Path=/; Max-Age=${age}; SameSite=Lax`;
+}
@@ -0,0 +1,462 @@
+import { config } from "./config.js";
+import { db } from "./db.js";
+import { limitSettings } from "./limits.js";
+import { forgetShared, sharedFetch } from "./redis.js";
+import { durations } from "./stats.js";
+import { syntheticPrices } from "./synthetic.js";
+
+export const REGIONS = ["NO1", "NO2", "NO3", "NO4", "NO5"];
+
+// Validate a `/prices/:year/:month/:day/:region` style date; `{ date }` or `{ message }`
+export function parseDay(year, month, day, region) {
+  const yearNum = parseInt(year);
+  const monthNum = parseInt(month);
+  const dayNum = parseInt(day);
+
+  // Check for NaN values (invalid numeric inputs)
+  if (isNaN(yearNum) || isNaN(monthNum) || isNaN(dayNum)) {
+    return { message: "Year, month, and day must be valid numbers" };
+  }
+
+  if (yearNum < 2020 || yearNum > 2030) {
+    return { message: "Year must be between 2020 and 2030" };
+  }
+  if (monthNum < 1 || monthNum > 12) {
+    return { message: "Month must be between 1 and 12" };
+  }
+
+  // Validate day for the given month and year
+  const daysInMonth = new Date(yearNum, monthNum, 0).getDate();
+  if (dayNum < 1 || dayNum > daysInMonth) {
+    return {
+      message: `Day must be between 1 and ${daysInMonth} for month ${monthNum}`,
+    };
+  }
+
+  if (!REGIONS.includes(region)) {
+    return { message: "Region must be NO1-NO5" };
+  }
+
+  return { date: new Date(yearNum, monthNum - 1, dayNum) };
+}
+
+const BREAKER_THRESHOLD = 5;
+const BREAKER_COOLDOWN = 5 * 60 * 1000;
+
+// Health of the upstream API; the breaker opens after repeated failures (not
+// 404s, which just mean unpublished) and lets a single attempt through once
+// the cooldown has passed
+const upstream = {
+  breaker: "closed",
+  failures: 0,
+  opened: null,
+  latency: null,
+  lastError: null,
+  lastSuccess: {},
+};
+
+function failed(message) {
+  upstream.failures++;
+  upstream.lastError = message;
+  if (upstream.breaker === "half-open") upstream.breaker = "open";
+  if (upstream.failures >= BREAKER_THRESHOLD) upstream.breaker = "open";
+  if (upstream.breaker === "open") upstream.opened = Date.now();
+}
+
+// Fetch electricity prices
+export async function fetchPrices(year, month, day, region) {
+  if (config.synthetic) return syntheticPrices(year, month, day, region);
+  if (upstream.breaker === "open") {
+    if (Date.now() - upstream.opened < BREAKER_COOLDOWN) {
+      throw new Error("Upstream unavailable (circuit open)");
+    }
+    upstream.breaker = "half-open";
+  }
+
+  const date = `${year}-${month.toString().padStart(2, "0")}-${day.toString().padStart(2, "0")}`;
+  const url = `https://www.hvakosterstrommen.no/api/v1/prices/${year}/${date.slice(5)}_${region}.json`;
+  const started = Date.now();
+  let response;
+  try {
+    response = await fetch(url, {
+      // Reuse the connection between the fetches of the zones and days
+      keepalive: true,
+      signal: AbortSignal.timeout(
+        limitSettings().upstream_timeout_seconds * 1000,
+      ),
+    });
+  } catch (error) {
+    failed(error.message);
+    throw error;
+  }
+  upstream.latency = Date.now() - started;
+
+  if (!response.ok) {
+    if (response.status !== 404) failed(`HTTP ${response.status}`);
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+
+  upstream.breaker = "closed";
+  upstream.failures = 0;
+  upstream.lastSuccess[region] = { date, at: new Date().toISOString() };
+  return response.json();
+}
+
+// Breaker state, latest latency and the last successful fetch per region
+export function upstreamStatus() {
+  return {
+    breaker: upstream.breaker,
+    failures: upstream.failures,
+    opened_at: upstream.opened && new Date(upstream.opened).toISOString(),
+    latency_ms: upstream.latency,
+    last_error: upstream.lastError,
+    last_success: upstream.lastSuccess,
+  };
+}
+
+// Map the upstream response to the shape served by `/prices`
+export function toChart(data) {
+  return data.map((item) => {
+    // Extract hour from UTC time string without timezone conversion
+    const hour = parseInt(item.time_start.split("T")[1].split(":")[0]);
+    return {
+      hour,
+      price: item.NOK_per_kWh * 100.0,
+      time: item.time_start,
+      price_nok: item.NOK_per_kWh,
+      price_eur: item.EUR_per_kWh,
+    };
+  });
+}
+
+// The interval of `chart` that is active at `now` (the last one that has started)
+export function currentEntry(chart, now = new Date()) {
+  let current = null;
+  for (const item of chart) {
+    if (new Date(item.time) <= now) current = item;
+  }
+  return current;
+}
+
+export const LEVELS = [
+  "very_cheap",
+  "cheap",
+  "normal",
+  "expensive",
+  "very_expensive",
+];
+
+// Classify `price` by its rank among the day's `prices`
+export function priceLevel(price, prices) {
+  if (prices.length === 0) return "normal";
+  const below = prices.filter((p) => p < price).length;
+  const rank = below / prices.length;
+  if (rank < 0.2) return "very_cheap";
+  if (rank < 0.4) return "cheap";
+  if (rank < 0.6) return "normal";
+  if (rank < 0.8) return "expensive";
+  return "very_expensive";
+}
+
+const cache = new Map();
+const CACHE_SIZE = 256;
+
+const storedListeners = [];
+
+// Run `listener(region, chart)` whenever a newly fetched day has been stored;
+// returns a function that removes the listener again
+export function onStored(listener) {
+  storedListeners.push(listener);
+  return () => {
+    const index = storedListeners.indexOf(listener);
+    if (index !== -1) storedListeners.splice(index, 1);
+  };
+}
+
+// Resolve with why it returned: "interval" at `until` (ms, if given),
+// "day-ahead" when prices for `region` are stored, "timeout" after `timeout`
+// ms, or "aborted" when `signal` aborts
+export function waitForChange(region, { until, timeout, signal }) {
+  return new Promise((resolve) => {
+    const timers = [];
+    const finish = (reason) => {
+      timers.forEach(clearTimeout);
+      unsubscribe();
+      resolve(reason);
+    };
+    const unsubscribe = onStored((stored) => {
+      if (stored === region) finish("day-ahead");
+    });
+    timers.push(setTimeout(() => finish("timeout"), timeout));
+    if (until) {
+      timers.push(setTimeout(() => finish("interval"), until - Date.now()));
+    }
+    signal?.addEventListener("abort", () => finish("aborted"));
+  });
+}
+
+// `YYYY-MM-DD` of a local date
+export function isoDate(date) {
+  const month = (date.getMonth() + 1).toString().padStart(2, "0");
+  const day = date.getDate().toString().padStart(2, "0");
+  return `${date.getFullYear()}-${month}-${day}`;
+}
+
+function cacheKey(date, region) {
+  return `${region}/${isoDate(date)}`;
+}
+
+// Size of the in-memory cache and the age of its oldest and newest entries
+export function cacheStatus() {
+  const ages = [...cache.values()].map((entry) =>
+    Math.round((Date.now() - entry.cached) / 1000),
+  );
+  return {
+    entries: cache.size,
+    capacity: CACHE_SIZE,
+    oldest_seconds: ages.length > 0 ? Math.max(...ages) : null,
+    newest_seconds: ages.length > 0 ? Math.min(...ages) : null,
+  };
+}
+
+// Latest stored interval per region
+export function latestStored() {
+  const rows = db()
+    .query("SELECT region, MAX(time) AS time FROM prices GROUP BY region")
+    .all();
+  return Object.fromEntries(rows.map((row) => [row.region, row.time]));
+}
+
+// Persist a day of prices (as returned by `toChart`) from `source`. Each
+// interval is kept once per resolution, so refetches and corrections update
+// it in place while hourly and 15-minute data for the same day can coexist
+export function storePrices(region, chart, source = "hvakosterstrommen") {
+  const insert = db().prepare(
+    `INSERT INTO prices
+       (region, time, resolution, price_nok, price_eur, source, fetched_at)
+     VALUES (?, ?, ?, ?, ?, ?, ?)
+     ON CONFLICT (region, time, resolution) DO UPDATE SET
+       price_nok = excluded.price_nok,
+       price_eur = excluded.price_eur,
+       source = excluded.source,
+       fetched_at = excluded.fetched_at`,
+  );
+  const fetched = new Date().toISOString();
+  const lengths = durations(chart);
+  db().transaction(() => {
+    chart.forEach((item, i) => {
+      insert.run(
+        region,
+        item.time,
+        Math.round(lengths[i] * 60),
+        item.price_nok,
+        item.price_eur,
+        source,
+        fetched,
+      );
+    });
+  })();
+}
+
+// Stored prices for `region` from `from` to `to` (inclusive local dates), in
+// the finest resolution stored for each day
+export function loadPrices(region, from, to) {
+  const end = new Date(to);
+  end.setDate(end.getDate() + 1);
+  const rows = db()
+    .query(
+      `SELECT time AS time_start, resolution,
+         price_nok AS NOK_per_kWh, price_eur AS EUR_per_kWh
+       FROM prices
+       WHERE region = ? AND time >= ? AND time < ?
+       ORDER BY time`,
+    )
+    .all(region, isoDate(from), isoDate(end));
+
+  const finest = new Map();
+  for (const row of rows) {
+    const date = row.time_start.slice(0, 10);
+    finest.set(date, Math.min(finest.get(date) ?? Infinity, row.resolution));
+  }
+  return toChart(
+    rows.filter(
+      (row) => row.resolution === finest.get(row.time_start.slice(0, 10)),
+    ),
+  );
+}
+
+// Where the stored intervals from `from` to `to` came from, and when
+export function provenance(region, from, to) {
+  const end = new Date(to);
+  end.setDate(end.getDate() + 1);
+  return db()
+    .query(
+      `SELECT source, resolution, COUNT(*) AS intervals,
+         MAX(fetched_at) AS last_fetched
+       FROM prices
+       WHERE region = ? AND time >= ? AND time < ?
+       GROUP BY source, resolution`,
+    )
+    .all(region, isoDate(from), isoDate(end));
+}
+
+// Prices for `date` in `region` (as served by `/prices`), cached per zone and day
+export async function getPrices(date, region) {
+  const key = cacheKey(date, region);
+  if (cache.has(key)) return cache.get(key).chart;
+
+  let chart = loadPrices(region, date, date);
+  if (chart.length === 0) {
+    // One upstream fetch per zone and day across replicas sharing a Redis
+    chart = await sharedFetch(`prices:${key}`, async () =>
+      toChart(
+        await fetchPrices(
+          date.getFullYear(),
+          date.getMonth() + 1,
+          date.getDate(),
+          region,
+        ),
+      ),
+    );
+    storePrices(region, chart);
+    for (const listener of storedListeners) {
+      Promise.resolve(listener(region, chart)).catch((error) =>
+        console.error(`Stored-prices listener failed: ${error.message}`),
+      );
+    }
+  }
+
+  if (cache.size >= CACHE_SIZE) {
+    cache.delete(cache.keys().next().value);
+  }
+  cache.set(key, { chart, cached: Date.now() });
+  return chart;
+}
+
+// Drop `date` in `region` from the cache, e.g. after storing corrected prices
+export function forgetCached(date, region) {
+  cache.delete(cacheKey(date, region));
+  forgetShared(`prices:${cacheKey(date, region)}`);
+}
+
+// Parse a `YYYY-MM-DD` date as local midnight, or null if it isn't one
+export function parseDate(text) {
+  const match = /^(\d{4})-(\d{2})-(\d{2})$/.exec(text || "");
+  if (!match) return null;
+  const date = new Date(+match[1], +match[2] - 1, +match[3]);
+  return date.getDate() === +match[3] ? date : null;
+}
+
+// Validate `?from=&to=` (inclusive, at most `maxDays` days); `{ from, to }` or `{ message }`
+export function parseRange(query, maxDays = 3 * 366) {
+  const from = parseDate(query.from);
+  const to = parseDate(query.to);
+  if (!from || !to) {
+    return { message: "from and to must be dates on the form YYYY-MM-DD" };
+  }
+  if (from > to) {
+    return { message: "from must not be after to" };
+  }
+  if (from < new Date(2020, 0, 1)) {
+    return { message: "from must not be before 2020-01-01" };
+  }
+  if ((to - from) / 86400000 >= maxDays) {
+    return { message: `The range must not exceed ${maxDays} days` };
+  }
+  return { from, to };
+}
+
+// `?from_hour=6&to_hour=22` (to_hour exclusive, wrapping past midnight when
+// it is the smaller); `{ from, to }`, null if neither is given, or `{ message }`
+export function parseHourRange(query) {
+  if (query.from_hour === undefined && query.to_hour === undefined) {
+    return null;
+  }
+  const from = query.from_hour === undefined ? 0 : Number(query.from_hour);
+  const to = query.to_hour === undefined ? 24 : Number(query.to_hour);
+  if (
+    !Number.isInteger(from) ||
+    !Number.isInteger(to) ||
+    from < 0 ||
+    from > 23 ||
+    to < 1 ||
+    to > 24 ||
+    from === to
+  ) {
+    return {
+      message: "from_hour (0-23) and to_hour (1-24) must be different hours",
+    };
+  }
+  return { from, to };
+}
+
+// The intervals of `chart` within an hour range (all of them for null)
+export function inHourRange(chart, range) {
+  if (!range) return chart;
+  return chart.filter((item) =>
+    range.from < range.to
+      ? item.hour >= range.from && item.hour < range.to
+      : item.hour >= range.from || item.hour < range.to,
+  );
+}
+
+const PERIOD_UNITS = { d: 1, w: 7, m: 30, y: 365 };
+
+// `?from=&to=`, `?period=30d` (d/w/m/y) ending today, or `?period=week|month`
+// for the current calendar week/month; `{ from, to }` or `{ message }`
+export function parsePeriod(query, fallback = "365d", maxDays = 20 * 366) {
+  if (query.from || query.to) return parseRange(query, maxDays);
+
+  const period = query.period || fallback;
+  const to = new Date();
+  to.setHours(0, 0, 0, 0);
+
+  // Calendar windows up to and including today
+  if (period === "week" || period === "month") {
+    const from = new Date(to);
+    if (period === "week") {
+      from.setDate(from.getDate() - ((from.getDay() + 6) % 7));
+    } else {
+      from.setDate(1);
+    }
+    return { from, to };
+  }
+
+  const match = /^(\d+)([dwmy])$/.exec(period);
+  if (!match || +match[1] === 0) {
+    return {
+      message: "period must be week, month or on the form 30d, 12w, 6m or 1y",
+    };
+  }
+
+  const days = +match[1] * PERIOD_UNITS[match[2]];
+  if (days > maxDays) {
+    return { message: `The period must not exceed ${maxDays} days` };
+  }
+
+  const from = new Date(to);
+  from.setDate(from.getDate() - days + 1);
+  return { from, to };
+}
+
+// Every date from `from` to `to`, inclusive
+export function* eachDay(from, to) {
+  const date = new Date(from);
+  while (date <= to) {
+    yield new Date(date);
+    date.setDate(date.getDate() + 1);
+  }
+}
+
+// Today's prices followed by tomorrow's, once published
+export async function getHorizon(region, now = new Date()) {
+  const chart = await getPrices(now, region);
+  const tomorrow = new Date(now);
+  tomorrow.setDate(tomorrow.getDate() + 1);
+  try {
+    return [...chart, ...(await getPrices(tomorrow, region))];
+  } catch (error) {
+    // Not published before ~13:00
+    return chart;
+  }
+}
@@ -0,0 +1,73 @@
+import { db } from "./db.js";
+import { getLocation } from "./locations.js";
+import { REGIONS } from "./prices.js";
+import { TARIFFS } from "./tariffs.js";
+
+// Lowercase letters, digits, `-` and `_`, so a name is safe in a link and a
+// cookie
+const NAME = /^[a-z0-9_-]{1,32}$/;
+
+export function validProfileName(name) {
+  return typeof name === "string" && NAME.test(name);
+}
+
+// What is wrong with a profile's selections (`{ region, tariff, location }`,
+// each optional: a zone, a nettleie preset and a configured location whose
+// consumption source to use), or null
+export function validateProfile(profile) {
+  if (!profile || typeof profile !== "object" || Array.isArray(profile)) {
+    return "Expected { region, tariff, location }";
+  }
+  const { region, tariff, location, ...rest } = profile;
+  const unknown = Object.keys(rest);
+  if (unknown.length > 0) return `Unknown fields: ${unknown.join(", ")}`;
+  if (region != null && !REGIONS.includes(region)) {
+    return "region must be NO1-NO5";
+  }
+  if (tariff != null && !TARIFFS[tariff]) {
+    return `Unknown tariff preset: ${tariff}`;
+  }
+  if (location != null && !getLocation(location)) {
+    return `Unknown location: ${location}`;
+  }
+  return null;
+}
+
+// Stored profile names, alphabetically
+export function listProfiles() {
+  return db()
+    .query("SELECT name FROM profiles ORDER BY name")
+    .all()
+    .map((row) => row.name);
+}
+
+// `{ name, region, tariff, location }` of a stored profile, or null
+export function getProfile(name) {
+  if (!validProfileName(name)) return null;
+  const row = db()
+    .query(
+      `SELECT name, region, tariff, location FROM profiles WHERE name = ?`,
+    )
+    .get(name);
+  return row ?? null;
+}
+
+// Store a profile's selections, replacing any before
+export function saveProfile(name, { region, tariff, location }) {
+  db()
+    .query(
+      `INSERT INTO profiles (name, region, tariff, location) VALUES (?, ?, ?, ?)
+       ON CONFLICT (name) DO UPDATE SET region = excluded.region,
+         tariff = excluded.tariff, location = excluded.location`,
+    )
+    .run(name, region ?? null, tariff ?? null, location ?? null);
+  return getProfile(name);
+}
+
+// Forget a profile; whether there was one
+export function deleteProfile(name) {
+  const { changes } = db()
+    .query("DELETE FROM profiles WHERE name = ?")
+    .run(name);
+  return changes > 0;
+}
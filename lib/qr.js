@@ -0,0 +1,330 @@
+// Minimal QR code writer: byte mode at error correction level M, versions
+// 1–10 (up to 213 bytes, plenty for a link), drawn as a grayscale PNG
+import { deflateSync } from "zlib";
+import { crc32 } from "./xlsx.js";
+
+// Per version: error correction codewords per block, then `[blocks, data
+// codewords per block]` for each group of blocks
+const BLOCKS = [
+  null,
+  [10, [1, 16]],
+  [16, [1, 28]],
+  [26, [1, 44]],
+  [18, [2, 32]],
+  [24, [2, 43]],
+  [16, [4, 27]],
+  [18, [4, 31]],
+  [22, [2, 38], [2, 39]],
+  [22, [3, 36], [2, 37]],
+  [26, [4, 43], [1, 44]],
+];
+
+// Centre rows and columns of the alignment patterns per version
+const ALIGNMENT = [
+  null,
+  [],
+  [6, 18],
+  [6, 22],
+  [6, 26],
+  [6, 30],
+  [6, 34],
+  [6, 22, 38],
+  [6, 24, 42],
+  [6, 26, 46],
+  [6, 28, 50],
+];
+
+const MASKS = [
+  (x, y) => (x + y) % 2 === 0,
+  (x, y) => y % 2 === 0,
+  (x) => x % 3 === 0,
+  (x, y) => (x + y) % 3 === 0,
+  (x, y) => (Math.floor(x / 3) + Math.floor(y / 2)) % 2 === 0,
+  (x, y) => ((x * y) % 2) + ((x * y) % 3) === 0,
+  (x, y) => (((x * y) % 2) + ((x * y) % 3)) % 2 === 0,
+  (x, y) => (((x + y) % 2) + ((x * y) % 3)) % 2 === 0,
+];
+
+// Exponents and logarithms in GF(256) modulo x⁸ + x⁴ + x³ + x² + 1
+const EXP = new Array(512);
+const LOG = new Array(256);
+for (let i = 0, value = 1; i < 255; i++) {
+  EXP[i] = EXP[i + 255] = value;
+  LOG[value] = i;
+  value = (value << 1) ^ (value & 0x80 ? 0x11d : 0);
+}
+
+function multiply(a, b) {
+  return a === 0 || b === 0 ? 0 : EXP[LOG[a] + LOG[b]];
+}
+
+// Reed–Solomon error correction codewords of `data`
+function correction(data, degree) {
+  // Generator polynomial (x - α⁰)(x - α¹)…, highest power first
+  let generator = [1];
+  for (let i = 0; i < degree; i++) {
+    const next = new Array(generator.length + 1).fill(0);
+    generator.forEach((coefficient, j) => {
+      next[j] ^= coefficient;
+      next[j + 1] ^= multiply(coefficient, EXP[i]);
+    });
+    generator = next;
+  }
+
+  const remainder = new Array(degree).fill(0);
+  for (const byte of data) {
+    const factor = byte ^ remainder.shift();
+    remainder.push(0);
+    for (let i = 0; i < degree; i++) {
+      remainder[i] ^= multiply(generator[i + 1], factor);
+    }
+  }
+  return remainder;
+}
+
+// Data codewords of `version`
+function capacity(version) {
+  const [, ...groups] = BLOCKS[version];
+  return groups.reduce((sum, [count, size]) => sum + count * size, 0);
+}
+
+// Data and error correction codewords of `bytes` in `version`, interleaved
+// by block as they are placed
+function codewords(bytes, version) {
+  const [degree, ...groups] = BLOCKS[version];
+  const length = capacity(version);
+
+  const bits = [];
+  const push = (value, length) => {
+    for (let i = length - 1; i >= 0; i--) bits.push((value >>> i) & 1);
+  };
+  push(0b0100, 4); // Byte mode
+  push(bytes.length, version < 10 ? 8 : 16);
+  for (const byte of bytes) push(byte, 8);
+  push(0, Math.min(4, length * 8 - bits.length));
+  push(0, (8 - (bits.length % 8)) % 8);
+
+  const data = [];
+  for (let i = 0; i < bits.length; i += 8) {
+    data.push(bits.slice(i, i + 8).reduce((byte, bit) => (byte << 1) | bit));
+  }
+  for (let pad = 0xec; data.length < length; pad ^= 0xec ^ 0x11) {
+    data.push(pad);
+  }
+
+  const blocks = [];
+  let offset = 0;
+  for (const [count, size] of groups) {
+    for (let i = 0; i < count; i++) {
+      const block = data.slice(offset, (offset += size));
+      blocks.push({ data: block, correction: correction(block, degree) });
+    }
+  }
+  const result = [];
+  const longest = Math.max(...blocks.map((block) => block.data.length));
+  for (let i = 0; i < longest; i++) {
+    for (const block of blocks) {
+      if (i < block.data.length) result.push(block.data[i]);
+    }
+  }
+  for (let i = 0; i < degree; i++) {
+    for (const block of blocks) result.push(block.correction[i]);
+  }
+  return result;
+}
+
+// The finder, timing and alignment patterns of `version`, with the format
+// and version information of `mask`; `{ size, modules, reserved }`
+function functionPatterns(version, mask) {
+  const size = 17 + 4 * version;
+  const modules = Array.from({ length: size }, () => Array(size).fill(false));
+  const reserved = Array.from({ length: size }, () => Array(size).fill(false));
+  const set = (x, y, dark) => {
+    modules[y][x] = dark;
+    reserved[y][x] = true;
+  };
+
+  for (let i = 0; i < size; i++) {
+    set(6, i, i % 2 === 0);
+    set(i, 6, i % 2 === 0);
+  }
+  for (const [cx, cy] of [
+    [3, 3],
+    [size - 4, 3],
+    [3, size - 4],
+  ]) {
+    for (let dy = -4; dy <= 4; dy++) {
+      for (let dx = -4; dx <= 4; dx++) {
+        const x = cx + dx;
+        const y = cy + dy;
+        if (x < 0 || y < 0 || x >= size || y >= size) continue;
+        const ring = Math.max(Math.abs(dx), Math.abs(dy));
+        set(x, y, ring !== 2 && ring !== 4);
+      }
+    }
+  }
+  const centres = ALIGNMENT[version];
+  const last = centres.length - 1;
+  centres.forEach((cx, i) => {
+    centres.forEach((cy, j) => {
+      // Not on top of the finder patterns
+      if ((i === 0 && j === 0) || (i === 0 && j === last)) return;
+      if (i === last && j === 0) return;
+      for (let dy = -2; dy <= 2; dy++) {
+        for (let dx = -2; dx <= 2; dx++) {
+          set(cx + dx, cy + dy, Math.max(Math.abs(dx), Math.abs(dy)) !== 1);
+        }
+      }
+    });
+  });
+
+  // Format information: level M (00) and the mask, BCH-coded, twice
+  const format = mask;
+  let remainder = format;
+  for (let i = 0; i < 10; i++) {
+    remainder = (remainder << 1) ^ ((remainder >>> 9) * 0x537);
+  }
+  const formatBits = ((format << 10) | remainder) ^ 0x5412;
+  const bit = (bits, i) => ((bits >>> i) & 1) === 1;
+  for (let i = 0; i <= 5; i++) set(8, i, bit(formatBits, i));
+  set(8, 7, bit(formatBits, 6));
+  set(8, 8, bit(formatBits, 7));
+  set(7, 8, bit(formatBits, 8));
+  for (let i = 9; i < 15; i++) set(14 - i, 8, bit(formatBits, i));
+  for (let i = 0; i < 8; i++) set(size - 1 - i, 8, bit(formatBits, i));
+  for (let i = 8; i < 15; i++) set(8, size - 15 + i, bit(formatBits, i));
+  set(8, size - 8, true);
+
+  // Version information from version 7 on, twice
+  if (version >= 7) {
+    remainder = version;
+    for (let i = 0; i < 12; i++) {
+      remainder = (remainder << 1) ^ ((remainder >>> 11) * 0x1f25);
+    }
+    const versionBits = (version << 12) | remainder;
+    for (let i = 0; i < 18; i++) {
+      const a = size - 11 + (i % 3);
+      const b = Math.floor(i / 3);
+      set(a, b, bit(versionBits, i));
+      set(b, a, bit(versionBits, i));
+    }
+  }
+  return { size, modules, reserved };
+}
+
+// Lower is easier to read: long runs, 2 × 2 blocks, finder-like patterns
+// and an uneven share of dark modules are penalised
+function penalty(modules) {
+  const size = modules.length;
+  let score = 0;
+  const lines = [
+    ...modules,
+    ...modules.map((_, x) => modules.map((row) => row[x])),
+  ];
+  for (const line of lines) {
+    let run = 1;
+    for (let i = 1; i <= size; i++) {
+      if (i < size && line[i] === line[i - 1]) {
+        run++;
+        continue;
+      }
+      if (run >= 5) score += run - 2;
+      run = 1;
+    }
+    const text = line.map(Number).join("");
+    score += 40 * (text.split("10111010000").length - 1);
+    score += 40 * (text.split("00001011101").length - 1);
+  }
+  for (let y = 0; y < size - 1; y++) {
+    for (let x = 0; x < size - 1; x++) {
+      const dark = modules[y][x];
+      if (
+        modules[y][x + 1] === dark &&
+        modules[y + 1][x] === dark &&
+        modules[y + 1][x + 1] === dark
+      ) {
+        score += 3;
+      }
+    }
+  }
+  const dark = modules.flat().filter(Boolean).length;
+  score += 10 * Math.floor(Math.abs((dark * 100) / (size * size) - 50) / 5);
+  return score;
+}
+
+// Dark (true) and light modules of a QR code for `text`, rows first
+export function qrCode(text) {
+  const bytes = new TextEncoder().encode(text);
+  const version = BLOCKS.findIndex(
+    (blocks, version) =>
+      blocks &&
+      4 + (version < 10 ? 8 : 16) + bytes.length * 8 <= capacity(version) * 8,
+  );
+  if (version === -1) throw new Error("Too long for a QR code");
+  const data = codewords(bytes, version);
+  // Bit `i` of the codewords; past them come remainder bits of 0
+  const dataBit = (i) =>
+    i < data.length * 8 && ((data[i >>> 3] >>> (7 - (i & 7))) & 1) === 1;
+
+  let best = null;
+  MASKS.forEach((masked, mask) => {
+    const { size, modules, reserved } = functionPatterns(version, mask);
+    // Up and down two columns at a time from the right, around the
+    // vertical timing pattern
+    let i = 0;
+    for (let right = size - 1; right >= 1; right -= 2) {
+      if (right === 6) right = 5;
+      for (let vertical = 0; vertical < size; vertical++) {
+        for (let j = 0; j < 2; j++) {
+          const x = right - j;
+          const upward = ((right + 1) & 2) === 0;
+          const y = upward ? size - 1 - vertical : vertical;
+          if (reserved[y][x]) continue;
+          modules[y][x] = dataBit(i) !== masked(x, y);
+          i++;
+        }
+      }
+    }
+    const score = penalty(modules);
+    if (!best || score < best.score) best = { modules, score };
+  });
+  return best.modules;
+}
+
+function chunk(type, data) {
+  const body = Buffer.concat([Buffer.from(type, "ascii"), data]);
+  const head = Buffer.alloc(4);
+  head.writeUInt32BE(data.length);
+  const tail = Buffer.alloc(4);
+  tail.writeUInt32BE(crc32(body));
+  return Buffer.concat([head, body, tail]);
+}
+
+// PNG of the QR code for `text`, `scale` pixels per module with the
+// `margin` (in modules) of white around it that readers need
+export function qrPng(text, { scale = 8, margin = 4 } = {}) {
+  const modules = qrCode(text);
+  const side = (modules.length + 2 * margin) * scale;
+  // Rows of 8-bit gray, each after a filter type byte (0: none)
+  const pixels = Buffer.alloc((side + 1) * side, 255);
+  for (let py = 0; py < side; py++) {
+    const row = py * (side + 1);
+    pixels[row] = 0;
+    const y = Math.floor(py / scale) - margin;
+    for (let px = 0; px < side; px++) {
+      const x = Math.floor(px / scale) - margin;
+      if (modules[y]?.[x]) pixels[row + 1 + px] = 0;
+    }
+  }
+
+  const header = Buffer.alloc(13);
+  header.writeUInt32BE(side, 0);
+  header.writeUInt32BE(side, 4);
+  header[8] = 8; // Bit depth; the rest (grayscale, no interlacing) are 0
+  return Buffer.concat([
+    Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]),
+    chunk("IHDR", header),
+    chunk("IDAT", deflateSync(pixels)),
+    chunk("IEND", Buffer.alloc(0)),
+  ]);
+}
@@ -0,0 +1,104 @@
+import { db } from "./db.js";
+import { isoDate } from "./prices.js";
+
+const ECB = "https://www.ecb.europa.eu/stats/eurofxref";
+
+// Currencies kept from the ECB reference rates (units per EUR)
+export const CURRENCIES = ["NOK", "SEK", "DKK", "USD", "GBP"];
+
+// Reference rates per date from an ECB eurofxref XML document
+export function parseRates(xml) {
+  const days = [];
+  const pattern = /<Cube time=['"](\d{4}-\d{2}-\d{2})['"]>([\s\S]*?)<\/Cube>/g;
+  for (const [, date, body] of xml.matchAll(pattern)) {
+    const rates = {};
+    for (const [, currency, rate] of body.matchAll(
+      /currency=['"]([A-Z]{3})['"]\s+rate=['"]([\d.]+)['"]/g,
+    )) {
+      if (CURRENCIES.includes(currency)) rates[currency] = parseFloat(rate);
+    }
+    days.push({ date, rates });
+  }
+  return days;
+}
+
+// Fetch and store the latest ECB rates, or the last 90 days with `history`
+export async function refreshRates(history = false) {
+  const url = `${ECB}/eurofxref-${history ? "hist-90d" : "daily"}.xml`;
+  const response = await fetch(url);
+  if (!response.ok) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+  const days = parseRates(await response.text());
+
+  const insert = db().prepare(
+    `INSERT INTO exchange_rates (date, currency, rate) VALUES (?, ?, ?)
+     ON CONFLICT (date, currency) DO UPDATE SET rate = excluded.rate`,
+  );
+  db().transaction(() => {
+    for (const { date, rates } of days) {
+      for (const [currency, rate] of Object.entries(rates)) {
+        insert.run(date, currency, rate);
+      }
+    }
+  })();
+  return days.length;
+}
+
+// Units of `currency` per EUR on `date`, from the latest reference rate on or
+// before it (there are none on weekends and holidays), or null
+export function rateOn(currency, date) {
+  if (currency === "EUR") return 1;
+  const row = db()
+    .query(
+      `SELECT date, rate FROM exchange_rates
+       WHERE currency = ? AND date <= ?
+       ORDER BY date DESC LIMIT 1`,
+    )
+    .get(currency, isoDate(date));
+  return row ? row.rate : null;
+}
+
+// How far the NOK/EUR pair implied by upstream prices on `date` is from the
+// ECB reference rate, in percent; null without prices or a stored rate
+export function checkRate(chart, date) {
+  const ecb = rateOn("NOK", date);
+  const priced = chart.filter((item) => Math.abs(item.price_eur) > 1e-6);
+  if (!ecb || priced.length === 0) return null;
+  const upstream =
+    priced.reduce((sum, item) => sum + item.price_nok / item.price_eur, 0) /
+    priced.length;
+  return {
+    date: isoDate(date),
+    upstream,
+    ecb,
+    deviation: ((upstream - ecb) / ecb) * 100,
+  };
+}
+
+// `chart` with `price_currency` per kWh in `currency` (converted from EUR at
+// each day's reference rate) and `currency`; throws if a rate is missing
+export function inCurrency(chart, currency) {
+  const rates = new Map();
+  return chart.map((item) => {
+    const date = item.time.slice(0, 10);
+    if (!rates.has(date)) {
+      rates.set(date, rateOn(currency, new Date(`${date}T00:00`)));
+    }
+    const rate = rates.get(date);
+    if (!rate) throw new Error(`No ${currency} exchange rate for ${date}`);
+    return { ...item, currency, price_currency: item.price_eur * rate };
+  });
+}
+
+// `?currency=` as an upper-case code, null if not given, or `{ message }`
+export function parseCurrency(query) {
+  if (!query.currency) return null;
+  const currency = query.currency.toUpperCase();
+  if (currency !== "EUR" && !CURRENCIES.includes(currency)) {
+    return {
+      message: `currency must be one of: EUR, ${CURRENCIES.join(", ")}`,
+    };
+  }
+  return currency;
+}
@@ -0,0 +1,116 @@
+import { db } from "./db.js";
+import { formatDateTime, formatOre } from "./locale.js";
+import { notify } from "./notify.js";
+import { onStored } from "./prices.js";
+import { average } from "./stats.js";
+import { formatLocal } from "./time.js";
+
+export const RECORDS = {
+  highest_price: "høyeste pris",
+  lowest_price: "laveste pris",
+  highest_daily_average: "høyeste døgnsnitt",
+};
+
+// Record candidates (price in NOK/kWh, with the interval or date it belongs to)
+function candidates(rows) {
+  if (rows.length === 0) return null;
+
+  let highest = rows[0];
+  let lowest = rows[0];
+  const days = new Map();
+  for (const row of rows) {
+    if (row.price_nok > highest.price_nok) highest = row;
+    if (row.price_nok < lowest.price_nok) lowest = row;
+    const day = row.time.slice(0, 10);
+    if (!days.has(day)) days.set(day, []);
+    days.get(day).push(row.price_nok);
+  }
+
+  let highestDay = null;
+  for (const [day, prices] of days) {
+    const avg = average(prices);
+    if (!highestDay || avg > highestDay.value) {
+      highestDay = { value: avg, time: day };
+    }
+  }
+
+  return {
+    highest_price: { value: highest.price_nok, time: highest.time },
+    lowest_price: { value: lowest.price_nok, time: lowest.time },
+    highest_daily_average: highestDay,
+  };
+}
+
+function beats(kind, value, record) {
+  return kind === "lowest_price" ? value < record : value > record;
+}
+
+// Current records for `region` (NOK/kWh), computed from the full history the
+// first time they are asked for
+export function getRecords(region) {
+  const rows = db()
+    .query("SELECT kind, value, time FROM records WHERE region = ?")
+    .all(region);
+  if (rows.length > 0) {
+    return Object.fromEntries(
+      rows.map((row) => [row.kind, { value: row.value, time: row.time }]),
+    );
+  }
+
+  const initial = candidates(
+    db()
+      .query(
+        "SELECT time, price_nok FROM prices WHERE region = ? ORDER BY time",
+      )
+      .all(region),
+  );
+  if (!initial) return {};
+  saveRecords(region, initial);
+  return initial;
+}
+
+function saveRecords(region, records) {
+  const upsert = db().prepare(
+    `INSERT INTO records (region, kind, value, time) VALUES (?, ?, ?, ?)
+     ON CONFLICT (region, kind) DO UPDATE SET
+       value = excluded.value,
+       time = excluded.time`,
+  );
+  db().transaction(() => {
+    for (const [kind, record] of Object.entries(records)) {
+      upsert.run(region, kind, record.value, record.time);
+    }
+  })();
+}
+
+// Compare a newly stored day against the records, and announce any new ones;
+// past days (from the backfill or an export) update the records quietly
+export async function checkRecords(region, chart, now = new Date()) {
+  const records = getRecords(region);
+  const today = candidates(chart);
+  if (!today) return;
+
+  const broken = {};
+  for (const [kind, candidate] of Object.entries(today)) {
+    const record = records[kind];
+    if (!record || beats(kind, candidate.value, record.value)) {
+      broken[kind] = candidate;
+    }
+  }
+  if (Object.keys(broken).length === 0) return;
+
+  saveRecords(region, broken);
+  if (chart[0].time.slice(0, 10) < formatLocal(now).slice(0, 10)) return;
+  for (const [kind, record] of Object.entries(broken)) {
+    await notify({
+      event: "record",
+      title: `Ny rekord i ${region}`,
+      message:
+        `Ny ${RECORDS[kind]} i ${region}: ` +
+        `${formatOre(record.value * 100)} øre/kWh (${formatDateTime(record.time)})`,
+      data: { region, kind, ...record },
+    });
+  }
+}
+
+onStored(checkRecords);
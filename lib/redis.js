@@ -0,0 +1,139 @@
+import { config } from "./config.js";
+
+export function redisSettings(overrides = {}) {
+  return {
+    url: null, // e.g. redis://localhost:6379
+    prefix: "elektron",
+    ttl_hours: 24, // How long replicas share a fetched day
+    lock_seconds: 30, // Longest a replica waits for another one's fetch
+    ...config.redis,
+    ...overrides,
+  };
+}
+
+let client = null;
+let clientUrl = null;
+
+// Client for the configured Redis, or null when replicas don't share a cache
+export function redis() {
+  const { url } = redisSettings();
+  if (!url) return null;
+  if (url !== clientUrl) {
+    if (!Bun.RedisClient) throw new Error("Redis needs Bun 1.2.9 or newer");
+    client?.close();
+    client = new Bun.RedisClient(url);
+    clientUrl = url;
+  }
+  return client;
+}
+
+function unavailable(error) {
+  console.error(`Redis unavailable: ${error.message}`);
+}
+
+function sleep(ms) {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+// Value of `key` shared by every replica: from Redis if another replica has
+// fetched it, otherwise from `fetch` in the one replica holding the lock for
+// `key` while the others wait for its result. Redis being down falls back to
+// fetching directly, as a single instance would
+export async function sharedFetch(key, fetch) {
+  const shared = redis();
+  if (!shared) return fetch();
+  const settings = redisSettings();
+  const name = `${settings.prefix}:${key}`;
+  const lock = `${name}:lock`;
+
+  const deadline = Date.now() + settings.lock_seconds * 1000;
+  while (Date.now() < deadline) {
+    let cached = null;
+    let locked = false;
+    try {
+      cached = await shared.get(name);
+      if (!cached) {
+        locked = await shared.send("SET", [
+          lock,
+          "1",
+          "NX",
+          "PX",
+          String(settings.lock_seconds * 1000),
+        ]);
+      }
+    } catch (error) {
+      unavailable(error);
+      return fetch();
+    }
+    if (cached) return JSON.parse(cached);
+
+    if (locked) {
+      try {
+        const value = await fetch();
+        await shared
+          .send("SET", [
+            name,
+            JSON.stringify(value),
+            "EX",
+            String(Math.round(settings.ttl_hours * 3600)),
+          ])
+          .catch(unavailable);
+        return value;
+      } finally {
+        await shared.del(lock).catch(unavailable);
+      }
+    }
+    await sleep(250);
+  }
+  // The lock holder is stuck; don't wait for it any longer
+  return fetch();
+}
+
+// JSON value of `key` in the shared store, or null
+export async function sharedGet(key) {
+  const shared = redis();
+  if (!shared) return null;
+  try {
+    const value = await shared.get(`${redisSettings().prefix}:${key}`);
+    return value ? JSON.parse(value) : null;
+  } catch (error) {
+    unavailable(error);
+    return null;
+  }
+}
+
+// Store `value` as JSON under `key` in the shared store
+export async function sharedSet(key, value) {
+  const shared = redis();
+  if (!shared) return;
+  await shared
+    .set(`${redisSettings().prefix}:${key}`, JSON.stringify(value))
+    .catch(unavailable);
+}
+
+// Whether this replica is the first to claim `key` within `seconds`; true
+// without Redis (or with it down), as there is no one else to defer to
+export async function claim(key, seconds) {
+  const shared = redis();
+  if (!shared) return true;
+  try {
+    const claimed = await shared.send("SET", [
+      `${redisSettings().prefix}:${key}`,
+      "1",
+      "NX",
+      "EX",
+      String(seconds),
+    ]);
+    return Boolean(claimed);
+  } catch (error) {
+    unavailable(error);
+    return true;
+  }
+}
+
+// Drop `key` from the shared cache, e.g. after storing corrected prices
+export async function forgetShared(key) {
+  const shared = redis();
+  if (!shared) return;
+  await shared.del(`${redisSettings().prefix}:${key}`).catch(unavailable);
+}
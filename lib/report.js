@@ -0,0 +1,99 @@
+import { consumptionCost, costedConsumption } from "./cost.js";
+import { isoDate, loadPrices, parseDate } from "./prices.js";
+import { average, percentChange } from "./stats.js";
+import { stromstotte, taxSettings } from "./tax.js";
+
+// `2026-09` (a month) or `2026-09-14` (a day) as `{ label, from, to,
+// previous }`, where `previous` is the month or day before; null if invalid
+export function parseReportPeriod(text) {
+  const day = parseDate(text);
+  if (day) {
+    const before = new Date(day);
+    before.setDate(before.getDate() - 1);
+    return {
+      label: isoDate(day),
+      from: day,
+      to: day,
+      previous: { label: isoDate(before), from: before, to: before },
+    };
+  }
+
+  const match = /^(\d{4})-(\d{2})$/.exec(text || "");
+  if (!match || +match[2] < 1 || +match[2] > 12) return null;
+  const month = (offset) => {
+    const from = new Date(+match[1], +match[2] - 1 + offset, 1);
+    return {
+      label: isoDate(from).slice(0, 7),
+      from,
+      to: new Date(from.getFullYear(), from.getMonth() + 1, 0),
+    };
+  };
+  return { ...month(0), previous: month(-1) };
+}
+
+// Average, extremes and strømstøtte (øre/kWh including VAT) of stored prices
+function summarize(chart, region) {
+  if (chart.length === 0) return null;
+  const settings = taxSettings(region);
+  const support = chart.map(
+    (item) => stromstotte(item, settings) * (1 + settings.vat),
+  );
+  const cheapest = chart.reduce((a, b) => (b.price < a.price ? b : a));
+  const dearest = chart.reduce((a, b) => (b.price > a.price ? b : a));
+  return {
+    average: average(chart.map((item) => item.price)),
+    min: { time: cheapest.time, price: cheapest.price },
+    max: { time: dearest.time, price: dearest.price },
+    support: average(support),
+    support_intervals: support.filter((value) => value > 0).length,
+    intervals: chart.length,
+  };
+}
+
+// Summary of a month or day in a zone compared to the one before, with the
+// cost and support received for a location's consumption when it has any
+export async function priceReport(region, period, name, location) {
+  const chart = loadPrices(region, period.from, period.to);
+  const summary = summarize(chart, region);
+  if (!summary) return null;
+
+  const before = period.previous;
+  const previous = summarize(loadPrices(region, before.from, before.to), region);
+
+  let consumption = null;
+  if (location?.consumption) {
+    const hours = await costedConsumption(
+      name,
+      location,
+      period.from,
+      period.to,
+    );
+    const settings = taxSettings(region);
+    // The support per interval as a "price", to join it with the consumption
+    const support = consumptionCost(
+      hours,
+      chart.map((item) => ({
+        time: item.time,
+        total: stromstotte(item, settings) * (1 + settings.vat),
+      })),
+    );
+    consumption = {
+      location: name,
+      kwh: hours.reduce((sum, row) => sum + row.kwh, 0),
+      cost: hours.reduce((sum, row) => sum + row.cost, 0),
+      support: support.reduce((sum, row) => sum + row.cost, 0),
+    };
+  }
+
+  return {
+    region,
+    period: period.label,
+    ...summary,
+    consumption,
+    previous: previous && {
+      period: before.label,
+      average: previous.average,
+      change: percentChange(previous.average, summary.average),
+    },
+  };
+}
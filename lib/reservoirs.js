@@ -0,0 +1,70 @@
+import { db } from "./db.js";
+
+const API =
+  "https://biapi.nve.no/magasinstatistikk/api/Magasinstatistikk/HentOffentligData";
+const DAY = 24 * 60 * 60 * 1000;
+
+let lastFetched = 0;
+
+// Area of an NVE row: NO1–NO5 for the price areas, NO for the whole country
+function area(row) {
+  if (row.omrType === "EL") return `NO${row.omrnr}`;
+  if (row.omrType === "NO") return "NO";
+  return null;
+}
+
+// Fetch the weekly filling levels from NVE and store them
+export async function refreshReservoirs() {
+  const response = await fetch(API);
+  if (!response.ok) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+  const rows = (await response.json()).filter(area);
+
+  const upsert = db().prepare(
+    `INSERT INTO reservoirs
+       (area, date, year, week, filling, filling_twh, capacity_twh)
+     VALUES (?, ?, ?, ?, ?, ?, ?)
+     ON CONFLICT (area, date) DO UPDATE SET
+       filling = excluded.filling,
+       filling_twh = excluded.filling_twh,
+       capacity_twh = excluded.capacity_twh`,
+  );
+  db().transaction(() => {
+    for (const row of rows) {
+      upsert.run(
+        area(row),
+        row.dato_Id.slice(0, 10),
+        row.iso_aar,
+        row.iso_uke,
+        row.fyllingsgrad,
+        row.fylling_TWh ?? null,
+        row.kapasitet_TWh ?? null,
+      );
+    }
+  })();
+  lastFetched = Date.now();
+  return rows.length;
+}
+
+// Stored filling levels for `area` since `from` (YYYY-MM-DD), refreshed from
+// NVE at most once a day (new figures are published weekly)
+export async function getReservoirs(area, from) {
+  if (Date.now() - lastFetched > DAY) {
+    try {
+      await refreshReservoirs();
+    } catch (error) {
+      // Serve what is stored
+      console.error(`NVE reservoir fetch failed: ${error.message}`);
+    }
+  }
+
+  return db()
+    .query(
+      `SELECT date, year, week, filling, filling_twh, capacity_twh
+       FROM reservoirs
+       WHERE area = ? AND date >= ?
+       ORDER BY date`,
+    )
+    .all(area, from);
+}
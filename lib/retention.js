@@ -0,0 +1,104 @@
+import { config } from "./config.js";
+import { db } from "./db.js";
+import { isoDate, loadPrices } from "./prices.js";
+import { summarize } from "./stats.js";
+
+// Days to keep of each table; null keeps everything. Daily price aggregates
+// are kept forever
+export function retentionSettings(overrides = {}) {
+  return {
+    prices_days: null,
+    corrections_days: null,
+    charger_actions_days: null,
+    audit_days: null,
+    ...config.retention,
+    ...overrides,
+  };
+}
+
+function cutoff(days) {
+  const date = new Date();
+  date.setHours(0, 0, 0, 0);
+  date.setDate(date.getDate() - days);
+  return date;
+}
+
+// Aggregate the prices older than the retention into `daily_prices`, delete
+// them and other expired rows, and give the space back to the file system
+export function compact(settings = retentionSettings()) {
+  const deleted = {};
+  db().transaction(() => {
+    if (settings.prices_days) {
+      const before = isoDate(cutoff(settings.prices_days));
+      db()
+        .query(
+          `INSERT OR REPLACE INTO daily_prices
+             (region, date, average, min, max, intervals)
+           SELECT region, substr(time, 1, 10),
+             AVG(price_nok) * 100, MIN(price_nok) * 100, MAX(price_nok) * 100,
+             COUNT(*)
+           FROM prices
+           WHERE time < ?
+             AND resolution = (
+               SELECT MIN(resolution) FROM prices finer
+               WHERE finer.region = prices.region
+                 AND substr(finer.time, 1, 10) = substr(prices.time, 1, 10)
+             )
+           GROUP BY region, substr(time, 1, 10)`,
+        )
+        .run(before);
+      deleted.prices = db()
+        .query("DELETE FROM prices WHERE time < ?")
+        .run(before).changes;
+    }
+    if (settings.corrections_days) {
+      deleted.corrections = db()
+        .query("DELETE FROM corrections WHERE time < ?")
+        .run(isoDate(cutoff(settings.corrections_days))).changes;
+    }
+    if (settings.charger_actions_days) {
+      deleted.charger_actions = db()
+        .query("DELETE FROM charger_actions WHERE time < ?")
+        .run(cutoff(settings.charger_actions_days).toISOString()).changes;
+    }
+    if (settings.audit_days) {
+      deleted.audit_log = db()
+        .query("DELETE FROM audit_log WHERE time < ?")
+        .run(cutoff(settings.audit_days).toISOString()).changes;
+    }
+  })();
+
+  if (Object.values(deleted).some((count) => count > 0)) {
+    db().exec("VACUUM");
+  }
+  db().exec("PRAGMA wal_checkpoint(TRUNCATE)");
+  return deleted;
+}
+
+// Average, minimum and maximum (øre/kWh) per day from `from` to `to`: from the
+// stored intervals where they are kept, and the aggregates where compacted
+export function dailyPrices(region, from, to) {
+  const days = new Map();
+  for (const item of loadPrices(region, from, to)) {
+    const date = item.time.slice(0, 10);
+    if (!days.has(date)) days.set(date, []);
+    days.get(date).push(item.price);
+  }
+
+  const end = new Date(to);
+  end.setDate(end.getDate() + 1);
+  const compacted = db()
+    .query(
+      `SELECT date, average, min, max, intervals FROM daily_prices
+       WHERE region = ? AND date >= ? AND date < ?`,
+    )
+    .all(region, isoDate(from), isoDate(end));
+
+  const stored = [...days].map(([date, prices]) => {
+    const { min, avg, max } = summarize(prices);
+    return { date, average: avg, min, max, intervals: prices.length };
+  });
+  return [...compacted.filter((row) => !days.has(row.date)), ...stored].sort(
+    (a, b) => (a.date < b.date ? -1 : 1),
+  );
+}
@@ -0,0 +1,264 @@
+import { audited } from "./audit.js";
+import { CHARGERS, chargerSettings } from "./charger.js";
+import { config } from "./config.js";
+import { formatOre } from "./locale.js";
+import { publish } from "./mqtt.js";
+import { NOTIFIERS, notify } from "./notify.js";
+import { automationPaused, deviceOverride } from "./overrides.js";
+import { currentEntry, getPrices, priceLevel } from "./prices.js";
+import { sharedGet, sharedSet } from "./redis.js";
+
+// Conditions of a rule's `when`; all given conditions must hold
+export const CONDITIONS = {
+  // Current price (øre/kWh) below or above a value
+  price_below: (value, { current }) => current.price < value,
+  price_above: (value, { current }) => current.price > value,
+  // One of the price levels (very_cheap, cheap, ...), or a single level
+  level: (value, { level }) => [value].flat().includes(level),
+  // Among the `value` cheapest or most expensive hours of the day
+  cheapest: (value, { rank }) => rank < value,
+  most_expensive: (value, { rank, count }) => rank >= count - value,
+  // Hours of day (0-23) and weekdays (1 = Monday ... 7 = Sunday)
+  hours: (value, { current }) => value.includes(current.hour),
+  weekdays: (value, { weekday }) => value.includes(weekday),
+};
+
+// Replace `{price}`, `{level}`, `{hour}` and `{region}` in action strings;
+// the price is written by `price`, a plain number unless meant for people
+function fill(template, context, price = (value) => value.toFixed(1)) {
+  return template.replace(/\{(price|level|hour|region)\}/g, (_, key) =>
+    key === "price" ? price(context.current.price) : context[key],
+  );
+}
+
+// Actions of a rule's `then`, selected by `type`
+export const ACTIONS = {
+  webhook: async (action, context) => {
+    const response = await fetch(action.url, {
+      method: action.method || "POST",
+      headers: { "content-type": "application/json", ...action.headers },
+      body: JSON.stringify({ rule: context.rule, ...context.summary }),
+    });
+    if (!response.ok) {
+      throw new Error(`HTTP error! status: ${response.status}`);
+    }
+  },
+  mqtt: async (action, context) => {
+    publish(fill(action.topic, context), fill(action.payload || "", context));
+  },
+  // Through the configured notifiers, or the action's own `notifiers`
+  notify: async (action, context) => {
+    await notify(
+      {
+        event: "rule",
+        title: fill(action.title || context.rule, context, formatOre),
+        message: fill(action.message || "", context, formatOre),
+        data: context.summary,
+        chart: {
+          region: context.region,
+          date: context.current.time.slice(0, 10),
+        },
+      },
+      action.notifiers,
+    );
+  },
+  // Pause or resume the configured EV charger
+  charger: async (action) => {
+    const settings = chargerSettings();
+    const send = CHARGERS[settings.type];
+    if (!send) throw new Error(`Unknown charger type: ${settings.type}`);
+    if (settings.dry_run) return "dry_run";
+    await send(settings, action.action);
+  },
+};
+
+// What an action does, for the audit log; webhook URLs are left out since
+// they often carry a token
+export function describeAction(action, context) {
+  switch (action.type) {
+    case "mqtt": {
+      const payload = fill(action.payload || "", context);
+      return `mqtt ${fill(action.topic, context)} ${payload}`;
+    }
+    case "notify":
+      return `notify "${fill(action.title || context.rule, context, formatOre)}"`;
+    case "charger":
+      return `charger ${action.action}`;
+    default:
+      return action.type;
+  }
+}
+
+// Why a matching rule acted: its conditions and the price at the time
+function matchReason(conditions, context) {
+  const names = Object.keys(conditions);
+  const price = formatOre(context.current.price);
+  const when = names.length > 0 ? names.join(", ") : "always";
+  return `${when} at ${price} øre/kWh (${context.level})`;
+}
+
+// Whether `rule` matches at `now`, with the outcome of each condition
+export async function evaluateRule(rule, now = new Date()) {
+  const region = rule.region || "NO2";
+  const chart = await getPrices(now, region);
+  const current = currentEntry(chart, now);
+  if (!current) return { matched: false, conditions: {} };
+
+  const prices = chart.map((item) => item.price);
+  const context = {
+    region,
+    current,
+    hour: current.hour,
+    level: priceLevel(current.price, prices),
+    rank: prices.filter((price) => price < current.price).length,
+    count: prices.length,
+    weekday: ((new Date(current.time.slice(0, 10)).getUTCDay() + 6) % 7) + 1,
+  };
+
+  const conditions = {};
+  for (const [name, value] of Object.entries(rule.when || {})) {
+    const condition = CONDITIONS[name];
+    if (!condition) throw new Error(`Unknown condition: ${name}`);
+    conditions[name] = condition(value, context);
+  }
+
+  return {
+    matched: Object.values(conditions).every(Boolean),
+    conditions,
+    context: {
+      ...context,
+      rule: rule.name,
+      summary: {
+        region,
+        time: current.time,
+        price: current.price,
+        level: context.level,
+      },
+    },
+  };
+}
+
+// Check that every condition and action of `rule` is known
+export function validateRule(rule) {
+  if (!rule || typeof rule !== "object") return "rule must be an object";
+  for (const name of Object.keys(rule.when || {})) {
+    if (!CONDITIONS[name]) return `Unknown condition: ${name}`;
+  }
+  for (const action of rule.then || []) {
+    if (!ACTIONS[action.type]) return `Unknown action type: ${action.type}`;
+    for (const notifier of action.notifiers || []) {
+      if (!NOTIFIERS[notifier.type]) {
+        return `Unknown notifier type: ${notifier.type}`;
+      }
+    }
+  }
+  const quiet = rule.quiet_hours;
+  if (
+    quiet &&
+    ![quiet.from, quiet.to].every(
+      (hour) => Number.isInteger(hour) && hour >= 0 && hour <= 24,
+    )
+  ) {
+    return "quiet_hours must be { from, to } with hours from 0 to 24";
+  }
+  return null;
+}
+
+// When a rule may notify: `quiet_hours` (`{ from, to }`, local hours, `to`
+// exclusive and wrapping past midnight; none if they are equal) and `renotify_minutes`, the least time
+// between notifications while the rule keeps matching (null: only when it
+// starts matching). Defaults from `alerts`, overridden per rule
+export function alertSettings(rule = {}) {
+  return {
+    quiet_hours: null,
+    renotify_minutes: null,
+    ...config.alerts,
+    ...(rule.quiet_hours !== undefined && { quiet_hours: rule.quiet_hours }),
+    ...(rule.renotify_minutes !== undefined && {
+      renotify_minutes: rule.renotify_minutes,
+    }),
+  };
+}
+
+// Per rule name: whether it matched last time, and when it last notified
+const alerts = new Map();
+
+export function alertState() {
+  return Object.fromEntries(alerts);
+}
+
+// Whether `hour` is within `quiet`; 24 is midnight, and a span starting where
+// it ends (e.g. 0 to 0 or 0 to 24) has no quiet hours rather than only them
+function isQuiet(hour, quiet) {
+  if (!quiet) return false;
+  const from = quiet.from % 24;
+  const to = quiet.to % 24;
+  if (from === to) return false;
+  return from < to ? hour >= from && hour < to : hour >= from || hour < to;
+}
+
+// Whether a matching rule's notify actions should run now. Notifications held
+// back by quiet hours are sent once they end, if the rule still matches
+function shouldNotify(rule, context, now) {
+  const settings = alertSettings(rule);
+  const state = alerts.get(rule.name);
+  if (isQuiet(context.hour, settings.quiet_hours)) return false;
+  if (!state?.notified_at) return true;
+  if (settings.renotify_minutes === null) return false;
+  const since = now - Date.parse(state.notified_at);
+  return since >= settings.renotify_minutes * 60000;
+}
+
+// Evaluate every configured rule and run the actions of those that match,
+// recording each in the audit log; notify actions are deduplicated and kept
+// out of quiet hours. Nothing runs while the automation is paused, and
+// charger actions don't while it is forced on or off
+export async function runRules(now = new Date()) {
+  if (await automationPaused(now)) return;
+
+  // Stateless replicas take turns running the rules, so the state is shared
+  if (config.stateless) {
+    const shared = (await sharedGet("alerts")) || {};
+    alerts.clear();
+    for (const [name, state] of Object.entries(shared)) alerts.set(name, state);
+  }
+
+  for (const rule of config.rules || []) {
+    try {
+      const { matched, conditions, context } = await evaluateRule(rule, now);
+      if (!matched) {
+        alerts.set(rule.name, { matched: false, notified_at: null });
+        continue;
+      }
+
+      const notifying = shouldNotify(rule, context, now);
+      for (const action of rule.then || []) {
+        if (action.type === "notify" && !notifying) continue;
+        if (
+          action.type === "charger" &&
+          (await deviceOverride("charger", now))
+        ) {
+          continue;
+        }
+        await audited(
+          {
+            source: `rule:${rule.name}`,
+            action: describeAction(action, context),
+            reason: matchReason(conditions, context),
+          },
+          () => ACTIONS[action.type](action, context),
+        );
+      }
+      alerts.set(rule.name, {
+        matched: true,
+        notified_at: notifying
+          ? now.toISOString()
+          : alerts.get(rule.name)?.notified_at ?? null,
+      });
+    } catch (error) {
+      console.error(`Rule ${rule.name} failed: ${error.message}`);
+    }
+  }
+
+  if (config.stateless) await sharedSet("alerts", alertState());
+}
@@ -0,0 +1,122 @@
+import { config } from "./config.js";
+import { claim } from "./redis.js";
+import { formatLocal } from "./time.js";
+
+const DAY = 24 * 60 * 60 * 1000;
+
+const jobs = new Map();
+
+// Next run after `now`: aligned to whole `every` periods, or the next of the
+// `at` times of day ("HH:MM", Norwegian local time)
+function nextRun(when, now) {
+  if (when.every) return now - (now % when.every) + when.every;
+
+  const candidates = [];
+  for (const offset of [0, 1]) {
+    const local = formatLocal(now + offset * DAY);
+    const date = local.slice(0, 10);
+    const zone = local.slice(19);
+    for (const time of when.at) {
+      candidates.push(Date.parse(`${date}T${time}:00${zone}`));
+    }
+  }
+  return Math.min(...candidates.filter((time) => time > now));
+}
+
+function describe(when) {
+  return when.every
+    ? `every ${when.every / 60000} min`
+    : `daily at ${when.at.join(", ")}`;
+}
+
+// Run a job now, unless it is still running from last time
+export async function runJob(name) {
+  const job = jobs.get(name);
+  if (!job) throw new Error(`Unknown job: ${name}`);
+  if (job.status.running) return job.status;
+
+  const started = Date.now();
+  job.status.running = true;
+  try {
+    await job.task();
+    job.status.last_error = null;
+    job.status.last_success = new Date(started).toISOString();
+  } catch (error) {
+    job.status.failures++;
+    job.status.last_error = error.message;
+    console.error(`Job ${name} failed: ${error.message}`);
+  } finally {
+    job.status.running = false;
+    job.status.runs++;
+    job.status.last_run = new Date(started).toISOString();
+    job.status.last_duration = Date.now() - started;
+  }
+  return job.status;
+}
+
+// Whether this replica runs the job's run at `time`: always, unless the job is
+// `exclusive` and another stateless replica has already claimed that run
+function claimRun(name, when, time) {
+  if (!when.exclusive || !config.stateless) return Promise.resolve(true);
+  return claim(`job:${name}:${time}`, 3600);
+}
+
+function plan(name) {
+  const job = jobs.get(name);
+  const next = nextRun(job.when, Date.now());
+  job.status.next_run = new Date(next).toISOString();
+  job.timer = setTimeout(async () => {
+    if (await claimRun(name, job.when, next)) await runJob(name);
+    // Unless it was replaced or cleared while running
+    if (jobs.get(name) === job) plan(name);
+  }, next - Date.now());
+}
+
+// Register and start a background job: `when` is `{ every: ms }` (also run
+// right away) or `{ at: ["13:00", ...] }`, with `exclusive: true` for jobs
+// only one of several stateless replicas should run, e.g. notifications
+export function schedule(name, when, task) {
+  if (jobs.has(name)) clearTimeout(jobs.get(name).timer);
+
+  jobs.set(name, {
+    when,
+    task,
+    timer: null,
+    status: {
+      name,
+      schedule: describe(when),
+      running: false,
+      runs: 0,
+      failures: 0,
+      last_run: null,
+      last_success: null,
+      last_duration: null,
+      last_error: null,
+      next_run: null,
+    },
+  });
+
+  plan(name);
+  if (when.every) {
+    const now = Date.now();
+    claimRun(name, when, now - (now % when.every)).then(
+      (claimed) => claimed && runJob(name),
+    );
+  }
+}
+
+// Whether a job called `name` is registered
+export function hasJob(name) {
+  return jobs.has(name);
+}
+
+// Stop and forget every job
+export function clearJobs() {
+  for (const job of jobs.values()) clearTimeout(job.timer);
+  jobs.clear();
+}
+
+// Status of every registered job
+export function jobStatus() {
+  return [...jobs.values()].map((job) => ({ ...job.status }));
+}
@@ -0,0 +1,129 @@
+import { readFileSync } from "fs";
+import { createSecureServer } from "http2";
+import { config } from "./config.js";
+
+export function serverSettings(overrides = {}) {
+  return {
+    port: 3000,
+    // Addresses to listen on instead of `port` on every IPv4 address, e.g.
+    // `["[::]:3000", "0.0.0.0:3000"]`; an entry may also be `{ address,
+    // tls, http2 }`, e.g. for HTTPS on a port of its own
+    listen: null,
+    // Seconds an idle keep-alive connection is kept open; longer than the
+    // 30 s between SSE keep-alives, and at most 255
+    idle_timeout_seconds: 60,
+    tls: null, // `{ cert, key }` file paths (PEM)
+    http2: false, // With `tls`, also speak HTTP/2 (falling back to HTTP/1.1)
+    max_connections: null, // HTTP/2 only; Bun.serve has no such limit
+    ...config.server,
+    ...overrides,
+  };
+}
+
+// Serve a fetch handler over HTTP/2 (and HTTP/1.1 for older clients) with
+// node's http2 module, which Bun.serve doesn't speak
+function serveHttp2(handle, settings, { hostname, port, ipv6Only }) {
+  const server = createSecureServer(
+    {
+      cert: readFileSync(settings.tls.cert),
+      key: readFileSync(settings.tls.key),
+      allowHTTP1: true,
+    },
+    async (req, res) => {
+      const headers = new Headers();
+      for (const [name, value] of Object.entries(req.headers)) {
+        if (name.startsWith(":")) continue;
+        for (const item of [value].flat()) headers.append(name, item);
+      }
+      const host = req.headers[":authority"] || req.headers.host;
+      const body = ["GET", "HEAD"].includes(req.method)
+        ? undefined
+        : await new Response(req).arrayBuffer();
+      const controller = new AbortController();
+      res.on("close", () => controller.abort());
+
+      const response = await handle(
+        new Request(`https://${host}${req.url}`, {
+          method: req.method,
+          headers,
+          body,
+          signal: controller.signal,
+        }),
+      );
+      const head = Object.fromEntries(response.headers);
+      const cookies = response.headers.getSetCookie();
+      if (cookies.length > 0) head["set-cookie"] = cookies;
+      res.writeHead(response.status, head);
+      if (!response.body) return res.end();
+      // Streamed, so server-sent events flow as they are written
+      const reader = response.body.getReader();
+      while (!controller.signal.aborted) {
+        const { done, value } = await reader.read();
+        if (done) break;
+        res.write(value);
+      }
+      res.end();
+    },
+  );
+  server.setTimeout(settings.idle_timeout_seconds * 1000);
+  if (settings.max_connections) {
+    server.maxConnections = settings.max_connections;
+  }
+  server.listen({ port, host: hostname, ipv6Only });
+  return { hostname, port, protocol: "https" };
+}
+
+// `{ hostname, port }` of `host:port`, `[IPv6]:port` or a bare port
+export function parseAddress(address) {
+  const match = /^(?:\[([0-9a-fA-F:.]+)\]|([^:]*)):(\d+)$/.exec(address);
+  if (match) {
+    return { hostname: match[1] ?? (match[2] || "0.0.0.0"), port: +match[3] };
+  }
+  if (/^\d+$/.test(address)) return { hostname: "0.0.0.0", port: +address };
+  throw new Error(`Invalid listen address: ${address}`);
+}
+
+// Listen with `handle` on `port`, or as configured in `server`; `{ hostname,
+// port, protocol }` of each listener
+export function listen(handle, port = null) {
+  const settings = serverSettings();
+  const binds = port
+    ? [{ address: `${port}` }]
+    : (settings.listen || [`${settings.port}`]).map((bind) =>
+        typeof bind === "string" ? { address: bind } : bind,
+      );
+
+  const addresses = binds.map((bind) => parseAddress(bind.address));
+  return binds.map((bind, i) => {
+    const options = { ...settings, ...bind };
+    const { hostname, port } = addresses[i];
+    // `[::]` also takes IPv4 unless told not to, which would leave an IPv4
+    // address on the same port unable to bind
+    const ipv6Only =
+      hostname.includes(":") &&
+      addresses.some((other) => other.port === port && other !== addresses[i]);
+    if (options.http2) {
+      if (!options.tls) throw new Error("http2 needs tls");
+      return serveHttp2(handle, options, { hostname, port, ipv6Only });
+    }
+
+    const server = Bun.serve({
+      hostname,
+      port,
+      ipv6Only,
+      fetch: handle,
+      idleTimeout: Math.min(options.idle_timeout_seconds, 255),
+      ...(options.tls && {
+        tls: {
+          cert: Bun.file(options.tls.cert),
+          key: Bun.file(options.tls.key),
+        },
+      }),
+    });
+    return {
+      hostname: server.hostname,
+      port: server.port,
+      protocol: options.tls ? "https" : "http",
+    };
+  });
+}
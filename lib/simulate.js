@@ -0,0 +1,58 @@
+import { chargerSettings, currentPlan } from "./charger.js";
+import { config } from "./config.js";
+import { getPrices, isoDate } from "./prices.js";
+import { describeAction, evaluateRule } from "./rules.js";
+import { fromLocal } from "./time.js";
+import { planWaterHeater, waterHeaterSettings } from "./waterheater.js";
+
+// What the automation would do over `date` without doing any of it: the
+// intervals each rule matches with the actions it would take (before quiet
+// hours and deduplication), the water heater's schedule and the charging
+// session starting that evening. Manual overrides aren't taken into account
+export async function simulateDay(date) {
+  const rules = [];
+  for (const rule of config.rules || []) {
+    const chart = await getPrices(date, rule.region || "NO2");
+    const matches = [];
+    for (const item of chart) {
+      const { matched, context } = await evaluateRule(
+        rule,
+        new Date(item.time),
+      );
+      if (!matched) continue;
+      matches.push({
+        time: item.time,
+        price: item.price,
+        actions: (rule.then || []).map((action) =>
+          describeAction(action, context),
+        ),
+      });
+    }
+    rules.push({ name: rule.name, matches });
+  }
+
+  let waterHeater = null;
+  if (config.water_heater) {
+    const settings = waterHeaterSettings();
+    waterHeater = planWaterHeater(
+      await getPrices(date, settings.region),
+      settings,
+    );
+  }
+
+  let charger = null;
+  if (config.charger?.type) {
+    const { plug_in } = chargerSettings();
+    const hour = String(plug_in).padStart(2, "0");
+    charger = await currentPlan(
+      new Date(fromLocal(`${isoDate(date)}T${hour}:00`)),
+    );
+  }
+
+  return {
+    date: isoDate(date),
+    rules,
+    water_heater: waterHeater,
+    charger,
+  };
+}
@@ -0,0 +1,69 @@
+import { ADDON, config } from "./config.js";
+import { REGIONS } from "./prices.js";
+
+// Zone pre-selected for a subpath (`/no4`, or one from `sites.paths`), or
+// null if the path isn't one
+export function pathRegion(path) {
+  const region = config.sites?.paths?.[path] || path.toUpperCase();
+  return REGIONS.includes(region) ? region : null;
+}
+
+// Subpaths with a pre-selected zone: the zones themselves and `sites.paths`
+export function sitePaths() {
+  return [
+    ...REGIONS.map((region) => region.toLowerCase()),
+    ...Object.keys(config.sites?.paths || {}),
+  ];
+}
+
+// Base URL of the pages: the Home Assistant ingress path (`X-Ingress-Path`)
+// when running as the add-on, otherwise the root. Anyone can send the header,
+// so it is ignored elsewhere, and never taken for another host (`//host`)
+export function basePath(request) {
+  const ingress = (ADDON && request.headers.get("x-ingress-path")) || "";
+  return /^\/(?!\/)[\w/-]*$/.test(ingress)
+    ? `${ingress.replace(/\/$/, "")}/`
+    : "/";
+}
+
+// Address of the dashboard (`base_url`, or where this request reached it),
+// optionally of `region`'s subpath showing `date` (YYYY-MM-DD)
+export function dashboardUrl(request, region = null, date = null) {
+  const base = config.base_url
+    ? `${config.base_url.replace(/\/$/, "")}/`
+    : new URL(basePath(request), request.url).href;
+  return (
+    base + (region ? region.toLowerCase() : "") + (date ? `?date=${date}` : "")
+  );
+}
+
+// Point the `<base href>` of a page at `basePath`, so its relative links,
+// scripts and stylesheets resolve behind the ingress proxy
+export async function withBase(request, response) {
+  const base = basePath(request);
+  if (base === "/") return;
+  const rebase = (page) =>
+    page.replace('<base href="/">', `<base href="${base}">`);
+
+  if (typeof response === "string") return rebase(response);
+  if (
+    response instanceof Response &&
+    response.headers.get("content-type")?.startsWith("text/html")
+  ) {
+    return new Response(rebase(await response.text()), {
+      status: response.status,
+      headers: response.headers,
+    });
+  }
+}
+
+// Default zone for a request: `?region=`, then the zone of the `profile` in
+// use, then the zone `sites.hosts` maps the hostname to, then NO2
+export function defaultRegion(request, query = {}, profile = null) {
+  if (REGIONS.includes(query.region)) return query.region;
+  if (REGIONS.includes(profile?.region)) return profile.region;
+
+  const host = new URL(request.url).hostname;
+  const region = config.sites?.hosts?.[host];
+  return REGIONS.includes(region) ? region : "NO2";
+}
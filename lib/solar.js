@@ -0,0 +1,92 @@
+import { config } from "./config.js";
+import { durations } from "./stats.js";
+import { location } from "./weather.js";
+
+const RAD = Math.PI / 180;
+
+export function solarSettings(region) {
+  return {
+    kwp: 0,
+    tilt: 30,
+    azimuth: 180, // Degrees from north; 180 is due south
+    losses: 0.14,
+    ...location(region),
+    ...config.solar,
+  };
+}
+
+// Sun's zenith and azimuth (degrees, azimuth from north) at `time`
+function sunPosition(time, latitude, longitude) {
+  const date = new Date(time);
+  const start = Date.UTC(date.getUTCFullYear(), 0, 0);
+  const day = Math.floor((date - start) / 86400000);
+  const hours = date.getUTCHours() + date.getUTCMinutes() / 60;
+
+  const declination = 23.44 * Math.sin((360 / 365) * (284 + day) * RAD);
+  const b = (360 / 365) * (day - 81) * RAD;
+  const equationOfTime =
+    9.87 * Math.sin(2 * b) - 7.53 * Math.cos(b) - 1.5 * Math.sin(b);
+  const solarMinutes = hours * 60 + 4 * longitude + equationOfTime;
+  const hourAngle = solarMinutes / 4 - 180;
+
+  const phi = latitude * RAD;
+  const delta = declination * RAD;
+  const h = hourAngle * RAD;
+  const cosZenith =
+    Math.sin(phi) * Math.sin(delta) +
+    Math.cos(phi) * Math.cos(delta) * Math.cos(h);
+  const zenith = Math.acos(Math.max(-1, Math.min(1, cosZenith))) / RAD;
+  const azimuth =
+    (Math.atan2(
+      Math.sin(h),
+      Math.cos(h) * Math.sin(phi) - Math.tan(delta) * Math.cos(phi),
+    ) /
+      RAD +
+      180) %
+    360;
+  return { zenith, azimuth };
+}
+
+// Clear-sky irradiance on the panel plane (W/m²)
+function planeIrradiance(time, settings) {
+  const { zenith, azimuth } = sunPosition(
+    time,
+    settings.latitude,
+    settings.longitude,
+  );
+  if (zenith >= 90) return 0;
+
+  const cosZenith = Math.cos(zenith * RAD);
+  const airMass = 1 / (cosZenith + 0.50572 * (96.07995 - zenith) ** -1.6364);
+  const direct = 1353 * 0.7 ** (airMass ** 0.678);
+  const diffuse = 0.1 * direct;
+
+  const tilt = settings.tilt * RAD;
+  const cosIncidence =
+    cosZenith * Math.cos(tilt) +
+    Math.sin(zenith * RAD) *
+      Math.sin(tilt) *
+      Math.cos((azimuth - settings.azimuth) * RAD);
+  return (
+    direct * Math.max(0, cosIncidence) + (diffuse * (1 + Math.cos(tilt))) / 2
+  );
+}
+
+// Clear-sky production (kWh) per interval of `chart`, with the spot value of
+// feeding it to the grid; an upper bound, since clouds are not modelled
+export function solarProduction(chart, settings) {
+  const lengths = durations(chart);
+  return chart.map((item, i) => {
+    const middle = Date.parse(item.time) + (lengths[i] * 3600000) / 2;
+    const irradiance = planeIrradiance(middle, settings);
+    const power = settings.kwp * (irradiance / 1000) * (1 - settings.losses);
+    const production = power * lengths[i];
+    return {
+      time: item.time,
+      hour: item.hour,
+      price: item.price,
+      production,
+      revenue: production * item.price_nok,
+    };
+  });
+}
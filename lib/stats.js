@@ -0,0 +1,646 @@
+import { formatLocal } from "./time.js";
+
+// The numbers among `values`, so a missing (NaN) price can't poison a sum or
+// a comparison
+function finite(values) {
+  return values.filter(Number.isFinite);
+}
+
+// Mean of the numbers in `values`, or null if there are none
+export function average(values) {
+  const numbers = finite(values);
+  if (numbers.length === 0) return null;
+  return numbers.reduce((a, b) => a + b, 0) / numbers.length;
+}
+
+// Smallest and largest of the numbers in `values`, or null if there are none;
+// a loop rather than `Math.min(...values)`, which fails on long lists
+export function minimum(values) {
+  let lowest = null;
+  for (const value of values) {
+    if (Number.isFinite(value) && (lowest === null || value < lowest)) {
+      lowest = value;
+    }
+  }
+  return lowest;
+}
+
+export function maximum(values) {
+  let highest = null;
+  for (const value of values) {
+    if (Number.isFinite(value) && (highest === null || value > highest)) {
+      highest = value;
+    }
+  }
+  return highest;
+}
+
+// Change from `before` to `after` in percent, or null if `before` is zero
+export function percentChange(before, after) {
+  if (before === 0) return null;
+  return ((after - before) / Math.abs(before)) * 100;
+}
+
+// Population standard deviation, or null without numbers
+export function standardDeviation(values) {
+  const mean = average(values);
+  if (mean === null) return null;
+  return Math.sqrt(average(values.map((value) => (value - mean) ** 2)));
+}
+
+// Pearson correlation coefficient of two equally long lists, or null
+export function correlation(xs, ys) {
+  if (xs.length < 2 || xs.length !== ys.length) return null;
+  const meanX = average(xs);
+  const meanY = average(ys);
+  let covariance = 0;
+  let varianceX = 0;
+  let varianceY = 0;
+  for (let i = 0; i < xs.length; i++) {
+    covariance += (xs[i] - meanX) * (ys[i] - meanY);
+    varianceX += (xs[i] - meanX) ** 2;
+    varianceY += (ys[i] - meanY) ** 2;
+  }
+  if (varianceX === 0 || varianceY === 0) return null;
+  return covariance / Math.sqrt(varianceX * varianceY);
+}
+
+// Minimum, average and maximum of a list of prices, or null if it holds no
+// numbers
+export function summarize(prices) {
+  const avg = average(prices);
+  if (avg === null) return null;
+  return { min: minimum(prices), avg, max: maximum(prices) };
+}
+
+// Average price per weekday (1 = Monday … 7 = Sunday) and per hour of day,
+// each relative to the overall average of `chart`
+export function seasonality(chart) {
+  if (chart.length === 0) return null;
+  const overall = average(chart.map((item) => item.price));
+
+  const weekdays = new Map();
+  const hours = new Map();
+  for (const item of chart) {
+    // Day of week of the local date in the timestamp
+    const weekday = new Date(item.time.slice(0, 10)).getUTCDay() || 7;
+    if (!weekdays.has(weekday)) weekdays.set(weekday, []);
+    weekdays.get(weekday).push(item.price);
+    if (!hours.has(item.hour)) hours.set(item.hour, []);
+    hours.get(item.hour).push(item.price);
+  }
+
+  const relative = (value) =>
+    overall === 0 ? 0 : ((value - overall) / Math.abs(overall)) * 100;
+  const summarizeGroup = (groups, key) =>
+    [...groups]
+      .sort(([a], [b]) => a - b)
+      .map(([group, prices]) => {
+        const avg = average(prices);
+        return { [key]: group, average: avg, relative: relative(avg) };
+      });
+
+  return {
+    average: overall,
+    weekdays: summarizeGroup(weekdays, "weekday"),
+    hours: summarizeGroup(hours, "hour"),
+  };
+}
+
+// Average daily price curves of weekdays and of weekends (Saturday and
+// Sunday), with the weekend's difference per hour of day
+export function weekendCurves(chart) {
+  const groups = { weekdays: new Map(), weekends: new Map() };
+  for (const item of chart) {
+    const weekday = new Date(item.time.slice(0, 10)).getUTCDay();
+    const hours =
+      weekday === 0 || weekday === 6 ? groups.weekends : groups.weekdays;
+    if (!hours.has(item.hour)) hours.set(item.hour, []);
+    hours.get(item.hour).push(item.price);
+  }
+  if (groups.weekdays.size === 0 || groups.weekends.size === 0) return null;
+
+  const curve = (hours) => {
+    const points = [...hours]
+      .sort(([a], [b]) => a - b)
+      .map(([hour, prices]) => ({ hour, average: average(prices) }));
+    return {
+      average: average([...hours.values()].flat()),
+      hours: points,
+    };
+  };
+  const weekdays = curve(groups.weekdays);
+  const weekends = curve(groups.weekends);
+
+  const weekdayAt = new Map(weekdays.hours.map((p) => [p.hour, p.average]));
+  return {
+    weekdays,
+    weekends,
+    difference: weekends.hours
+      .filter((point) => weekdayAt.has(point.hour))
+      .map((point) => ({
+        hour: point.hour,
+        difference: point.average - weekdayAt.get(point.hour),
+      })),
+    relative: percentChange(weekdays.average, weekends.average),
+  };
+}
+
+// Intra-day volatility per day: price range, coefficient of variation and
+// the largest hour-to-hour jump, with their averages over all days
+export function volatility(chart) {
+  const days = new Map();
+  for (const item of chart) {
+    const date = item.time.slice(0, 10);
+    if (!days.has(date)) days.set(date, []);
+    days.get(date).push(item);
+  }
+
+  const result = [...days].map(([date, items]) => {
+    const prices = items.map((item) => item.price);
+    const mean = average(prices);
+    const hours = hourly(items);
+    let jump = null;
+    for (let i = 1; i < hours.length; i++) {
+      const change = hours[i].price - hours[i - 1].price;
+      if (!jump || Math.abs(change) > Math.abs(jump.change)) {
+        jump = { time: hours[i].time, change };
+      }
+    }
+    return {
+      date,
+      range: maximum(prices) - minimum(prices),
+      // Relative to the mean's magnitude, or null when it is zero
+      cv: mean === 0 ? null : standardDeviation(prices) / Math.abs(mean),
+      jump,
+    };
+  });
+  if (result.length === 0) return null;
+
+  const cvs = result.map((day) => day.cv).filter((cv) => cv !== null);
+  return {
+    average: {
+      range: average(result.map((day) => day.range)),
+      cv: cvs.length > 0 ? average(cvs) : null,
+      jump: average(result.map((day) => Math.abs(day.jump?.change ?? 0))),
+    },
+    days: result,
+  };
+}
+
+// Direction from one price to another: "up", "down", or "flat" when the change
+// is under half an øre and 2 %
+export function trend(from, to) {
+  const change = to - from;
+  if (Math.abs(change) < Math.max(0.5, Math.abs(from) * 0.02)) return "flat";
+  return change > 0 ? "up" : "down";
+}
+
+// `chart` with the `trend` of each price from the interval before it (the last
+// of `yesterday` for the first) and from the same time of day `yesterday`;
+// null where there is nothing to compare with
+export function withTrends(chart, yesterday) {
+  const sameTime = new Map(
+    yesterday.map((item) => [item.time.slice(11, 16), item.price]),
+  );
+  return chart.map((item, i) => {
+    const previous = i > 0 ? chart[i - 1] : yesterday[yesterday.length - 1];
+    const before = sameTime.get(item.time.slice(11, 16));
+    return {
+      ...item,
+      trend_previous: previous ? trend(previous.price, item.price) : null,
+      trend_yesterday: before === undefined ? null : trend(before, item.price),
+    };
+  });
+}
+
+// Linearly interpolated `q`-quantile (0–1, clamped) of an ascending list
+export function quantile(sorted, q) {
+  if (sorted.length === 0) return null;
+  const position = (sorted.length - 1) * Math.min(Math.max(q, 0), 1);
+  const lower = Math.floor(position);
+  const upper = Math.ceil(position);
+  return sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower);
+}
+
+// The `percent` (0–100) percentile of the numbers in `values`, or null if
+// there are none
+export function percentile(values, percent) {
+  return quantile(ascending(values), percent / 100);
+}
+
+export function median(values) {
+  return percentile(values, 50);
+}
+
+// Percentile rank (0–100) of `value` in an ascending list, counting ties as
+// half below, or null for an empty list
+export function percentileRank(sorted, value) {
+  if (sorted.length === 0) return null;
+  let below = 0;
+  let high = sorted.length;
+  while (below < high) {
+    const mid = (below + high) >> 1;
+    if (sorted[mid] < value) below = mid + 1;
+    else high = mid;
+  }
+  let equal = 0;
+  while (below + equal < sorted.length && sorted[below + equal] === value) {
+    equal++;
+  }
+  return ((below + equal / 2) / sorted.length) * 100;
+}
+
+// `chart` with each price's percentile within the day and within `history`
+// (e.g. the trailing 30 days)
+export function withPercentiles(chart, history) {
+  const ascending = (items) =>
+    items.map((item) => item.price).sort((a, b) => a - b);
+  const day = ascending(chart);
+  const recent = ascending(history);
+  return chart.map((item) => ({
+    ...item,
+    percentile_day: percentileRank(day, item.price),
+    percentile_30d: percentileRank(recent, item.price),
+  }));
+}
+
+// Price duration curve: the price below which `percent` % of the intervals fall
+export function durationCurve(prices, points = 101) {
+  if (prices.length === 0) return [];
+  const sorted = [...prices].sort((a, b) => a - b);
+  return Array.from({ length: points }, (_, i) => {
+    const q = points === 1 ? 1 : i / (points - 1);
+    return { percent: q * 100, price: quantile(sorted, q) };
+  });
+}
+
+// How many of the prices fall in each bin `{ from, to, count }`, from
+// inclusive and to exclusive: `bins` equally wide bins from the lowest to the
+// highest price (which goes in the last), or bins `width` wide on multiples
+// of it. Missing prices aren't counted
+export function histogram(prices, { bins = 20, width = null } = {}) {
+  const low = minimum(prices);
+  if (low === null) return [];
+  const high = maximum(prices);
+
+  let start = low;
+  let size = (high - low) / bins;
+  let count = bins;
+  if (width) {
+    start = Math.floor(low / width) * width;
+    size = width;
+    count = Math.floor((high - start) / width) + 1;
+  } else if (size === 0) {
+    // A flat list in one bin around its price
+    start = low - 0.5;
+    size = 1;
+    count = 1;
+  }
+
+  const result = Array.from({ length: count }, (_, i) => ({
+    from: start + i * size,
+    to: start + (i + 1) * size,
+    count: 0,
+  }));
+  for (const price of finite(prices)) {
+    const index = Math.floor((price - start) / size);
+    result[Math.min(Math.max(index, 0), count - 1)].count++;
+  }
+  return result;
+}
+
+// Length in hours of each interval, from the gap to the next one (the last
+// interval is assumed to be as long as the one before it)
+export function durations(chart) {
+  return chart.map((item, i) => {
+    const next = chart[i + 1] || item;
+    const previous = chart[i - 1] || item;
+    const gap =
+      next === item
+        ? Date.parse(item.time) - Date.parse(previous.time)
+        : Date.parse(next.time) - Date.parse(item.time);
+    // Gaps across missing days are capped at one hour
+    return gap > 0 ? Math.min(gap / 3600000, 1) : 1;
+  });
+}
+
+// Hours below `below` and above `above` øre, in total and per day
+export function thresholdHours(chart, { below, above }) {
+  const lengths = durations(chart);
+  const days = new Map();
+  chart.forEach((item, i) => {
+    const date = item.time.slice(0, 10);
+    if (!days.has(date)) {
+      days.set(date, {
+        date,
+        hours: 0,
+        below: below === undefined ? null : 0,
+        above: above === undefined ? null : 0,
+      });
+    }
+    const day = days.get(date);
+    day.hours += lengths[i];
+    if (below !== undefined && item.price < below) day.below += lengths[i];
+    if (above !== undefined && item.price > above) day.above += lengths[i];
+  });
+
+  const buckets = [...days.values()];
+  const total = (key) =>
+    buckets.reduce(
+      (sum, day) => (day[key] === null ? null : sum + day[key]),
+      0,
+    );
+  return {
+    hours: total("hours"),
+    below: total("below"),
+    above: total("above"),
+    days: buckets,
+  };
+}
+
+// Index of the interval active at `now` (0 if `now` is before the first one)
+function activeIndex(chart, now) {
+  let index = 0;
+  while (index + 1 < chart.length && new Date(chart[index + 1].time) <= now) {
+    index++;
+  }
+  return index;
+}
+
+// The window of `hours` hours starting with interval `i`, or null if the
+// intervals run out before it is covered
+function windowFrom(chart, lengths, i, hours) {
+  let covered = 0;
+  let cost = 0;
+  let estimated = false;
+  for (let j = i; j < chart.length && covered < hours - 1e-9; j++) {
+    const used = Math.min(lengths[j], hours - covered);
+    covered += used;
+    cost += chart[j].price * used;
+    estimated ||= chart[j].source === "forecast";
+  }
+  if (covered < hours - 1e-9) return null;
+
+  const end = Date.parse(chart[i].time) + hours * 3600000;
+  return {
+    start: chart[i].time,
+    end: formatLocal(end),
+    average: cost / hours,
+    // Windows relying on estimated prices are flagged as such
+    ...(estimated && { source: "forecast" }),
+  };
+}
+
+// Cheapest run of consecutive intervals covering `hours` hours, starting no
+// earlier than the interval active at `now`; `{ start, end, average }` or null.
+// Runs over a missing price are skipped
+export function cheapestWindow(chart, hours, now = null) {
+  if (!(hours > 0)) return null;
+  const lengths = durations(chart);
+  let best = null;
+  for (let i = now ? activeIndex(chart, now) : 0; i < chart.length; i++) {
+    const window = windowFrom(chart, lengths, i, hours);
+    if (!window) break;
+    if (!Number.isFinite(window.average)) continue;
+    if (!best || window.average < best.average) best = window;
+  }
+  return best;
+}
+
+// The first run of consecutive intervals below `below` øre lasting at least
+// `hours` hours that hasn't ended at `now`; `{ start, end, hours, average,
+// active }` or null. A run that is already underway is `active`
+export function nextWindowBelow(chart, hours, below, now) {
+  const lengths = durations(chart);
+  let run = null;
+  const finish = () => {
+    if (!run || run.hours < hours - 1e-9) return null;
+    const start = chart[run.first].time;
+    const last = run.last;
+    return {
+      start,
+      end: formatLocal(Date.parse(chart[last].time) + lengths[last] * 3600000),
+      hours: run.hours,
+      average: run.cost / run.hours,
+      active: Date.parse(start) <= now,
+    };
+  };
+
+  for (let i = activeIndex(chart, now); i < chart.length; i++) {
+    if (chart[i].price < below) {
+      run ||= { first: i, last: i, hours: 0, cost: 0 };
+      run.last = i;
+      run.hours += lengths[i];
+      run.cost += chart[i].price * lengths[i];
+      continue;
+    }
+    const found = finish();
+    if (found) return found;
+    run = null;
+  }
+  return finish();
+}
+
+// The window of `hours` hours starting with the interval active at `now`
+export function windowAt(chart, hours, now) {
+  if (chart.length === 0 || !(hours > 0)) return null;
+  return windowFrom(chart, durations(chart), activeIndex(chart, now), hours);
+}
+
+// Average sub-hourly intervals into one entry per hour (starting at the time
+// of the hour's first interval)
+export function hourly(chart) {
+  const hours = new Map();
+  for (const item of chart) {
+    if (!hours.has(item.hour)) {
+      hours.set(item.hour, { time: item.time, prices: [] });
+    }
+    hours.get(item.hour).prices.push(item.price);
+  }
+  return [...hours].map(([hour, { time, prices }]) => ({
+    hour,
+    time,
+    price: average(prices),
+  }));
+}
+
+// Choose `count` hours of `hours` (from `hourly`) minimizing the total price,
+// such that every hour in `mustRun` is chosen and no more than `maxGap`
+// consecutive hours are skipped. Returns the chosen hours, or null if the
+// constraints can't be met with `count` hours.
+export function planHours(hours, count, { maxGap = 24, mustRun = [] } = {}) {
+  const n = hours.length;
+  const gapLimit = Math.min(maxGap, n);
+  // best[k][g]: cheapest cost so far with k hours chosen and g skipped since
+  const empty = () =>
+    Array.from({ length: count + 1 }, () => new Array(gapLimit + 1).fill(null));
+  let best = empty();
+  best[0][0] = { cost: 0, chosen: [] };
+
+  for (let i = 0; i < n; i++) {
+    const next = empty();
+    const required = mustRun.includes(hours[i].hour);
+    for (let k = 0; k <= count; k++) {
+      for (let g = 0; g <= gapLimit; g++) {
+        const state = best[k][g];
+        if (!state) continue;
+
+        const keep = (k2, g2, cost, chosen) => {
+          if (!next[k2][g2] || cost < next[k2][g2].cost) {
+            next[k2][g2] = { cost, chosen };
+          }
+        };
+        if (k < count) {
+          keep(k + 1, 0, state.cost + hours[i].price, [
+            ...state.chosen,
+            hours[i].hour,
+          ]);
+        }
+        if (!required && g < gapLimit) {
+          keep(k, g + 1, state.cost, state.chosen);
+        }
+      }
+    }
+    best = next;
+  }
+
+  const finals = best[count].filter(Boolean);
+  if (finals.length === 0) return null;
+  return finals.reduce((a, b) => (b.cost < a.cost ? b : a)).chosen;
+}
+
+// Largest-Triangle-Three-Buckets downsampling of `[{ x, y }]` (sorted by x)
+// to `threshold` points, keeping the first and last point and the visual
+// shape in between
+export function lttb(data, threshold) {
+  if (threshold >= data.length || threshold < 3) return data;
+
+  const sampled = [data[0]];
+  const size = (data.length - 2) / (threshold - 2);
+  let a = 0;
+  for (let i = 0; i < threshold - 2; i++) {
+    // Average of the next bucket, the third corner of the triangle
+    const nextStart = Math.floor((i + 1) * size) + 1;
+    const nextEnd = Math.min(Math.floor((i + 2) * size) + 1, data.length);
+    let avgX = 0;
+    let avgY = 0;
+    for (let j = nextStart; j < nextEnd; j++) {
+      avgX += data[j].x;
+      avgY += data[j].y;
+    }
+    avgX /= nextEnd - nextStart;
+    avgY /= nextEnd - nextStart;
+
+    // The point in this bucket spanning the largest triangle
+    const start = Math.floor(i * size) + 1;
+    const end = Math.floor((i + 1) * size) + 1;
+    let largest = -1;
+    let chosen = start;
+    for (let j = start; j < end; j++) {
+      const area = Math.abs(
+        (data[a].x - avgX) * (data[j].y - data[a].y) -
+          (data[a].x - data[j].x) * (avgY - data[a].y),
+      );
+      if (area > largest) {
+        largest = area;
+        chosen = j;
+      }
+    }
+    sampled.push(data[chosen]);
+    a = chosen;
+  }
+  sampled.push(data[data.length - 1]);
+  return sampled;
+}
+
+function ascending(values) {
+  return finite(values).sort((a, b) => a - b);
+}
+
+// Statistics of a day the page can show below the chart, from the day's
+// `prices`, the price `current`ly active (null on other days) and the prices
+// `remaining` from it on; null where a statistic doesn't apply
+export const DAY_STATISTICS = {
+  min: { label: "Min.", value: ({ prices }) => minimum(prices) },
+  avg: { label: "Gjn.", value: ({ prices }) => average(prices) },
+  max: { label: "Maks", value: ({ prices }) => maximum(prices) },
+  median: { label: "Median", value: ({ prices }) => median(prices) },
+  current: { label: "Nå", value: ({ current }) => current },
+  percentile: {
+    label: "Plassering",
+    unit: "%",
+    value: ({ prices, current }) =>
+      current === null ? null : percentileRank(ascending(prices), current),
+  },
+  remaining: {
+    label: "Resten av dagen",
+    value: ({ remaining }) =>
+      remaining.length > 0 ? average(remaining) : null,
+  },
+};
+
+export const DEFAULT_STATISTICS = ["min", "avg", "max"];
+
+// Comma-separated statistic names (`?stats=median,current`); the names, or
+// `{ message }` naming an unknown one
+export function parseStatistics(text) {
+  const names = text.split(",").filter(Boolean);
+  const unknown = names.find((name) => !DAY_STATISTICS[name]);
+  if (unknown || names.length === 0) {
+    return {
+      message: `stats must be among: ${Object.keys(DAY_STATISTICS).join(", ")}`,
+    };
+  }
+  return names;
+}
+
+// `[{ name, label, unit, value }]` of the `names` statistics for a day of
+// prices, leaving out those that don't apply
+export function dayStatistics(chart, names, now = new Date()) {
+  if (chart.length === 0) return [];
+  const prices = chart.map((item) => item.price);
+  const index = activeIndex(chart, now);
+  const today = formatLocal(now).slice(0, 10) === chart[0].time.slice(0, 10);
+  const context = {
+    prices,
+    current: today ? chart[index].price : null,
+    remaining: today ? prices.slice(index) : [],
+  };
+  return names
+    .map((name) => ({
+      name,
+      label: DAY_STATISTICS[name].label,
+      unit: DAY_STATISTICS[name].unit || null,
+      value: DAY_STATISTICS[name].value(context),
+    }))
+    .filter((statistic) => statistic.value !== null);
+}
+
+// How `tomorrow` compares with `today` hour by hour: the change of the
+// average (øre and %), how many hours get cheaper and dearer, and the
+// `movers` hours changing the most
+export function dayDelta(today, tomorrow, movers = 3) {
+  const before = new Map(hourly(today).map((item) => [item.hour, item.price]));
+  const changes = hourly(tomorrow)
+    .filter((item) => before.has(item.hour))
+    .map((item) => ({
+      hour: item.hour,
+      today: before.get(item.hour),
+      tomorrow: item.price,
+      change: item.price - before.get(item.hour),
+    }));
+  const averageToday = average(today.map((item) => item.price));
+  const averageTomorrow = average(tomorrow.map((item) => item.price));
+  return {
+    average_today: averageToday,
+    average_tomorrow: averageTomorrow,
+    change: averageTomorrow - averageToday,
+    change_percent: percentChange(averageToday, averageTomorrow),
+    cheaper_hours: changes.filter((item) => item.change < 0).length,
+    dearer_hours: changes.filter((item) => item.change > 0).length,
+    movers: [...changes]
+      .sort((a, b) => Math.abs(b.change) - Math.abs(a.change))
+      .slice(0, movers),
+  };
+}
@@ -0,0 +1,83 @@
+import { config } from "./config.js";
+import { formatClock, formatDate, formatOre, formatPercent } from "./locale.js";
+import { notify } from "./notify.js";
+import { getPrices, isoDate } from "./prices.js";
+import { cheapestWindow, dayDelta, summarize } from "./stats.js";
+
+export function summarySettings(overrides = {}) {
+  return {
+    regions: ["NO2"],
+    // After the day-ahead prices are usually published
+    at: "14:30",
+    ...config.summary,
+    ...overrides,
+  };
+}
+
+// "Mot i dag: 12 % lavere snitt, 15 timer billigere og 9 dyrere"
+function deltaLine(delta) {
+  const percent = delta.change_percent;
+  const change =
+    percent === null || Math.abs(percent) < 0.5
+      ? "omtrent samme snitt"
+      : `${formatPercent(Math.abs(percent))} ${percent < 0 ? "lavere" : "høyere"} snitt`;
+  return (
+    `Mot i dag: ${change}, ${delta.cheaper_hours} timer billigere ` +
+    `og ${delta.dearer_hours} dyrere`
+  );
+}
+
+// Tomorrow's prices in each region as a "summary" notification with its chart
+export async function sendSummary(settings = summarySettings()) {
+  const tomorrow = new Date();
+  tomorrow.setDate(tomorrow.getDate() + 1);
+  const date = isoDate(tomorrow);
+
+  const missing = [];
+  for (const region of settings.regions) {
+    let chart;
+    try {
+      chart = await getPrices(tomorrow, region);
+    } catch (error) {
+      missing.push(region);
+      continue;
+    }
+
+    const { avg } = summarize(chart.map((item) => item.price));
+    const cheapest = chart.reduce((a, b) => (b.price < a.price ? b : a));
+    const dearest = chart.reduce((a, b) => (b.price > a.price ? b : a));
+    const window = cheapestWindow(chart, 3);
+    let delta = null;
+    try {
+      delta = dayDelta(await getPrices(new Date(), region), chart);
+    } catch (error) {
+      // Today missing is no reason to hold tomorrow's summary back
+    }
+    const lines = [
+      `Snitt: ${formatOre(avg)} øre/kWh`,
+      `Laveste: ${formatOre(cheapest.price)} øre/kWh kl. ${formatClock(cheapest.time)}`,
+      `Høyeste: ${formatOre(dearest.price)} øre/kWh kl. ${formatClock(dearest.time)}`,
+      ...(window
+        ? [`Billigste tre timer: ${formatClock(window.start)}–${formatClock(window.end)}`]
+        : []),
+      ...(delta ? [deltaLine(delta)] : []),
+    ];
+    await notify({
+      event: "summary",
+      title: `Strømprisene i ${region} i morgen (${formatDate(date)})`,
+      message: lines.join("\n"),
+      data: {
+        region,
+        date,
+        average: avg,
+        min: cheapest,
+        max: dearest,
+        delta,
+      },
+      chart: { region, date },
+    });
+  }
+  if (missing.length > 0) {
+    throw new Error(`No prices for ${missing.join(", ")} tomorrow`);
+  }
+}
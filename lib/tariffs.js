@@ -0,0 +1,65 @@
+import { readFileSync } from "fs";
+import { dirname, join } from "path";
+import { config } from "./config.js";
+import { average } from "./stats.js";
+
+// Nettleie presets per grid company: energy rates (øre/kWh) with their night
+// window and optional winter rates, and monthly capacity steps (NOK), all
+// excluding VAT. Approximate; check them against the grid company's prices
+export const TARIFFS = JSON.parse(
+  readFileSync(
+    join(dirname(new URL(import.meta.url).pathname), "tariffs.json"),
+    "utf8",
+  ),
+);
+
+// The configured tariff: a preset by name (`tariff.preset`) with any fields
+// overridden, a fully custom structure, or null if none is configured
+export function tariffSettings(tariff = config.tariff) {
+  if (!tariff) return null;
+
+  const { preset, ...overrides } = tariff;
+  if (preset && !TARIFFS[preset]) {
+    throw new Error(`Unknown tariff preset: ${preset}`);
+  }
+  return { ...TARIFFS[preset], ...overrides };
+}
+
+// Energy part of the nettleie (øre/kWh excluding VAT) for the interval
+// starting at `time` (upstream local time)
+export function gridRate(time, tariff) {
+  const date = new Date(time.slice(0, 10));
+  const month = date.getUTCMonth() + 1;
+  const weekend = [0, 6].includes(date.getUTCDay());
+  const hour = parseInt(time.slice(11, 13));
+
+  const { from, to, weekends } = tariff.night;
+  const night =
+    (weekends && weekend) ||
+    (from > to ? hour >= from || hour < to : hour >= from && hour < to);
+
+  const energy = tariff.winter?.months.includes(month)
+    ? tariff.winter.energy
+    : tariff.energy;
+  return night ? energy.night : energy.day;
+}
+
+// Monthly capacity charge (NOK excluding VAT) for a peak of `kw`
+export function capacityCharge(kw, tariff) {
+  const step = tariff.capacity.find(
+    (item) => item.max_kw === null || kw <= item.max_kw,
+  );
+  return step.nok_per_month;
+}
+
+// Peak the capacity step is chosen by: the average of the three highest
+// hourly consumptions (kWh ≈ kW) on different days
+export function monthlyPeak(hours) {
+  const days = new Map();
+  for (const row of hours) {
+    const day = row.time.slice(0, 10);
+    days.set(day, Math.max(days.get(day) || 0, row.kwh));
+  }
+  const top = [...days.values()].sort((a, b) => b - a).slice(0, 3);
+  return average(top) ?? 0;
+}
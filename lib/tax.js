@@ -0,0 +1,61 @@
+import { config } from "./config.js";
+
+// Elavgift in øre/kWh excluding VAT: a reduced rate for January–March and the
+// ordinary rate for the rest of the year. Override or add years with
+// `tax.elavgift`, e.g. `{ "2027": { "winter": 7.13, "ordinary": 7.13 } }`
+export const ELAVGIFT = {
+  2023: { winter: 9.16, ordinary: 15.41 },
+  2024: { winter: 9.51, ordinary: 16.44 },
+  2025: { winter: 9.79, ordinary: 15.66 },
+  2026: { winter: 7.13, ordinary: 7.13 },
+};
+
+export function taxSettings(region) {
+  return {
+    // VAT is not charged on electricity in Nord-Norge (NO4)
+    vat: region === "NO4" ? 0 : 0.25,
+    // Finnmark and parts of Nord-Troms are exempt from elavgift
+    elavgift_exempt: false,
+    ...config.tax,
+  };
+}
+
+// Elavgift (øre/kWh, excluding VAT) for the interval starting at `time`
+export function elavgift(time, settings) {
+  if (settings.elavgift_exempt) return 0;
+
+  // The upstream local time, so the month is the Norwegian one
+  const year = parseInt(time.slice(0, 4));
+  const month = parseInt(time.slice(5, 7));
+  const rates = { ...ELAVGIFT, ...settings.elavgift };
+  const known = Object.keys(rates)
+    .map(Number)
+    .filter((rateYear) => rateYear <= year);
+  // Years not in the table use the latest known rates
+  const rate = rates[known.length > 0 ? Math.max(...known) : year];
+  if (!rate) return 0;
+  return month <= 3 ? rate.winter : rate.ordinary;
+}
+
+// Strømstøtte threshold in øre/kWh excluding VAT; the state covers 90 % of
+// the spot price above it. Override or add years with `tax.stromstotte`
+export const STROMSTOTTE = {
+  2023: 70,
+  2024: 73,
+  2025: 75,
+  2026: 77,
+};
+
+// Strømstøtte (øre/kWh, excluding VAT) for a price interval. Computed per
+// interval as it has been since September 2024; earlier months were settled
+// on the monthly average, so this is an approximation for those
+export function stromstotte(item, settings) {
+  const year = parseInt(item.time.slice(0, 4));
+  const thresholds = { ...STROMSTOTTE, ...settings.stromstotte };
+  const known = Object.keys(thresholds)
+    .map(Number)
+    .filter((thresholdYear) => thresholdYear <= year);
+  if (known.length === 0) return 0;
+  const threshold = thresholds[Math.max(...known)];
+  return Math.max(0, item.price - threshold) * 0.9;
+}
@@ -0,0 +1,51 @@
+export const TIME_ZONE = "Europe/Oslo";
+
+const PARTS = new Intl.DateTimeFormat("en-GB", {
+  timeZone: TIME_ZONE,
+  year: "numeric",
+  month: "2-digit",
+  day: "2-digit",
+  hour: "2-digit",
+  minute: "2-digit",
+  second: "2-digit",
+  hourCycle: "h23",
+  timeZoneName: "longOffset",
+});
+
+// Timestamp in the same form as the upstream `time_start` (Norwegian local time)
+export function formatLocal(time) {
+  const parts = Object.fromEntries(
+    PARTS.formatToParts(new Date(time)).map((part) => [part.type, part.value]),
+  );
+  const offset = parts.timeZoneName.replace("GMT", "") || "+00:00";
+  return (
+    `${parts.year}-${parts.month}-${parts.day}` +
+    `T${parts.hour}:${parts.minute}:${parts.second}${offset}`
+  );
+}
+
+export const MINUTE = 60 * 1000;
+export const HOUR = 60 * MINUTE;
+
+// Epoch ms of a Norwegian wall-clock time (`YYYY-MM-DDTHH:MM`); the repeated
+// hour in October resolves to its first (summer time) occurrence, and the
+// skipped hour in March to the hour after
+export function fromLocal(wall) {
+  const utc = Date.parse(`${wall}:00Z`);
+  // Norwegian local time is UTC+2 in summer and UTC+1 in winter
+  const candidates = [utc - 2 * HOUR, utc - HOUR];
+  return (
+    candidates.find((time) => formatLocal(time).slice(0, 16) === wall) ??
+    utc - HOUR
+  );
+}
+
+// Hours in a duration like `2h`, `90m`, `1h30m` or a bare number of hours,
+// or null if it isn't one
+export function parseDuration(text) {
+  const match = /^(?:(\d+(?:\.\d+)?)h)?(?:(\d+)m)?$/.exec(text || "");
+  if (match && (match[1] || match[2])) {
+    return parseFloat(match[1] || 0) + parseInt(match[2] || 0) / 60;
+  }
+  return /^\d+(?:\.\d+)?$/.test(text || "") ? parseFloat(text) : null;
+}
@@ -0,0 +1,162 @@
+import { formatDate, formatHour, formatOre } from "./locale.js";
+import { REGIONS, currentEntry, getPrices, isoDate } from "./prices.js";
+import { hourly, summarize } from "./stats.js";
+
+const ESC = "\x1b[";
+const BLOCKS = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+const CHART_HEIGHT = 12;
+
+function sameDay(a, b) {
+  return (
+    a.getFullYear() === b.getFullYear() &&
+    a.getMonth() === b.getMonth() &&
+    a.getDate() === b.getDate()
+  );
+}
+
+// Step chart as rows of text, one column block per hour
+function renderChart(hours, selected, width) {
+  const prices = hours.map((item) => item.price);
+  const low = Math.min(0, ...prices);
+  const high = Math.max(0, ...prices);
+  const range = high - low || 1;
+  const columnWidth = Math.max(1, Math.floor((width - 8) / hours.length));
+
+  const rows = [];
+  for (let row = CHART_HEIGHT - 1; row >= 0; row--) {
+    const label =
+      row === CHART_HEIGHT - 1
+        ? formatOre(high).padStart(6)
+        : row === 0
+          ? formatOre(low).padStart(6)
+          : "      ";
+    let line = label + " │";
+    for (let i = 0; i < hours.length; i++) {
+      // Height of the bar in eighths of a row
+      const eighths = Math.round(
+        ((hours[i].price - low) / range) * CHART_HEIGHT * 8,
+      );
+      const fill = Math.max(0, Math.min(8, eighths - row * 8));
+      const cell = BLOCKS[fill].repeat(columnWidth);
+      line += i === selected ? `${ESC}7m${cell}${ESC}0m` : cell;
+    }
+    rows.push(line);
+  }
+
+  // Hour labels, spaced so they don't overlap on narrow columns
+  const step = Math.ceil(3 / columnWidth);
+  const axis = "       └" + "─".repeat(hours.length * columnWidth);
+  let labels = "";
+  for (let i = 0; i < hours.length; i += step) {
+    labels =
+      labels.padEnd(8 + i * columnWidth) +
+      hours[i].hour.toString().padStart(2, "0");
+  }
+  rows.push(axis, labels.slice(0, width));
+  return rows;
+}
+
+// Interactive terminal dashboard: `elektron tui [region]`
+export async function tui(region = "NO2") {
+  const { stdin, stdout } = process;
+  if (!stdin.isTTY) {
+    throw new Error("elektron tui must be run in a terminal");
+  }
+
+  let regionIndex = Math.max(0, REGIONS.indexOf(region));
+  let date = new Date();
+  let hours = [];
+  let selected = 0;
+  let error = null;
+
+  async function load() {
+    error = null;
+    try {
+      const chart = await getPrices(date, REGIONS[regionIndex]);
+      hours = hourly(chart);
+      const now = new Date();
+      const current = sameDay(date, now) ? currentEntry(chart, now) : null;
+      selected = current
+        ? hours.findIndex((item) => item.hour === current.hour)
+        : 0;
+    } catch (err) {
+      hours = [];
+      error = "Er du i fremtiden? Neste dags priser blir tilgjengelige rundt klokken 13.";
+    }
+  }
+
+  function draw() {
+    const width = stdout.columns || 80;
+    const lines = [
+      `${ESC}1mStrømpriser (øre/kWh) den ${formatDate(isoDate(date))} i ${REGIONS[regionIndex]}${ESC}0m`,
+      "",
+    ];
+
+    if (error || hours.length === 0) {
+      lines.push(error || "Hmm. Ingen data.");
+    } else {
+      lines.push(...renderChart(hours, selected, width), "");
+
+      const item = hours[selected];
+      lines.push(
+        `${ESC}1m${formatHour(item.hour)} - ${formatOre(item.price)} øre${ESC}0m`,
+      );
+
+      const { min, avg, max } = summarize(hours.map((h) => h.price));
+      lines.push(
+        `Min.: ${formatOre(min)}   Gjn.: ${formatOre(avg)}   Maks: ${formatOre(max)}`,
+      );
+    }
+
+    lines.push(
+      "",
+      `${ESC}2m←/→ time   p/n dag   r region   q avslutt${ESC}0m`,
+    );
+    stdout.write(`${ESC}H${ESC}2J` + lines.join("\n"));
+  }
+
+  function quit() {
+    stdout.write(`${ESC}?25h${ESC}?1049l`);
+    stdin.setRawMode(false);
+    process.exit(0);
+  }
+
+  stdout.write(`${ESC}?1049h${ESC}?25l`);
+  stdin.setRawMode(true);
+  stdin.setEncoding("utf8");
+
+  await load();
+  draw();
+
+  stdin.on("data", async (key) => {
+    switch (key) {
+      case "q":
+      case "\x03": // Ctrl+C
+        quit();
+        return;
+      case "\x1b[D":
+      case "h":
+        selected = Math.max(0, selected - 1);
+        break;
+      case "\x1b[C":
+      case "l":
+        selected = Math.min(hours.length - 1, selected + 1);
+        break;
+      case "p":
+      case "n":
+        date = new Date(date);
+        date.setDate(date.getDate() + (key === "n" ? 1 : -1));
+        await load();
+        break;
+      case "r":
+        regionIndex = (regionIndex + 1) % REGIONS.length;
+        await load();
+        break;
+      default:
+        return;
+    }
+    draw();
+  });
+
+  stdout.on("resize", draw);
+}
@@ -0,0 +1,35 @@
+// Units prices can be presented in: retail øre/kWh (the default), or the
+// wholesale EUR/MWh traders quote, both without taxes
+export const UNITS = {
+  ore: { label: "øre/kWh", short: "øre", value: (item) => item.price },
+  eur_mwh: {
+    label: "EUR/MWh",
+    short: "EUR",
+    value: (item) => item.price_eur * 1000,
+  },
+};
+
+// Whether `unit` is one of `UNITS` (and not, say, `toString`)
+function known(unit) {
+  return typeof unit === "string" && Object.hasOwn(UNITS, unit);
+}
+
+// `?unit=`, then the `unit` cookie, then øre/kWh
+export function preferredUnit(request, query = {}) {
+  if (known(query.unit)) return query.unit;
+  const cookie = /(?:^|;\s*)unit=([^;]+)/.exec(
+    request.headers.get("cookie") || "",
+  );
+  return cookie && known(cookie[1]) ? cookie[1] : "ore";
+}
+
+// Remember an explicitly chosen `?unit=` for a year
+export function unitCookie(unit) {
+  return `unit=${unit}; Path=/; Max-Age=31536000; SameSite=Lax`;
+}
+
+// `chart` with `price` in `unit`
+export function inUnit(chart, unit) {
+  if (unit === "ore") return chart;
+  return chart.map((item) => ({ ...item, price: UNITS[unit].value(item) }));
+}
@@ -0,0 +1,67 @@
+import { execSync } from "child_process";
+import { readFileSync } from "fs";
+import { dirname, join } from "path";
+import { config } from "./config.js";
+
+const ROOT = join(dirname(new URL(import.meta.url).pathname), "..");
+
+const { version } = JSON.parse(
+  readFileSync(join(ROOT, "package.json"), "utf8"),
+);
+
+// Set by the build (e.g. `GIT_COMMIT=$(git rev-parse HEAD)`), or read from
+// the checkout when running from source
+function gitCommit() {
+  if (process.env.GIT_COMMIT) return process.env.GIT_COMMIT;
+  try {
+    return execSync("git rev-parse HEAD", { cwd: ROOT, stdio: "pipe" })
+      .toString()
+      .trim();
+  } catch (error) {
+    return null;
+  }
+}
+
+const commit = gitCommit();
+const started = new Date().toISOString();
+
+// Integrations switched on by the current configuration
+function features() {
+  return {
+    influx: Boolean(config.influx?.url),
+    mqtt: Boolean(config.mqtt?.url),
+    charger: config.charger?.type || null,
+    water_heater: Boolean(config.mqtt?.url && config.water_heater),
+    rules: (config.rules || []).length,
+    notifiers: (config.notifiers || []).map((notifier) => notifier.type),
+    co2: Boolean(config.co2?.token),
+    forecast_provider: config.forecast?.provider?.name || null,
+    admin: Boolean(config.admin?.token),
+    pdf: Boolean(config.pdf?.command),
+    modbus: Boolean(config.modbus),
+    redis: Boolean(config.redis?.url),
+    stateless: Boolean(config.stateless),
+  };
+}
+
+// What is running: version, commit, build/start time, features and a
+// summary of the configuration without credentials
+export function versionInfo() {
+  return {
+    name: "elektron",
+    version,
+    commit,
+    build_time: process.env.BUILD_TIME || null,
+    started,
+    runtime: process.versions.bun
+      ? `bun ${process.versions.bun}`
+      : `node ${process.versions.node}`,
+    features: features(),
+    config: {
+      database: config.database,
+      backfill_days: config.backfill_days,
+      tasks: config.tasks,
+      anomalies: config.anomalies || null,
+    },
+  };
+}
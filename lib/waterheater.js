@@ -0,0 +1,73 @@
+import { auditLog, audited } from "./audit.js";
+import { config } from "./config.js";
+import { publish } from "./mqtt.js";
+import { automationPaused, deviceOverride } from "./overrides.js";
+import { currentEntry, getPrices } from "./prices.js";
+import { hourly, planHours } from "./stats.js";
+
+export function waterHeaterSettings(overrides = {}) {
+  return {
+    region: "NO2",
+    hours: 6, // Heating hours per day
+    max_gap: 8, // Longest run of hours without heating
+    must_run: [], // Hours that always heat, e.g. before the morning showers
+    ...config.water_heater,
+    ...overrides,
+  };
+}
+
+// Heating schedule for one day of prices; null if the settings can't be met
+export function planWaterHeater(chart, settings) {
+  const hours = hourly(chart);
+  const chosen = planHours(hours, Math.min(settings.hours, hours.length), {
+    maxGap: settings.max_gap,
+    mustRun: settings.must_run,
+  });
+  if (!chosen) return null;
+
+  return hours.map((item) => ({ ...item, on: chosen.includes(item.hour) }));
+}
+
+// Why the water heater is on or off in `hour`
+function heaterReason(hour, on, settings, forced) {
+  if (forced) {
+    return `forced ${on ? "on" : "off"} until ${forced.until || "cleared"}`;
+  }
+  if (settings.must_run.includes(hour)) return `${hour}:00 must always heat`;
+  return on
+    ? `${hour}:00 is one of the ${settings.hours} cheapest heating hours`
+    : `${hour}:00 is not among the ${settings.hours} heating hours`;
+}
+
+// Publish today's schedule and the current on/off state (or the one it is
+// forced to) to MQTT, recording each switch in the audit log. The state is
+// left alone while the automation is paused
+export async function publishWaterHeater() {
+  const settings = waterHeaterSettings();
+  const now = new Date();
+  const chart = await getPrices(now, settings.region);
+  const schedule = planWaterHeater(chart, settings);
+  if (!schedule) throw new Error("No schedule satisfies the settings");
+  publish("water_heater/schedule", schedule);
+  if (await automationPaused(now)) return;
+
+  const current = currentEntry(chart, now);
+  const forced = await deviceOverride("water_heater", now);
+  const on = forced
+    ? forced.on
+    : schedule.some((item) => item.on && item.hour === current?.hour);
+  const state = on ? "ON" : "OFF";
+  const [last] = auditLog({ limit: 1, source: "water_heater" });
+  if (last?.action === `state ${state}` && last.result === "ok") {
+    publish("water_heater/state", state);
+  } else {
+    await audited(
+      {
+        source: "water_heater",
+        action: `state ${state}`,
+        reason: heaterReason(current?.hour, on, settings, forced),
+      },
+      () => publish("water_heater/state", state),
+    );
+  }
+}
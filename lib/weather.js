@@ -0,0 +1,95 @@
+import { config } from "./config.js";
+import { db } from "./db.js";
+import { isoDate } from "./prices.js";
+
+const API = "https://api.met.no/weatherapi/locationforecast/2.0/compact";
+const TTL = 60 * 60 * 1000;
+const HOUR = 60 * 60 * 1000;
+
+// A representative city per price area, unless `weather.locations` says otherwise
+const LOCATIONS = {
+  NO1: { latitude: 59.91, longitude: 10.75 }, // Oslo
+  NO2: { latitude: 58.15, longitude: 8.0 }, // Kristiansand
+  NO3: { latitude: 63.43, longitude: 10.39 }, // Trondheim
+  NO4: { latitude: 69.65, longitude: 18.96 }, // Tromsø
+  NO5: { latitude: 60.39, longitude: 5.32 }, // Bergen
+};
+
+const fetched = new Map();
+
+export function location(region) {
+  const { latitude, longitude } = {
+    ...LOCATIONS[region],
+    ...config.weather?.locations?.[region],
+  };
+  return { latitude, longitude, key: `${latitude},${longitude}` };
+}
+
+// Fetch met.no's temperature forecast for `region` (at most hourly, as their
+// terms require) and store it; stored values accumulate into a history
+export async function refreshTemperatures(region) {
+  const place = location(region);
+  if (Date.now() - (fetched.get(place.key) || 0) < TTL) return;
+
+  const response = await fetch(
+    `${API}?lat=${place.latitude}&lon=${place.longitude}`,
+    {
+      headers: {
+        "user-agent":
+          config.weather?.user_agent ||
+          "elektron (https://github.com/hallvardnmbu/elektron)",
+      },
+    },
+  );
+  if (!response.ok) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+  const body = await response.json();
+
+  const upsert = db().prepare(
+    `INSERT INTO temperatures (location, time, temperature) VALUES (?, ?, ?)
+     ON CONFLICT (location, time) DO UPDATE SET
+       temperature = excluded.temperature`,
+  );
+  db().transaction(() => {
+    for (const entry of body.properties?.timeseries || []) {
+      const temperature = entry.data?.instant?.details?.air_temperature;
+      if (temperature !== undefined) {
+        upsert.run(place.key, entry.time, temperature);
+      }
+    }
+  })();
+  fetched.set(place.key, Date.now());
+}
+
+// Stored temperatures for `region` from `from` to `to` (inclusive local dates),
+// keyed by the start of the hour (ms)
+export function loadTemperatures(region, from, to) {
+  const start = new Date(from);
+  start.setDate(start.getDate() - 1);
+  const end = new Date(to);
+  end.setDate(end.getDate() + 2);
+
+  const rows = db()
+    .query(
+      `SELECT time, temperature FROM temperatures
+       WHERE location = ? AND time >= ? AND time < ?`,
+    )
+    .all(location(region).key, isoDate(start), isoDate(end));
+  return new Map(
+    rows.map((row) => [
+      Math.floor(Date.parse(row.time) / HOUR) * HOUR,
+      row.temperature,
+    ]),
+  );
+}
+
+// `chart` with each interval's temperature (°C, null where unknown)
+export function withTemperature(chart, temperatures) {
+  return chart.map((item) => ({
+    ...item,
+    temperature:
+      temperatures.get(Math.floor(Date.parse(item.time) / HOUR) * HOUR) ??
+      null,
+  }));
+}
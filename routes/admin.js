@@ -0,0 +1,204 @@
+import { Elysia } from "elysia";
+import { auditLog } from "../lib/audit.js";
+import { authorize } from "../lib/auth.js";
+import { createBackup } from "../lib/backup.js";
+import { importFile } from "../lib/importers.js";
+import { reload } from "../lib/jobs.js";
+import {
+  DEVICES,
+  clearOverride,
+  forceDevice,
+  overrideState,
+  pauseAutomation,
+  resumeAutomation,
+  validHours,
+} from "../lib/overrides.js";
+import { renderAdmin } from "../lib/pages.js";
+import { parseDate } from "../lib/prices.js";
+import { jobStatus, runJob } from "../lib/scheduler.js";
+import { simulateDay } from "../lib/simulate.js";
+
+function badRequest(message) {
+  return Response.json({ message }, { status: 400 });
+}
+
+const admin = new Elysia({ prefix: "/admin" })
+  // Controls for the automation; the data comes from the routes below
+  .get("/", () => renderAdmin(DEVICES))
+  // Whether the automation is paused and which devices are forced
+  .get("/automation", async () => Response.json(await overrideState()), {
+    beforeHandle: authorize,
+  })
+  // Stop every rule and device integration, for `{ hours }` if given
+  .post(
+    "/automation/pause",
+    async ({ body }) => {
+      const hours = body?.hours ?? null;
+      if (!validHours(hours)) {
+        return badRequest("hours must be between 0 and 168");
+      }
+      await pauseAutomation(hours);
+      return Response.json(await overrideState());
+    },
+    { beforeHandle: authorize },
+  )
+  .post(
+    "/automation/resume",
+    async () => {
+      await resumeAutomation();
+      return Response.json(await overrideState());
+    },
+    { beforeHandle: authorize },
+  )
+  // Force a device `{ on, hours }` whatever its schedule says
+  .put(
+    "/devices/:device/override",
+    async ({ params, body }) => {
+      if (!DEVICES.includes(params.device)) {
+        return Response.json(
+          { message: `Unknown device: ${params.device}` },
+          { status: 404 },
+        );
+      }
+      if (typeof body?.on !== "boolean") {
+        return badRequest("on must be true or false");
+      }
+      const hours = body.hours ?? null;
+      if (!validHours(hours)) {
+        return badRequest("hours must be between 0 and 168");
+      }
+      await forceDevice(params.device, body.on, hours);
+      return Response.json(await overrideState());
+    },
+    { beforeHandle: authorize },
+  )
+  // Back to the schedule
+  .delete(
+    "/devices/:device/override",
+    async ({ params }) => {
+      if (!DEVICES.includes(params.device)) {
+        return Response.json(
+          { message: `Unknown device: ${params.device}` },
+          { status: 404 },
+        );
+      }
+      await clearOverride(params.device);
+      return Response.json(await overrideState());
+    },
+    { beforeHandle: authorize },
+  )
+  // What the rules, water heater and charger would do on `?date=` (default
+  // today), without doing it
+  .get(
+    "/simulate",
+    async ({ query }) => {
+      const date = query.date ? parseDate(query.date) : new Date();
+      if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+      try {
+        return Response.json(await simulateDay(date));
+      } catch (error) {
+        return Response.json(
+          { message: "Finner ikke noe data. :-(" },
+          { status: 404 },
+        );
+      }
+    },
+    { beforeHandle: authorize },
+  )
+  // Background jobs with their last run, last error and next run
+  .get("/jobs", () => Response.json({ jobs: jobStatus() }), {
+    beforeHandle: authorize,
+  })
+  // What the rules and device integrations did, and why, newest first;
+  // `?source=` (e.g. `rule:` or `charger`), `?since=` and `?limit=`
+  .get(
+    "/audit",
+    ({ query }) => {
+      const limit = query.limit ? parseInt(query.limit) : 100;
+      if (isNaN(limit) || limit < 1 || limit > 1000) {
+        return badRequest("limit must be between 1 and 1000");
+      }
+      const since = query.since ? new Date(query.since) : null;
+      if (since && isNaN(since)) {
+        return badRequest("since must be an ISO date or time");
+      }
+      return Response.json({
+        actions: auditLog({
+          limit,
+          source: query.source || null,
+          since: since && since.toISOString(),
+        }),
+      });
+    },
+    { beforeHandle: authorize },
+  )
+  // Run a job right away
+  .post(
+    "/jobs/:name/run",
+    async ({ params }) => {
+      try {
+        return Response.json(await runJob(params.name));
+      } catch (error) {
+        return Response.json({ message: error.message }, { status: 404 });
+      }
+    },
+    { beforeHandle: authorize },
+  )
+  // Re-read the config file without restarting (same as SIGHUP)
+  .post(
+    "/reload",
+    () => {
+      try {
+        reload();
+      } catch (error) {
+        return Response.json({ message: error.message }, { status: 500 });
+      }
+      return Response.json({ message: "Reloaded" });
+    },
+    { beforeHandle: authorize },
+  )
+  // Download the database and config (`?format=gz` for gzip instead of zstd)
+  .get(
+    "/backup",
+    async ({ query }) => {
+      const format = query.format === "gz" ? "gz" : "zst";
+      let archive;
+      try {
+        archive = await createBackup(format);
+      } catch (error) {
+        return Response.json({ message: error.message }, { status: 500 });
+      }
+      const date = new Date().toISOString().slice(0, 10);
+      return new Response(archive, {
+        headers: {
+          "content-type":
+            format === "gz" ? "application/gzip" : "application/zstd",
+          "content-disposition": `attachment; filename="elektron-${date}.tar.${format}"`,
+        },
+      });
+    },
+    { beforeHandle: authorize },
+  )
+  // Import another tool's CSV export (tibber, elhub or entsoe) with
+  // `?location=` for consumption or `?region=` (and `&eur_nok=`) for prices
+  .post(
+    "/import/:format",
+    async ({ params, query, body, request }) => {
+      // `text/csv` isn't parsed by Elysia
+      const text = typeof body === "string" ? body : await request.text();
+      try {
+        return Response.json(
+          importFile(params.format, text, {
+            region: query.region,
+            location: query.location,
+            eur_nok: query.eur_nok && parseFloat(query.eur_nok),
+          }),
+        );
+      } catch (error) {
+        return Response.json({ message: error.message }, { status: 400 });
+      }
+    },
+    { beforeHandle: authorize },
+  );
+
+export default admin;
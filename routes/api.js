@@ -0,0 +1,1283 @@
+import { Elysia } from "elysia";
+import { anomalySettings, detectAnomalies } from "../lib/anomalies.js";
+import { applianceCost, appliances } from "../lib/appliances.js";
+import { chargerLog, chargerSettings, currentPlan } from "../lib/charger.js";
+import {
+  chartSettings,
+  settingsForUnit,
+  stepSeries,
+  validateChartSettings,
+} from "../lib/chart.js";
+import { carbonIntensity, withIntensity } from "../lib/co2.js";
+import { config } from "../lib/config.js";
+import { loadCorrections } from "../lib/corrections.js";
+import { fullPrices } from "../lib/cost.js";
+import {
+  extendHorizon,
+  firstUnpublished,
+  forecast,
+} from "../lib/forecast.js";
+import { gridOverview, gridSeries } from "../lib/grid.js";
+import { heatPumpSettings, setpointHints } from "../lib/heatpump.js";
+import { activeRequests, limitSettings } from "../lib/limits.js";
+import {
+  formatClock,
+  formatHours,
+  formatOre,
+} from "../lib/locale.js";
+import {
+  chartCookie,
+  chartOverrides,
+  preferredChart,
+  preferredProfile,
+  preferredStatistics,
+} from "../lib/preferences.js";
+import {
+  REGIONS,
+  cacheStatus,
+  currentEntry,
+  eachDay,
+  getHorizon,
+  getPrices,
+  inHourRange,
+  isoDate,
+  latestStored,
+  loadPrices,
+  parseDate,
+  parseHourRange,
+  parsePeriod,
+  priceLevel,
+  provenance,
+  upstreamStatus,
+  waitForChange,
+} from "../lib/prices.js";
+import { checkRate } from "../lib/rates.js";
+import { getRecords } from "../lib/records.js";
+import { getReservoirs } from "../lib/reservoirs.js";
+import { dailyPrices } from "../lib/retention.js";
+import {
+  alertState,
+  describeAction,
+  evaluateRule,
+  validateRule,
+} from "../lib/rules.js";
+import { jobStatus } from "../lib/scheduler.js";
+import { solarProduction, solarSettings } from "../lib/solar.js";
+import {
+  cheapestWindow,
+  correlation,
+  dayDelta,
+  dayStatistics,
+  durationCurve,
+  histogram,
+  lttb,
+  maximum,
+  nextWindowBelow,
+  parseStatistics,
+  percentChange,
+  percentileRank,
+  seasonality,
+  summarize,
+  thresholdHours,
+  volatility,
+  weekendCurves,
+  windowAt,
+} from "../lib/stats.js";
+import { TARIFFS, tariffSettings } from "../lib/tariffs.js";
+import { taxSettings } from "../lib/tax.js";
+import { formatLocal, fromLocal, parseDuration } from "../lib/time.js";
+import { UNITS, inUnit, preferredUnit } from "../lib/units.js";
+import { versionInfo } from "../lib/version.js";
+import { planWaterHeater, waterHeaterSettings } from "../lib/waterheater.js";
+import {
+  loadTemperatures,
+  location,
+  refreshTemperatures,
+  withTemperature,
+} from "../lib/weather.js";
+
+function badRequest(message) {
+  return Response.json({ message }, { status: 400 });
+}
+
+function notFound() {
+  return Response.json(
+    { message: "Finner ikke noe data. :-(" },
+    { status: 404 },
+  );
+}
+
+// Stored prices for a period, limited to `?from_hour=&to_hour=` if given
+function loadWithin(zone, period, hours) {
+  return inHourRange(loadPrices(zone, period.from, period.to), hours);
+}
+
+// `?region=`, defaulting to NO2; null if it isn't a known region
+function region(query) {
+  const value = query.region || "NO2";
+  return REGIONS.includes(value) ? value : null;
+}
+
+// The chart settings with `overrides`, remembering them in the `chart` cookie
+// when `remember` is set
+function chartConfig(overrides, remember = false) {
+  return Response.json(
+    {
+      defaults: chartSettings(),
+      overrides,
+      settings: chartSettings(overrides),
+    },
+    remember ? { headers: { "set-cookie": chartCookie(overrides) } } : {},
+  );
+}
+
+const api = new Elysia({ prefix: "/api/v1" })
+  // Average price per weekday and hour of day over the stored history
+  .get("/seasonality", ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const period = parsePeriod(query);
+    if (period.message) return badRequest(period.message);
+    const hours = parseHourRange(query);
+    if (hours?.message) return badRequest(hours.message);
+
+    const result = seasonality(loadWithin(zone, period, hours));
+    if (!result) return notFound();
+
+    return Response.json({ region: zone, ...result });
+  })
+  // Average daily curves of weekdays vs weekends over the stored history
+  .get("/weekend", ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const period = parsePeriod(query);
+    if (period.message) return badRequest(period.message);
+
+    const result = weekendCurves(loadPrices(zone, period.from, period.to));
+    if (!result) return notFound();
+
+    return Response.json({
+      region: zone,
+      from: isoDate(period.from),
+      to: isoDate(period.to),
+      ...result,
+    });
+  })
+  // Daily price range, coefficient of variation and largest jump
+  .get("/volatility", ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const period = parsePeriod(query);
+    if (period.message) return badRequest(period.message);
+
+    const result = volatility(loadPrices(zone, period.from, period.to));
+    if (!result) return notFound();
+
+    return Response.json({
+      region: zone,
+      from: isoDate(period.from),
+      to: isoDate(period.to),
+      ...result,
+    });
+  })
+  // The same calendar day in previous (stored) years
+  .get("/compare-year", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+    const hours = parseHourRange(query);
+    if (hours?.message) return badRequest(hours.message);
+
+    let chart;
+    try {
+      chart = inHourRange(await getPrices(date, zone), hours);
+    } catch (error) {
+      return notFound();
+    }
+    const current = summarize(chart.map((item) => item.price));
+    // No prices within `?from_hour=&to_hour=`
+    if (!current) return notFound();
+
+    const years = [];
+    for (let year = date.getFullYear() - 1; year >= 2020; year--) {
+      const previous = new Date(year, date.getMonth(), date.getDate());
+      // February 29th has no counterpart in most years
+      if (previous.getDate() !== date.getDate()) continue;
+
+      const stored = inHourRange(loadPrices(zone, previous, previous), hours);
+      if (stored.length === 0) continue;
+
+      const stats = summarize(stored.map((item) => item.price));
+      years.push({
+        date: isoDate(previous),
+        ...stats,
+        // How much the requested day's average differs from this year's, in %
+        change:
+          stats.avg === 0
+            ? null
+            : ((current.avg - stats.avg) / Math.abs(stats.avg)) * 100,
+        prices: stored,
+      });
+    }
+
+    return Response.json({
+      region: zone,
+      date: isoDate(date),
+      ...current,
+      prices: chart,
+      years,
+    });
+  })
+  // Share of intervals below each price over a period
+  .get("/duration-curve", ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const period = parsePeriod(query, "30d");
+    if (period.message) return badRequest(period.message);
+
+    const points = query.points ? parseInt(query.points) : 101;
+    if (isNaN(points) || points < 2 || points > 1000) {
+      return badRequest("points must be between 2 and 1000");
+    }
+    const hours = parseHourRange(query);
+    if (hours?.message) return badRequest(hours.message);
+
+    const chart = loadWithin(zone, period, hours);
+    if (chart.length === 0) return notFound();
+
+    return Response.json({
+      region: zone,
+      from: isoDate(period.from),
+      to: isoDate(period.to),
+      intervals: chart.length,
+      curve: durationCurve(
+        chart.map((item) => item.price),
+        points,
+      ),
+    });
+  })
+  // How many intervals fall in each price bin over a period (`?bins=` of
+  // them, or `?width=` øre wide), and how today's highest price ranks
+  .get("/histogram", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const period = parsePeriod(query, "30d");
+    if (period.message) return badRequest(period.message);
+
+    const bins = query.bins ? parseInt(query.bins) : 20;
+    if (isNaN(bins) || bins < 1 || bins > 200) {
+      return badRequest("bins must be between 1 and 200");
+    }
+    const width = query.width ? parseFloat(query.width) : null;
+    if (width !== null && !(width > 0)) {
+      return badRequest("width must be a positive number of øre");
+    }
+    const hours = parseHourRange(query);
+    if (hours?.message) return badRequest(hours.message);
+
+    const chart = loadWithin(zone, period, hours);
+    if (chart.length === 0) return notFound();
+    const prices = chart.map((item) => item.price);
+    const { min, max } = summarize(prices);
+    if (width !== null && (max - min) / width > 1000) {
+      return badRequest("width must give at most 1000 bins");
+    }
+
+    // Today's highest price within the hours, ranked against the period
+    let today = null;
+    try {
+      const day = inHourRange(await getPrices(new Date(), zone), hours);
+      const highest = maximum(day.map((item) => item.price));
+      if (highest !== null) {
+        const sorted = [...prices].sort((a, b) => a - b);
+        today = { max: highest, percentile: percentileRank(sorted, highest) };
+      }
+    } catch (error) {
+      // Without today's prices there is still the distribution
+    }
+
+    return Response.json({
+      region: zone,
+      from: isoDate(period.from),
+      to: isoDate(period.to),
+      intervals: chart.length,
+      bins: histogram(prices, { bins, width }),
+      today,
+    });
+  })
+  // All-time highest/lowest price and highest daily average
+  .get("/records", ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const records = getRecords(zone);
+    if (Object.keys(records).length === 0) return notFound();
+
+    return Response.json({ region: zone, records });
+  })
+  // Intervals deviating strongly from the trailing pattern for that hour
+  .get("/anomalies", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+
+    const settings = anomalySettings();
+    if (query.threshold) settings.threshold = parseFloat(query.threshold);
+    if (!(settings.threshold > 0)) {
+      return badRequest("threshold must be a positive number");
+    }
+
+    let chart;
+    try {
+      chart = await getPrices(date, zone);
+    } catch (error) {
+      return notFound();
+    }
+
+    return Response.json({
+      region: zone,
+      date: isoDate(date),
+      threshold: settings.threshold,
+      window: settings.window,
+      anomalies: detectAnomalies(zone, date, chart, settings),
+    });
+  })
+  // Hours below `?below=` / above `?above=` øre, per day over the period
+  .get("/threshold-hours", ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const period = parsePeriod(query, "week");
+    if (period.message) return badRequest(period.message);
+
+    const number = (value) =>
+      value === undefined ? undefined : parseFloat(value);
+    const below = number(query.below);
+    const above = number(query.above);
+    if (Number.isNaN(below) || Number.isNaN(above)) {
+      return badRequest("below and above must be numbers (øre/kWh)");
+    }
+    if (below === undefined && above === undefined) {
+      return badRequest("Specify below and/or above (øre/kWh)");
+    }
+    const hours = parseHourRange(query);
+    if (hours?.message) return badRequest(hours.message);
+
+    const chart = loadWithin(zone, period, hours);
+    if (chart.length === 0) return notFound();
+
+    return Response.json({
+      region: zone,
+      from: isoDate(period.from),
+      to: isoDate(period.to),
+      thresholds: { below: below ?? null, above: above ?? null },
+      ...thresholdHours(chart, { below, above }),
+    });
+  })
+  // Cheapest time in the visible horizon to run a task of `?hours=`
+  .get("/recommendation", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const task = query.task || null;
+    const hours = query.hours ? parseFloat(query.hours) : config.tasks[task];
+    if (!(hours > 0) || hours > 24) {
+      return badRequest(
+        `hours must be between 0 and 24, or task one of: ${Object.keys(config.tasks).join(", ")}`,
+      );
+    }
+
+    // `?forecast=N` extends the horizon with N days of estimates
+    const forecastDays = query.forecast ? parseInt(query.forecast) : 0;
+    if (isNaN(forecastDays) || forecastDays < 0 || forecastDays > 14) {
+      return badRequest("forecast must be between 0 and 14 days");
+    }
+
+    const now = new Date();
+    let chart;
+    try {
+      chart = await extendHorizon(
+        zone,
+        await getHorizon(zone, now),
+        forecastDays,
+      );
+    } catch (error) {
+      return notFound();
+    }
+
+    const best = cheapestWindow(chart, hours, now);
+    if (!best) return notFound();
+    const startNow = windowAt(chart, hours, now);
+    const change = startNow
+      ? percentChange(startNow.average, best.average)
+      : null;
+
+    // The best window per day, for "which day" rather than "which hour"
+    const days = [...new Set(chart.map((item) => item.time.slice(0, 10)))]
+      .map((date) => ({
+        date,
+        ...cheapestWindow(
+          chart.filter((item) => item.time.startsWith(date)),
+          hours,
+          now,
+        ),
+      }))
+      .filter((day) => day.start);
+
+    return Response.json({
+      region: zone,
+      task,
+      hours,
+      best,
+      now: startNow,
+      // How much cheaper the best window is than starting right away, in %
+      savings: change === null ? null : -change,
+      days,
+    });
+  })
+  // Yes or no to starting something lasting `?duration=` (e.g. `2h`, `90m`)
+  // right away: whether that costs at most `?tolerance=` % (default 10) more
+  // than the cheapest window from now on, with the reasoning
+  .get("/should-run", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const hours = parseDuration(query.duration || "2h");
+    if (!(hours > 0 && hours <= 24)) {
+      return badRequest("duration must be e.g. 2h or 90m, at most 24h");
+    }
+    const tolerance = query.tolerance ? parseFloat(query.tolerance) : 10;
+    if (!(tolerance >= 0)) return badRequest("tolerance must be at least 0");
+
+    const now = new Date();
+    let chart;
+    try {
+      chart = await getHorizon(zone, now);
+    } catch (error) {
+      return notFound();
+    }
+    const best = cheapestWindow(chart, hours, now);
+    const startNow = windowAt(chart, hours, now);
+    if (!best || !startNow) return notFound();
+
+    const extra = startNow.average - best.average;
+    const run = extra <= (Math.abs(best.average) * tolerance) / 100;
+    const compared =
+      best.start === startNow.start
+        ? "now is the cheapest window"
+        : `the cheapest window starts at ${formatClock(best.start)} ` +
+          `averaging ${formatOre(best.average)} øre/kWh`;
+    return Response.json({
+      run,
+      region: zone,
+      hours,
+      now: startNow,
+      best,
+      // øre/kWh more for starting now than in the best window
+      extra,
+      reason:
+        `Starting now averages ${formatOre(startNow.average)} øre/kWh ` +
+        `over ${formatHours(hours)} h; ${compared}`,
+    });
+  })
+  // What a run of a configured appliance costs with taxes and nettleie when
+  // started at `?start=` (`now` or `HH:MM` today) against the cheapest run
+  // still possible today
+  .get("/appliance-cost", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const configured = appliances();
+    const appliance = configured[query.name];
+    if (!appliance) {
+      const names = Object.keys(configured);
+      return badRequest(
+        names.length > 0
+          ? `name must be one of: ${names.join(", ")}`
+          : "No appliances are configured (appliances)",
+      );
+    }
+    if (!(appliance.kwh > 0 && appliance.hours > 0)) {
+      return badRequest(`${query.name} needs kwh and hours above 0`);
+    }
+
+    const now = new Date();
+    const start = query.start || "now";
+    if (start !== "now" && !/^([01]\d|2[0-3]):[0-5]\d$/.test(start)) {
+      return badRequest("start must be now or HH:MM");
+    }
+    const startAt =
+      start === "now"
+        ? now
+        : new Date(fromLocal(`${formatLocal(now).slice(0, 10)}T${start}`));
+
+    let chart;
+    try {
+      chart = await getPrices(now, zone);
+    } catch (error) {
+      return notFound();
+    }
+    const prices = fullPrices(chart, zone, tariffSettings());
+    const result = applianceCost(
+      prices.map((item) => ({ ...item, price: item.total })),
+      appliance,
+      startAt,
+      now,
+    );
+    if (!result) {
+      return badRequest(`${query.name} doesn't fit in today's prices`);
+    }
+
+    return Response.json({
+      name: query.name,
+      region: zone,
+      ...appliance,
+      ...result,
+    });
+  })
+  // Estimated prices for the days after the published day-ahead prices
+  .get("/forecast", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const days = query.days ? parseInt(query.days) : 7;
+    if (isNaN(days) || days < 1 || days > 14) {
+      return badRequest("days must be between 1 and 14");
+    }
+
+    let chart;
+    try {
+      chart = await getHorizon(zone);
+    } catch (error) {
+      return notFound();
+    }
+
+    const from = firstUnpublished(chart);
+    const prices = await forecast(zone, from, days);
+    if (prices.length === 0) return notFound();
+
+    return Response.json({
+      region: zone,
+      estimate: true,
+      from: isoDate(from),
+      days,
+      prices,
+    });
+  })
+  // Today's prices alongside the grid's carbon intensity (gCO₂eq/kWh)
+  .get("/co2", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    if (!config.co2?.token) {
+      return Response.json(
+        { message: "CO2 intensity is not configured (co2.token)" },
+        { status: 501 },
+      );
+    }
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+
+    let chart;
+    let intensity;
+    try {
+      chart = await getPrices(date, zone);
+      intensity = await carbonIntensity(zone);
+    } catch (error) {
+      return Response.json({ message: error.message }, { status: 502 });
+    }
+
+    const prices = withIntensity(chart, intensity);
+    const known = prices.filter((item) => item.co2 !== null);
+
+    return Response.json({
+      region: zone,
+      date: isoDate(date),
+      unit: "gCO2eq/kWh",
+      // Cleanest hour among those with known intensity
+      cleanest: known.length
+        ? known.reduce((a, b) => (b.co2 < a.co2 ? b : a))
+        : null,
+      prices,
+    });
+  })
+  // Latest production/consumption/exchange for the zone, and Norway's
+  // hourly production and consumption for `?date=` (Statnett)
+  .get("/grid", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+
+    try {
+      const [latest, series] = await Promise.all([
+        gridOverview(zone),
+        gridSeries(date),
+      ]);
+      return Response.json({
+        region: zone,
+        date: isoDate(date),
+        latest,
+        series,
+      });
+    } catch (error) {
+      return Response.json({ message: error.message }, { status: 502 });
+    }
+  })
+  // Weekly hydro reservoir filling (share of capacity) from NVE
+  .get("/reservoirs", async ({ query }) => {
+    const area = query.region || "NO2";
+    if (area !== "NO" && !REGIONS.includes(area)) {
+      return badRequest("Region must be NO1-NO5 (or NO for all of Norway)");
+    }
+
+    const period = parsePeriod(query, "1y");
+    if (period.message) return badRequest(period.message);
+
+    const history = await getReservoirs(area, isoDate(period.from));
+    if (history.length === 0) return notFound();
+
+    const latest = history[history.length - 1];
+    // Same week last year, for context
+    const lastYear = history.find(
+      (row) => row.year === latest.year - 1 && row.week === latest.week,
+    );
+
+    return Response.json({
+      region: area,
+      latest,
+      last_year: lastYear ?? null,
+      history,
+    });
+  })
+  // Price against temperature: the horizon with forecast temperatures, and
+  // the correlation between the two over the stored history
+  .get("/weather", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const period = parsePeriod(query, "90d");
+    if (period.message) return badRequest(period.message);
+
+    let horizon;
+    try {
+      await refreshTemperatures(zone);
+      horizon = await getHorizon(zone);
+    } catch (error) {
+      return Response.json({ message: error.message }, { status: 502 });
+    }
+
+    const upcoming = withTemperature(
+      horizon,
+      loadTemperatures(zone, new Date(), firstUnpublished(horizon)),
+    );
+    const history = withTemperature(
+      loadPrices(zone, period.from, period.to),
+      loadTemperatures(zone, period.from, period.to),
+    ).filter((item) => item.temperature !== null);
+
+    return Response.json({
+      region: zone,
+      location: location(zone),
+      from: isoDate(period.from),
+      to: isoDate(period.to),
+      // Pearson's r; strongly negative when cold weather drives prices up
+      correlation: correlation(
+        history.map((item) => item.price),
+        history.map((item) => item.temperature),
+      ),
+      samples: history.length,
+      prices: upcoming,
+    });
+  })
+  // Estimated clear-sky production against the spot price (plusskunde)
+  .get("/solar", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const settings = solarSettings(zone);
+    if (query.kwp) settings.kwp = parseFloat(query.kwp);
+    if (!(settings.kwp > 0)) {
+      return badRequest("Configure solar.kwp or pass ?kwp= (peak kW)");
+    }
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+
+    let chart;
+    try {
+      chart = await getPrices(date, zone);
+    } catch (error) {
+      return notFound();
+    }
+
+    const hours = solarProduction(chart, settings);
+    const production = hours.reduce((sum, item) => sum + item.production, 0);
+    const revenue = hours.reduce((sum, item) => sum + item.revenue, 0);
+
+    return Response.json({
+      region: zone,
+      date: isoDate(date),
+      panel: settings,
+      model: "clear-sky",
+      production,
+      revenue,
+      // The spot price the production is effectively sold at
+      average_price: production > 0 ? (revenue / production) * 100 : null,
+      hours,
+    });
+  })
+  // Cheapest hours to run a water heater, with a max gap and must-run hours
+  .get("/water-heater", async ({ query }) => {
+    const overrides = {};
+    if (query.region) overrides.region = query.region;
+    if (query.hours) overrides.hours = parseInt(query.hours);
+    if (query.max_gap) overrides.max_gap = parseInt(query.max_gap);
+    if (query.must_run) {
+      overrides.must_run = query.must_run.split(",").map(Number);
+    }
+    const settings = waterHeaterSettings(overrides);
+
+    if (!REGIONS.includes(settings.region)) {
+      return badRequest("Region must be NO1-NO5");
+    }
+    if (!(settings.hours >= 0 && settings.hours <= 24)) {
+      return badRequest("hours must be between 0 and 24");
+    }
+    if (!(settings.max_gap >= 0)) {
+      return badRequest("max_gap must be a non-negative number of hours");
+    }
+    if (settings.must_run.some((hour) => !(hour >= 0 && hour <= 23))) {
+      return badRequest("must_run must be hours between 0 and 23");
+    }
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+
+    let chart;
+    try {
+      chart = await getPrices(date, settings.region);
+    } catch (error) {
+      return notFound();
+    }
+
+    const schedule = planWaterHeater(chart, settings);
+    if (!schedule) {
+      return Response.json(
+        { message: "No schedule satisfies hours, max_gap and must_run" },
+        { status: 422 },
+      );
+    }
+
+    const on = schedule.filter((item) => item.on);
+    const current = currentEntry(chart, new Date());
+    return Response.json({
+      region: settings.region,
+      date: isoDate(date),
+      settings,
+      on_hours: on.map((item) => item.hour),
+      average_price: summarize(on.map((item) => item.price))?.avg ?? null,
+      // Only meaningful when `date` is today
+      on: current
+        ? schedule.some((item) => item.on && item.hour === current.hour)
+        : null,
+      schedule,
+    });
+  })
+  // Price-aware heat pump setpoint offsets, e.g. for Home Assistant climates
+  .get("/heat-pump", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const overrides = {};
+    for (const key of ["setpoint", "min", "max", "step"]) {
+      if (query[key] !== undefined) overrides[key] = parseFloat(query[key]);
+    }
+    const settings = heatPumpSettings(overrides);
+    if (
+      Object.values(settings).some((value) => !Number.isFinite(value)) ||
+      settings.min > settings.max
+    ) {
+      return badRequest(
+        "setpoint, min, max and step must be numbers; min <= max",
+      );
+    }
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+
+    let chart;
+    try {
+      chart = await getPrices(date, zone);
+    } catch (error) {
+      return notFound();
+    }
+
+    const hints = setpointHints(chart, settings);
+    const current = currentEntry(chart, new Date());
+    return Response.json({
+      region: zone,
+      date: isoDate(date),
+      settings,
+      // Only present when `date` is today
+      current: hints.find((item) => item.time === current?.time) || null,
+      hours: hints,
+    });
+  })
+  // The EV charging plan being followed, and the actions taken on the charger
+  .get("/charger", async ({ query }) => {
+    if (!config.charger?.type) {
+      return Response.json(
+        { message: "EV charger is not configured (charger.type)" },
+        { status: 501 },
+      );
+    }
+
+    const limit = query.limit ? parseInt(query.limit) : 50;
+    if (isNaN(limit) || limit < 1 || limit > 1000) {
+      return badRequest("limit must be between 1 and 1000");
+    }
+
+    let plan;
+    try {
+      plan = await currentPlan();
+    } catch (error) {
+      return notFound();
+    }
+
+    const { username, password, ...settings } = chargerSettings();
+    return Response.json({
+      settings,
+      plan,
+      actions: chargerLog(limit),
+    });
+  })
+  // The configured automation rules and whether each matches right now.
+  // Actions are only described, as webhook URLs, headers and notifiers often
+  // carry tokens and passwords
+  .get("/rules", async () => {
+    const rules = [];
+    for (const rule of config.rules || []) {
+      const { name, when } = rule;
+      try {
+        const { matched, conditions, context } = await evaluateRule(rule);
+        rules.push({
+          name,
+          when,
+          matched,
+          conditions,
+          alert: alertState()[name] || null,
+          actions: (rule.then || []).map((action) =>
+            context ? describeAction(action, context) : action.type,
+          ),
+        });
+      } catch (error) {
+        rules.push({ name, when, error: error.message });
+      }
+    }
+    return Response.json({ rules });
+  })
+  // Dry-run a rule (`{ rule, time? }`) without running its actions
+  .post("/rules/test", async ({ body }) => {
+    const invalid = validateRule(body?.rule);
+    if (invalid) return badRequest(invalid);
+
+    const now = body.time ? new Date(body.time) : new Date();
+    if (isNaN(now)) return badRequest("time must be an ISO 8601 timestamp");
+    if (body.rule.region && !REGIONS.includes(body.rule.region)) {
+      return badRequest("Region must be NO1-NO5");
+    }
+
+    let result;
+    try {
+      result = await evaluateRule(body.rule, now);
+    } catch (error) {
+      return notFound();
+    }
+
+    return Response.json({
+      matched: result.matched,
+      conditions: result.conditions,
+      // What would have run
+      actions: result.matched ? body.rule.then || [] : [],
+      ...(result.context && { context: result.context.summary }),
+    });
+  })
+  // Version, commit and enabled features of this install
+  .get("/version", () => Response.json(versionInfo()))
+  // Upstream, cache and background job health, for monitoring
+  .get("/status", () => {
+    const upstream = upstreamStatus();
+    const jobs = jobStatus();
+    return Response.json({
+      healthy:
+        upstream.breaker === "closed" &&
+        jobs.every((job) => job.last_error === null),
+      upstream,
+      cache: cacheStatus(),
+      requests: { active: activeRequests(), limits: limitSettings() },
+      latest: latestStored(),
+      jobs,
+    });
+  })
+  // Spot price with elavgift, nettleie and VAT, matching the invoice (øre/kWh);
+  // the zone and preset default to those of the `?profile=` in use
+  .get("/full-price", async ({ query, request }) => {
+    const profile = preferredProfile(request, query);
+    const zone = region({ ...query, region: query.region || profile?.region });
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+
+    let chart;
+    try {
+      chart = await getPrices(date, zone);
+    } catch (error) {
+      return notFound();
+    }
+
+    let tariff;
+    try {
+      const preset = query.tariff || profile?.tariff;
+      tariff = preset ? tariffSettings({ preset }) : tariffSettings();
+    } catch (error) {
+      return badRequest(error.message);
+    }
+
+    const prices = fullPrices(chart, zone, tariff);
+    return Response.json({
+      region: zone,
+      date: isoDate(date),
+      tax: taxSettings(zone),
+      tariff,
+      average: summarize(prices.map((item) => item.total)).avg,
+      prices,
+    });
+  })
+  // Nettleie presets selectable with `tariff.preset` (or `?tariff=`)
+  .get("/tariffs", ({ query }) => {
+    const presets = Object.entries(TARIFFS)
+      .filter(
+        ([, tariff]) => !query.region || tariff.regions.includes(query.region),
+      )
+      .map(([id, tariff]) => ({ id, ...tariff }));
+    return Response.json({ presets });
+  })
+  // Long-poll: responds when the next interval starts, when new day-ahead
+  // prices are stored, or after `?timeout=` seconds, with the price then
+  .get("/next-change", async ({ query, request }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const timeout = query.timeout ? parseInt(query.timeout) : 60;
+    if (isNaN(timeout) || timeout < 1 || timeout > 300) {
+      return badRequest("timeout must be between 1 and 300 seconds");
+    }
+
+    let chart;
+    try {
+      chart = await getHorizon(zone);
+    } catch (error) {
+      return notFound();
+    }
+    const next = chart.find((item) => Date.parse(item.time) > Date.now());
+
+    const reason = await waitForChange(zone, {
+      until: next && Date.parse(next.time),
+      timeout: timeout * 1000,
+      signal: request.signal,
+    });
+
+    // Tomorrow may have been stored while waiting
+    try {
+      chart = await getHorizon(zone);
+    } catch (error) {
+      return notFound();
+    }
+    const now = new Date();
+    const current = currentEntry(chart, now);
+    const upcoming = chart.find((item) => Date.parse(item.time) > now);
+
+    const today = chart.filter((item) => item.time.startsWith(isoDate(now)));
+    return Response.json({
+      region: zone,
+      reason,
+      current,
+      level: current
+        ? priceLevel(current.price, today.map((item) => item.price))
+        : null,
+      next_change: upcoming?.time ?? null,
+      // Whether tomorrow's prices are published
+      tomorrow: chart.length > today.length,
+    });
+  })
+  // When the next window of at least `?hours=` hours below `?below=` øre starts
+  .get("/countdown", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const hours = query.hours ? parseFloat(query.hours) : 1;
+    if (!(hours > 0) || hours > 24) {
+      return badRequest("hours must be between 0 and 24");
+    }
+    const below = parseFloat(query.below);
+    if (isNaN(below)) return badRequest("below must be a number (øre/kWh)");
+
+    const now = new Date();
+    let chart;
+    try {
+      chart = await getHorizon(zone, now);
+    } catch (error) {
+      return notFound();
+    }
+
+    const window = nextWindowBelow(chart, hours, below, now);
+    const price = formatOre(below);
+    const length = `minst ${formatHours(hours)} timer under ${price} øre`;
+    if (!window) {
+      return Response.json({
+        region: zone,
+        window: null,
+        message: `Ingen periode på ${length} i sikte.`,
+      });
+    }
+
+    const minutes = window.active
+      ? 0
+      : Math.ceil((Date.parse(window.start) - now) / 60000);
+    return Response.json({
+      region: zone,
+      window,
+      starts_in_minutes: minutes,
+      message: window.active
+        ? `Nå: under ${price} øre til kl. ${formatClock(window.end)}.`
+        : `Neste periode på ${length} starter om ${minutes} minutter ` +
+          `kl. ${formatClock(window.start)}.`,
+    });
+  })
+  // Stored prices over a long range, downsampled (LTTB) to `?points=`
+  .get("/series", ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const period = parsePeriod(query, "30d");
+    if (period.message) return badRequest(period.message);
+
+    const points = query.points ? parseInt(query.points) : 500;
+    if (isNaN(points) || points < 3 || points > 10000) {
+      return badRequest("points must be between 3 and 10000");
+    }
+
+    const chart = loadPrices(zone, period.from, period.to);
+    if (chart.length === 0) return notFound();
+
+    const series = lttb(
+      chart.map((item) => ({ x: Date.parse(item.time), y: item.price, item })),
+      points,
+    ).map(({ item }) => ({ time: item.time, price: item.price }));
+
+    return Response.json({
+      region: zone,
+      from: isoDate(period.from),
+      to: isoDate(period.to),
+      // Intervals in the range before downsampling
+      intervals: chart.length,
+      points: series.length,
+      series,
+    });
+  })
+  // Stored coverage over the period: missing and incomplete days, and the
+  // upstream corrections detected for it
+  .get("/data-quality", ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const period = parsePeriod(query, "30d");
+    if (period.message) return badRequest(period.message);
+
+    const counts = new Map();
+    for (const item of loadPrices(zone, period.from, period.to)) {
+      const date = item.time.slice(0, 10);
+      counts.set(date, (counts.get(date) || 0) + 1);
+    }
+    const missing = [];
+    const incomplete = [];
+    for (const date of eachDay(period.from, period.to)) {
+      const count = counts.get(isoDate(date));
+      if (!count) missing.push(isoDate(date));
+      // Fewer than a (DST-shortened) day of hours
+      else if (count < 23) {
+        incomplete.push({ date: isoDate(date), intervals: count });
+      }
+    }
+
+    return Response.json({
+      region: zone,
+      from: isoDate(period.from),
+      to: isoDate(period.to),
+      stored_days: counts.size,
+      missing,
+      incomplete,
+      sources: provenance(zone, period.from, period.to),
+      // Upstream's NOK/EUR pair on the last day against the ECB rate
+      exchange_rate: checkRate(
+        loadPrices(zone, period.to, period.to),
+        period.to,
+      ),
+      corrections: loadCorrections(zone, period.from, period.to),
+    });
+  })
+  // Daily average, minimum and maximum, including compacted history
+  .get("/daily", ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const period = parsePeriod(query);
+    if (period.message) return badRequest(period.message);
+
+    const days = dailyPrices(zone, period.from, period.to);
+    if (days.length === 0) return notFound();
+
+    return Response.json({ region: zone, days });
+  })
+  // Statistics of a day for the page below the chart, in the order asked for
+  // (`?stats=min,median,current,percentile,remaining`, or the saved choice)
+  .get("/day-stats", async ({ query, request }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+    const names = query.stats
+      ? parseStatistics(query.stats)
+      : preferredStatistics(request);
+    if (names.message) return badRequest(names.message);
+
+    const unit = preferredUnit(request, query);
+    let chart;
+    try {
+      chart = inUnit(await getPrices(date, zone), unit);
+    } catch (error) {
+      return notFound();
+    }
+    return Response.json({
+      region: zone,
+      date: isoDate(date),
+      unit: UNITS[unit].label,
+      stats: dayStatistics(chart, names),
+    });
+  })
+  // The step series the page's chart draws, its y-axis, which keeps the
+  // `?include=` values in view (by default 0; empty for the prices alone),
+  // and the cheap and expensive bands to shade
+  .get("/step-series", async ({ query, request }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) return badRequest("date must be on the form YYYY-MM-DD");
+    const include = (query.include ?? "0")
+      .split(",")
+      .filter((value) => value.trim() !== "")
+      .map(Number);
+    if (include.some((value) => !Number.isFinite(value))) {
+      return badRequest("include must be comma-separated numbers");
+    }
+
+    const unit = preferredUnit(request, query);
+    let chart;
+    try {
+      chart = inUnit(await getPrices(date, zone), unit);
+    } catch (error) {
+      return notFound();
+    }
+    return Response.json({
+      region: zone,
+      date: isoDate(date),
+      unit: UNITS[unit].label,
+      ...stepSeries(
+        chart,
+        include,
+        settingsForUnit(preferredChart(request), unit),
+      ),
+    });
+  })
+  // Chart settings: the server's defaults, this browser's overrides (kept
+  // in a cookie) and the two combined, which the page and SVG charts use
+  .get("/chart-config", ({ request }) => chartConfig(chartOverrides(request)))
+  // Replace this browser's overrides, e.g. `{ "labels": "nok", "grid": 4 }`
+  // or the axis policy `{ "include_zero": true, "max": 200 }`
+  .put("/chart-config", ({ body }) => {
+    const overrides = body ?? {};
+    const invalid = validateChartSettings(overrides);
+    if (invalid) return badRequest(invalid);
+    return chartConfig(overrides, true);
+  })
+  // Back to the server's defaults
+  .delete("/chart-config", () => chartConfig({}, true))
+  // Min., avg. and max. over the intervals left of today (and tomorrow, once
+  // published) from the current one, and their cheapest `?hours=` window
+  .get("/remaining", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+    const hours = query.hours ? parseFloat(query.hours) : 3;
+    if (!(hours > 0 && hours <= 24)) {
+      return badRequest("hours must be between 0 and 24");
+    }
+
+    const now = new Date();
+    let chart;
+    try {
+      chart = await getHorizon(zone, now);
+    } catch (error) {
+      return notFound();
+    }
+    const current = currentEntry(chart, now);
+    if (!current) return notFound();
+    const remaining = chart.slice(chart.indexOf(current));
+    const window = cheapestWindow(remaining, hours, now);
+
+    return Response.json({
+      region: zone,
+      from: current.time,
+      to: remaining[remaining.length - 1].time,
+      intervals: remaining.length,
+      ...summarize(remaining.map((item) => item.price)),
+      cheapest: window && { hours, ...window },
+    });
+  })
+  // Tomorrow's prices compared with today's, once they are published
+  .get("/tomorrow-delta", async ({ query }) => {
+    const zone = region(query);
+    if (!zone) return badRequest("Region must be NO1-NO5");
+
+    const today = new Date();
+    const tomorrow = new Date(today);
+    tomorrow.setDate(tomorrow.getDate() + 1);
+    let charts;
+    try {
+      charts = [await getPrices(today, zone), await getPrices(tomorrow, zone)];
+    } catch (error) {
+      // Published around 13:00
+      return notFound();
+    }
+    return Response.json({
+      region: zone,
+      date: isoDate(tomorrow),
+      ...dayDelta(...charts),
+    });
+  });
+
+export default api;
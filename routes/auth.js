@@ -0,0 +1,67 @@
+import { Elysia } from "elysia";
+import { errorResponse } from "../lib/errors.js";
+import {
+  completeLogin,
+  loginRedirect,
+  logoutCookies,
+  oidcSettings,
+  sessionUser,
+} from "../lib/oidc.js";
+import { basePath } from "../lib/sites.js";
+
+function notConfigured() {
+  return Response.json(
+    { message: "Login is not configured (oidc)" },
+    { status: 501 },
+  );
+}
+
+function redirect(location, cookies) {
+  const headers = new Headers({ location });
+  for (const cookie of cookies) headers.append("set-cookie", cookie);
+  return new Response(null, { status: 303, headers });
+}
+
+// OpenID Connect login for the admin routes and personal data
+const auth = new Elysia({ prefix: "/auth" })
+  // Off to the provider, coming back to `?next=` (a path on this site)
+  .get("/login", async ({ query, request }) => {
+    const settings = oidcSettings();
+    if (!settings) return notConfigured();
+    try {
+      const login = await loginRedirect(settings, request, query.next);
+      return redirect(login.url, [login.cookie]);
+    } catch (error) {
+      return errorResponse(request, 502, error.message);
+    }
+  })
+  // Where the provider sends the browser back with `?code=&state=`
+  .get("/callback", async ({ query, request }) => {
+    const settings = oidcSettings();
+    if (!settings) return notConfigured();
+    try {
+      const { next, cookie } = await completeLogin(settings, request, query);
+      const location = new URL(
+        `${basePath(request).replace(/\/$/, "")}${next}`,
+        request.url,
+      ).href;
+      return redirect(location, [cookie]);
+    } catch (error) {
+      return errorResponse(request, 403, error.message);
+    }
+  })
+  .get("/logout", ({ request }) => {
+    const home = new URL(basePath(request), request.url).href;
+    return redirect(home, logoutCookies(request));
+  })
+  // Who is logged in, if anyone
+  .get("/me", ({ request }) => {
+    const settings = oidcSettings();
+    if (!settings) return notConfigured();
+    const user = sessionUser(settings, request);
+    return user
+      ? Response.json(user)
+      : Response.json({ message: "Not logged in" }, { status: 401 });
+  });
+
+export default auth;
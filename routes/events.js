@@ -0,0 +1,70 @@
+import { Elysia } from "elysia";
+import {
+  REGIONS,
+  currentEntry,
+  getHorizon,
+  waitForChange,
+} from "../lib/prices.js";
+
+const encoder = new TextEncoder();
+
+// Comment lines keep proxies from closing an idle stream
+const KEEPALIVE = 30 * 1000;
+
+function message(event, data) {
+  return encoder.encode(`event: ${event}\ndata: ${JSON.stringify(data)}\n\n`);
+}
+
+const events = new Elysia()
+  // Server-sent events for the page: `tick` when a new interval starts and
+  // `prices` when the next day's prices have been stored
+  .get("/events", ({ query, request }) => {
+    const region = query.region || "NO2";
+    if (!REGIONS.includes(region)) {
+      return Response.json(
+        { message: "Region must be NO1-NO5" },
+        { status: 400 },
+      );
+    }
+
+    const stream = new ReadableStream({
+      async start(controller) {
+        while (!request.signal.aborted) {
+          let chart = [];
+          try {
+            chart = await getHorizon(region);
+          } catch (error) {
+            // Retry after the keep-alive
+          }
+          const next = chart.find((item) => Date.parse(item.time) > Date.now());
+
+          const reason = await waitForChange(region, {
+            until: next && Date.parse(next.time),
+            timeout: KEEPALIVE,
+            signal: request.signal,
+          });
+          if (reason === "aborted") break;
+
+          if (reason === "timeout") {
+            controller.enqueue(encoder.encode(": ping\n\n"));
+          } else if (reason === "interval") {
+            controller.enqueue(
+              message("tick", currentEntry(chart, new Date())),
+            );
+          } else {
+            controller.enqueue(message("prices", { region }));
+          }
+        }
+        controller.close();
+      },
+    });
+
+    return new Response(stream, {
+      headers: {
+        "content-type": "text/event-stream",
+        "cache-control": "no-cache",
+      },
+    });
+  });
+
+export default events;
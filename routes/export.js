@@ -0,0 +1,166 @@
+import { Elysia } from "elysia";
+import { writeParquet } from "../lib/parquet.js";
+import {
+  REGIONS,
+  eachDay,
+  getPrices,
+  loadPrices,
+  parseRange,
+} from "../lib/prices.js";
+import { taxSettings } from "../lib/tax.js";
+import { STYLES, excelTime, writeXlsx } from "../lib/xlsx.js";
+
+const encoder = new TextEncoder();
+
+// Days an NDJSON export fetches from upstream at most; further days missing
+// from the stored history are left out until the backfill job has them
+const MAX_FETCHED_DAYS = 31;
+
+const exporter = new Elysia()
+  // One JSON object per line, streamed a day at a time from the stored
+  // history, fetching (and storing) a limited number of days missing there
+  .get("/export.ndjson", ({ query }) => {
+    const region = query.region || "NO2";
+    if (!REGIONS.includes(region)) {
+      return Response.json(
+        { message: "Region must be NO1-NO5" },
+        { status: 400 },
+      );
+    }
+
+    const range = parseRange(query);
+    if (range.message) {
+      return Response.json({ message: range.message }, { status: 400 });
+    }
+
+    const days = eachDay(range.from, range.to);
+    let fetched = 0;
+    const stream = new ReadableStream({
+      async pull(controller) {
+        // Missing days are skipped rather than aborting the whole export, but
+        // every pull must enqueue something (or close) for the stream to go on.
+        // `for...of` would close the shared iterator on return, hence `next()`
+        for (let next = days.next(); !next.done; next = days.next()) {
+          const date = next.value;
+          let chart = loadPrices(region, date, date);
+          if (chart.length === 0) {
+            if (fetched >= MAX_FETCHED_DAYS) continue;
+            fetched++;
+            try {
+              chart = await getPrices(date, region);
+            } catch (error) {
+              continue;
+            }
+          }
+
+          const lines = chart
+            .map((item) => JSON.stringify({ region, ...item }) + "\n")
+            .join("");
+          controller.enqueue(encoder.encode(lines));
+          return;
+        }
+        controller.close();
+      },
+    });
+
+    return new Response(stream, {
+      headers: { "content-type": "application/x-ndjson; charset=utf-8" },
+    });
+  })
+  // Columnar export of the stored history (all regions unless `?region=`)
+  .get("/export.parquet", ({ query }) => {
+    const regions = query.region ? [query.region] : REGIONS;
+    if (!regions.every((region) => REGIONS.includes(region))) {
+      return Response.json(
+        { message: "Region must be NO1-NO5" },
+        { status: 400 },
+      );
+    }
+
+    const range = parseRange(query, 20 * 366);
+    if (range.message) {
+      return Response.json({ message: range.message }, { status: 400 });
+    }
+
+    const rows = regions.flatMap((region) =>
+      loadPrices(region, range.from, range.to).map((item) => ({
+        region,
+        time: Date.parse(item.time),
+        price_nok: item.price_nok,
+        price_eur: item.price_eur,
+      })),
+    );
+
+    const file = writeParquet(
+      [
+        { name: "region", type: "BYTE_ARRAY", converted: "UTF8" },
+        { name: "time", type: "INT64", converted: "TIMESTAMP_MILLIS" },
+        { name: "price_nok", type: "DOUBLE" },
+        { name: "price_eur", type: "DOUBLE" },
+      ],
+      rows,
+    );
+
+    return new Response(file, {
+      headers: {
+        "content-type": "application/vnd.apache.parquet",
+        "content-disposition": `attachment; filename="elektron_${query.from}_${query.to}.parquet"`,
+      },
+    });
+  })
+  // Spreadsheet of the stored prices with one sheet per month
+  .get("/export.xlsx", ({ query }) => {
+    const region = query.region || "NO2";
+    if (!REGIONS.includes(region)) {
+      return Response.json(
+        { message: "Region must be NO1-NO5" },
+        { status: 400 },
+      );
+    }
+
+    const range = parseRange(query);
+    if (range.message) {
+      return Response.json({ message: range.message }, { status: 400 });
+    }
+
+    const { vat } = taxSettings(region);
+    const months = new Map();
+    for (const item of loadPrices(region, range.from, range.to)) {
+      const month = item.time.slice(0, 7);
+      if (!months.has(month)) months.set(month, []);
+      months.get(month).push([
+        excelTime(item.time),
+        item.price_nok,
+        item.price,
+        item.price_eur,
+        item.price_nok * (1 + vat),
+      ]);
+    }
+    if (months.size === 0) {
+      return Response.json(
+        { message: "Finner ikke noe data. :-(" },
+        { status: 404 },
+      );
+    }
+
+    const columns = [
+      { header: "Tidspunkt", style: STYLES.datetime, width: 18 },
+      { header: "NOK/kWh", style: STYLES.decimals4 },
+      { header: "øre/kWh", style: STYLES.decimals2 },
+      { header: "EUR/kWh", style: STYLES.decimals4 },
+      { header: "NOK/kWh inkl. mva.", style: STYLES.decimals4, width: 20 },
+    ];
+    const file = writeXlsx(
+      [...months].map(([name, rows]) => ({ name, columns, rows })),
+    );
+
+    return new Response(file, {
+      headers: {
+        "content-type":
+          "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "content-disposition": `attachment; filename="elektron_${region}_${query.from}_${query.to}.xlsx"`,
+      },
+    });
+  });
+
+export default exporter;
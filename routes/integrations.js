@@ -0,0 +1,42 @@
+import { Elysia } from "elysia";
+import { answer } from "../lib/assistant.js";
+import { REGIONS } from "../lib/prices.js";
+
+// `{ intent, region, hours, task }` from the query or a JSON body
+async function fulfill(params) {
+  const region = params.region || "NO2";
+  if (!REGIONS.includes(region)) {
+    return Response.json(
+      { message: "Region must be NO1-NO5" },
+      { status: 400 },
+    );
+  }
+  const hours = params.hours ? parseFloat(params.hours) : undefined;
+  if (hours !== undefined && !(hours > 0 && hours <= 24)) {
+    return Response.json(
+      { message: "hours must be between 0 and 24" },
+      { status: 400 },
+    );
+  }
+
+  let text;
+  try {
+    text = await answer(params.intent, { region, hours, task: params.task });
+  } catch (error) {
+    return Response.json({ message: error.message }, { status: 400 });
+  }
+  if (params.format === "text") {
+    return new Response(text, {
+      headers: { "content-type": "text/plain; charset=utf-8" },
+    });
+  }
+  return Response.json({ intent: params.intent || "current", region, text });
+}
+
+const integrations = new Elysia({ prefix: "/integrations" })
+  // Spoken-friendly answer for voice assistant routines: `{ text }`, or the
+  // text alone with `?format=text`
+  .get("/assistant", ({ query }) => fulfill(query))
+  .post("/assistant", ({ body, query }) => fulfill({ ...query, ...body }));
+
+export default integrations;
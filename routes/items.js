@@ -0,0 +1,117 @@
+import { Elysia } from "elysia";
+import {
+  REGIONS,
+  currentEntry,
+  getHorizon,
+  priceLevel,
+} from "../lib/prices.js";
+import { cheapestWindow, summarize } from "../lib/stats.js";
+
+function round(value) {
+  return value === undefined || value === null
+    ? null
+    : Math.round(value * 100) / 100;
+}
+
+// Flat `{ name: value }` of everything a home automation item might want,
+// prices in øre/kWh; the cheapest window covers `hours` hours
+async function items(region, hours, now = new Date()) {
+  const chart = await getHorizon(region, now);
+  const current = currentEntry(chart, now);
+  if (!current) return null;
+
+  const today = chart.filter(
+    (item) => item.time.slice(0, 10) === current.time.slice(0, 10),
+  );
+  const prices = today.map((item) => item.price);
+  const { min, avg, max } = summarize(prices);
+  const next = chart[chart.indexOf(current) + 1];
+  const window = cheapestWindow(chart, hours, now);
+  return {
+    region,
+    time: current.time,
+    current_price: round(current.price),
+    current_price_nok: round(current.price_nok),
+    current_level: priceLevel(current.price, prices),
+    next_price: round(next?.price),
+    min_today: round(min),
+    avg_today: round(avg),
+    max_today: round(max),
+    cheapest_start: window?.start ?? null,
+    cheapest_end: window?.end ?? null,
+    cheapest_average: round(window?.average),
+  };
+}
+
+// `{ region, hours }` from the query, or an error response
+function parse(query) {
+  const region = query.region || "NO2";
+  if (!REGIONS.includes(region)) {
+    return Response.json(
+      { message: "Region must be NO1-NO5" },
+      { status: 400 },
+    );
+  }
+  const hours = query.hours ? parseFloat(query.hours) : 3;
+  if (!(hours > 0 && hours <= 24)) {
+    return Response.json(
+      { message: "hours must be between 0 and 24" },
+      { status: 400 },
+    );
+  }
+  return { region, hours };
+}
+
+const itemRoutes = new Elysia({ prefix: "/items" })
+  // Every item as flat key/value JSON, for Node-RED flows
+  .get("/", async ({ query }) => {
+    const params = parse(query);
+    if (params instanceof Response) return params;
+    try {
+      const values = await items(params.region, params.hours);
+      if (!values) {
+        return Response.json(
+          { message: "Finner ikke noe data. :-(" },
+          { status: 404 },
+        );
+      }
+      return Response.json(values);
+    } catch (error) {
+      return Response.json(
+        { message: "Finner ikke noe data. :-(" },
+        { status: 500 },
+      );
+    }
+  })
+  // One item as plain text (`/items/current_price`), for openHAB HTTP
+  // binding items without a JSONPath transformation
+  .get("/:name", async ({ params: { name }, query }) => {
+    const params = parse(query);
+    if (params instanceof Response) return params;
+    let values;
+    try {
+      values = await items(params.region, params.hours);
+    } catch (error) {
+      return Response.json(
+        { message: "Finner ikke noe data. :-(" },
+        { status: 500 },
+      );
+    }
+    if (!values) {
+      return Response.json(
+        { message: "Finner ikke noe data. :-(" },
+        { status: 404 },
+      );
+    }
+    if (!(name in values)) {
+      return Response.json(
+        { message: `Unknown item: ${name}` },
+        { status: 404 },
+      );
+    }
+    return new Response(values[name] === null ? "NULL" : `${values[name]}`, {
+      headers: { "content-type": "text/plain; charset=utf-8" },
+    });
+  });
+
+export default itemRoutes;
@@ -0,0 +1,231 @@
+import { Elysia } from "elysia";
+import { authenticate, authorize } from "../lib/auth.js";
+import { projectMonth } from "../lib/budget.js";
+import {
+  loadConsumption,
+  parseConsumption,
+  storeConsumption,
+} from "../lib/consumption.js";
+import {
+  consumptionCost,
+  costedConsumption,
+  fullPrices,
+  savingsByMonth,
+} from "../lib/cost.js";
+import { getLocation, locations } from "../lib/locations.js";
+import { renderLocations } from "../lib/pages.js";
+import {
+  currentEntry,
+  getPrices,
+  isoDate,
+  parseDate,
+  parsePeriod,
+} from "../lib/prices.js";
+import { summarize } from "../lib/stats.js";
+import { tariffSettings } from "../lib/tariffs.js";
+
+function notFound(name) {
+  return Response.json(
+    { message: `Finner ikke stedet ${name}. :-(` },
+    { status: 404 },
+  );
+}
+
+// Today's invoice prices for a location, with what it has used so far
+async function overview(name, location) {
+  const today = new Date();
+  const prices = fullPrices(
+    await getPrices(today, location.region),
+    location.region,
+    tariffSettings(location.tariff),
+  );
+  const current = currentEntry(prices, today);
+  const consumption = await loadConsumption(name, location, today, today);
+  const costs = consumption && consumptionCost(consumption, prices);
+
+  return {
+    name,
+    region: location.region,
+    current: current?.total ?? null,
+    average: summarize(prices.map((item) => item.total))?.avg ?? null,
+    kwh: costs && costs.reduce((sum, row) => sum + row.kwh, 0),
+    cost: costs && costs.reduce((sum, row) => sum + row.cost, 0),
+  };
+}
+
+// A location's consumption over `?period=` (default this month) joined with
+// its invoice prices; a Response if that isn't possible
+async function consumptionFor(name, query) {
+  const location = getLocation(name);
+  if (!location) return notFound(name);
+  if (!location.consumption) {
+    return Response.json(
+      { message: `${name} has no consumption source configured` },
+      { status: 501 },
+    );
+  }
+
+  const period = parsePeriod(query, "month", 366);
+  if (period.message) {
+    return Response.json({ message: period.message }, { status: 400 });
+  }
+
+  try {
+    const hours = await costedConsumption(
+      name,
+      location,
+      period.from,
+      period.to,
+    );
+    return { location, period, hours };
+  } catch (error) {
+    return Response.json({ message: error.message }, { status: 502 });
+  }
+}
+
+const locationRoutes = new Elysia({ prefix: "/locations" })
+  // Every configured location side by side
+  .get(
+    "/",
+    async () => {
+      const rows = [];
+      for (const [name, location] of Object.entries(locations())) {
+        try {
+          rows.push(await overview(name, location));
+        } catch (error) {
+          rows.push({ name, region: location.region, error: error.message });
+        }
+      }
+      return renderLocations(rows);
+    },
+    { beforeHandle: authenticate },
+  )
+  // Invoice prices (spot, elavgift, nettleie, VAT) for the location's zone
+  .get("/:name/prices", async ({ params, query }) => {
+    const location = getLocation(params.name);
+    if (!location) return notFound(params.name);
+
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) {
+      return Response.json(
+        { message: "date must be on the form YYYY-MM-DD" },
+        { status: 400 },
+      );
+    }
+
+    try {
+      const chart = await getPrices(date, location.region);
+      const tariff = tariffSettings(location.tariff);
+      return Response.json({
+        location: params.name,
+        region: location.region,
+        date: isoDate(date),
+        prices: fullPrices(chart, location.region, tariff),
+      });
+    } catch (error) {
+      return Response.json(
+        { message: "Finner ikke noe data. :-(" },
+        { status: 404 },
+      );
+    }
+  })
+  // Consumption with what each hour cost
+  .get(
+    "/:name/consumption",
+    async ({ params, query }) => {
+      const result = await consumptionFor(params.name, query);
+      if (result instanceof Response) return result;
+
+      const { location, period, hours } = result;
+      const kwh = hours.reduce((sum, row) => sum + row.kwh, 0);
+      const cost = hours.reduce((sum, row) => sum + row.cost, 0);
+
+      return Response.json({
+        location: params.name,
+        region: location.region,
+        from: isoDate(period.from),
+        to: isoDate(period.to),
+        kwh,
+        cost,
+        // NOK/kWh actually paid, weighted by consumption
+        average_price: kwh > 0 ? cost / kwh : null,
+        hours,
+      });
+    },
+    { beforeHandle: authenticate },
+  )
+  // Monthly savings from load shifting against a flat hourly profile
+  .get(
+    "/:name/savings",
+    async ({ params, query }) => {
+      const result = await consumptionFor(params.name, {
+        period: "6m",
+        ...query,
+      });
+      if (result instanceof Response) return result;
+
+      const { location, period, hours } = result;
+      const months = savingsByMonth(hours);
+      const total = months.reduce((sum, month) => sum + month.savings, 0);
+
+      return Response.json({
+        location: params.name,
+        region: location.region,
+        from: isoDate(period.from),
+        to: isoDate(period.to),
+        savings: total,
+        months,
+      });
+    },
+    { beforeHandle: authenticate },
+  )
+  // This month's projected cost against the location's `budget`
+  .get(
+    "/:name/budget",
+    async ({ params }) => {
+      const location = getLocation(params.name);
+      if (!location) return notFound(params.name);
+      if (!location.consumption) {
+        return Response.json(
+          { message: `${params.name} has no consumption source configured` },
+          { status: 501 },
+        );
+      }
+
+      try {
+        return Response.json(await projectMonth(params.name, location));
+      } catch (error) {
+        return Response.json({ message: error.message }, { status: 502 });
+      }
+    },
+    { beforeHandle: authenticate },
+  )
+  // Upload hourly consumption as JSON (`[{ time, kwh }]`) or CSV (`time,kwh`)
+  .post(
+    "/:name/consumption",
+    async ({ params, body, request }) => {
+      const location = getLocation(params.name);
+      if (!location) return notFound(params.name);
+      if (location.consumption?.type !== "upload") {
+        return Response.json(
+          { message: `${params.name} does not take uploaded consumption` },
+          { status: 409 },
+        );
+      }
+
+      // `text/csv` isn't parsed by Elysia
+      const rows = parseConsumption(body ?? (await request.text()));
+      if (!rows) {
+        return Response.json(
+          { message: "Expected [{ time, kwh }] or CSV with time,kwh" },
+          { status: 400 },
+        );
+      }
+
+      storeConsumption(params.name, rows);
+      return Response.json({ stored: rows.length });
+    },
+    { beforeHandle: authorize },
+  );
+
+export default locationRoutes;
@@ -0,0 +1,57 @@
+import { Elysia } from "elysia";
+import { handleMessage } from "../lib/mcp.js";
+import { basePath } from "../lib/sites.js";
+
+const encoder = new TextEncoder();
+
+// Comment lines keep proxies from closing an idle stream
+const KEEPALIVE = 30 * 1000;
+
+// Open SSE streams by session id, for the replies to their POSTed messages
+const sessions = new Map();
+
+function event(name, data) {
+  return encoder.encode(`event: ${name}\ndata: ${data}\n\n`);
+}
+
+const mcp = new Elysia({ prefix: "/mcp" })
+  // Model Context Protocol over SSE: the first event names the endpoint to
+  // POST JSON-RPC messages to (behind the ingress path, if any), and the
+  // replies come back on this stream
+  .get("/sse", ({ request }) => {
+    const session = crypto.randomUUID();
+    const endpoint = `${basePath(request)}mcp/messages?session=${session}`;
+    const stream = new ReadableStream({
+      start(controller) {
+        sessions.set(session, controller);
+        controller.enqueue(event("endpoint", endpoint));
+        const keepalive = setInterval(
+          () => controller.enqueue(encoder.encode(": ping\n\n")),
+          KEEPALIVE,
+        );
+        request.signal.addEventListener("abort", () => {
+          clearInterval(keepalive);
+          sessions.delete(session);
+          controller.close();
+        });
+      },
+    });
+
+    return new Response(stream, {
+      headers: {
+        "content-type": "text/event-stream",
+        "cache-control": "no-cache",
+      },
+    });
+  })
+  .post("/messages", async ({ query, body }) => {
+    const controller = sessions.get(query.session);
+    if (!controller) {
+      return Response.json({ message: "Unknown session" }, { status: 404 });
+    }
+    const reply = await handleMessage(body);
+    if (reply) controller.enqueue(event("message", JSON.stringify(reply)));
+    return new Response("Accepted", { status: 202 });
+  });
+
+export default mcp;
@@ -0,0 +1,67 @@
+import { Elysia } from "elysia";
+import { authorize } from "../lib/auth.js";
+import { getLocation } from "../lib/locations.js";
+import {
+  deleteProfile,
+  getProfile,
+  listProfiles,
+  saveProfile,
+  validProfileName,
+  validateProfile,
+} from "../lib/profiles.js";
+
+function notFound(name) {
+  return Response.json(
+    { message: `Finner ikke profilen ${name}. :-(` },
+    { status: 404 },
+  );
+}
+
+// A profile with where its selections lead: the page in its zone and the
+// consumption of its location
+function described(profile) {
+  const location = profile.location && getLocation(profile.location);
+  return {
+    ...profile,
+    page: `/?profile=${profile.name}`,
+    consumption: location?.consumption
+      ? `/locations/${profile.location}/consumption`
+      : null,
+  };
+}
+
+// Named selections of zone, nettleie preset and consumption location shared
+// by a household on one deployment; pick one with `/?profile=<name>`
+const profileRoutes = new Elysia({ prefix: "/profiles" })
+  .get("/", () => Response.json({ profiles: listProfiles() }))
+  .get("/:name", ({ params }) => {
+    const profile = getProfile(params.name);
+    return profile ? Response.json(described(profile)) : notFound(params.name);
+  })
+  // Create or replace a profile, e.g. `{ "region": "NO1", "tariff": "elvia",
+  // "location": "hjemme" }`
+  .put(
+    "/:name",
+    ({ params, body }) => {
+      if (!validProfileName(params.name)) {
+        return Response.json(
+          { message: "Name must be 1-32 of a-z, 0-9, - and _" },
+          { status: 400 },
+        );
+      }
+      const invalid = validateProfile(body ?? {});
+      if (invalid) return Response.json({ message: invalid }, { status: 400 });
+      return Response.json(described(saveProfile(params.name, body ?? {})));
+    },
+    { beforeHandle: authorize },
+  )
+  .delete(
+    "/:name",
+    ({ params }) =>
+      deleteProfile(params.name)
+        ? new Response(null, { status: 204 })
+        : notFound(params.name),
+    { beforeHandle: authorize },
+  );
+
+export default profileRoutes;
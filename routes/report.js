@@ -0,0 +1,79 @@
+import { Elysia } from "elysia";
+import { authenticate } from "../lib/auth.js";
+import { wantsHtml } from "../lib/errors.js";
+import { getLocation } from "../lib/locations.js";
+import { renderReport } from "../lib/pages.js";
+import { pdfEnabled, renderPdf } from "../lib/pdf.js";
+import { REGIONS } from "../lib/prices.js";
+import { parseReportPeriod, priceReport } from "../lib/report.js";
+import { dashboardUrl } from "../lib/sites.js";
+
+const report = new Elysia({ prefix: "/report" })
+  // Monthly (`2026-09`) or daily (`2026-09-14`) summary for `?region=` or a
+  // `?location=`: JSON, a printable page for browsers, or PDF with `.pdf`.
+  // A location's consumption is personal, so it takes a login
+  .get("/:period", async ({ params, query, request }) => {
+    if (query.location) {
+      const denied = authenticate({ request });
+      if (denied) return denied;
+    }
+    const pdf = params.period.endsWith(".pdf");
+    const period = parseReportPeriod(params.period.replace(/\.pdf$/, ""));
+    if (!period) {
+      return Response.json(
+        { message: "period must be on the form YYYY-MM or YYYY-MM-DD" },
+        { status: 400 },
+      );
+    }
+    if (pdf && !pdfEnabled()) {
+      return Response.json(
+        { message: "PDF output is not enabled (pdf.command)" },
+        { status: 501 },
+      );
+    }
+
+    const name = query.location || null;
+    const location = name && getLocation(name);
+    if (name && !location) {
+      return Response.json(
+        { message: `Finner ikke stedet ${name}. :-(` },
+        { status: 404 },
+      );
+    }
+    const region = location ? location.region : query.region || "NO2";
+    if (!REGIONS.includes(region)) {
+      return Response.json(
+        { message: "Region must be NO1-NO5" },
+        { status: 400 },
+      );
+    }
+
+    let result;
+    try {
+      result = await priceReport(region, period, name, location);
+    } catch (error) {
+      return Response.json({ message: error.message }, { status: 502 });
+    }
+    if (!result) {
+      return Response.json(
+        { message: "Finner ikke noe data. :-(" },
+        { status: 404 },
+      );
+    }
+
+    const link = dashboardUrl(request, region);
+    if (pdf) {
+      const filename = `elektron-${name || region}-${period.label}.pdf`;
+      return new Response(await renderPdf(renderReport(result, link)), {
+        headers: {
+          "content-type": "application/pdf",
+          "content-disposition": `inline; filename="${filename}"`,
+        },
+      });
+    }
+    return wantsHtml(request)
+      ? renderReport(result, link)
+      : Response.json(result);
+  });
+
+export default report;
@@ -0,0 +1,169 @@
+import { Elysia } from "elysia";
+import { escape } from "../lib/chart.js";
+import { REGIONS, isoDate, parseDate } from "../lib/prices.js";
+import { brandingSettings } from "../lib/pages.js";
+import { qrPng } from "../lib/qr.js";
+import {
+  dashboardUrl,
+  defaultRegion,
+  pathRegion,
+  sitePaths,
+} from "../lib/sites.js";
+
+// Monochrome lightning bolt, matching the page style
+const FAVICON = `<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 32 32">
+<rect width="32" height="32" fill="#1D1C1A"/>
+<path d="M18 3 7 18h7l-2 11 11-15h-7z" fill="#ffffff"/>
+</svg>
+`;
+
+const CACHE = "public, max-age=86400";
+
+// Indexable pages; the per-day prices and charts are linked from the feeds
+function sitemapPaths() {
+  return [
+    "/",
+    "/stats",
+    ...sitePaths().map((path) => `/${path}`),
+    ...REGIONS.map((region) => `/feed/${region}.xml`),
+  ];
+}
+
+// Zone and day (null for today) a link to the dashboard shows, or null if
+// `url` isn't one
+function linkedDay(request, url) {
+  let target;
+  try {
+    target = new URL(url);
+  } catch (error) {
+    return null;
+  }
+  const base = new URL(dashboardUrl(request));
+  if (
+    target.origin !== base.origin ||
+    !target.pathname.startsWith(base.pathname)
+  ) {
+    return null;
+  }
+
+  const path = target.pathname.slice(base.pathname.length).replace(/\/$/, "");
+  const query = Object.fromEntries(target.searchParams);
+  let region = null;
+  if (path === "") region = defaultRegion(request, query);
+  else if (sitePaths().includes(path)) region = pathRegion(path);
+  const date = query.date ? parseDate(query.date) : null;
+  if (!region || (query.date && !date)) return null;
+  return { region, date: date && isoDate(date) };
+}
+
+function favicon() {
+  return new Response(FAVICON, {
+    headers: { "content-type": "image/svg+xml", "cache-control": CACHE },
+  });
+}
+
+const site = new Elysia()
+  .get("/favicon.svg", favicon)
+  // Browsers ask for `/favicon.ico` regardless of the `<link rel="icon">`
+  .get("/favicon.ico", favicon)
+  .get("/robots.txt", ({ request }) => {
+    const origin = new URL(request.url).origin;
+    return new Response(
+      [
+        "User-agent: *",
+        "Disallow: /admin/",
+        "Disallow: /api/",
+        "Disallow: /export",
+        "",
+        `Sitemap: ${origin}/sitemap.xml`,
+        "",
+      ].join("\n"),
+      { headers: { "content-type": "text/plain; charset=utf-8" } },
+    );
+  })
+  .get("/sitemap.xml", ({ request }) => {
+    const origin = new URL(request.url).origin;
+    const urls = sitemapPaths()
+      .map((path) => `  <url><loc>${escape(origin + path)}</loc></url>`)
+      .join("\n");
+    return new Response(
+      `<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+${urls}
+</urlset>
+`,
+      { headers: { "content-type": "application/xml; charset=utf-8" } },
+    );
+  })
+  // oEmbed (https://oembed.com) for links to the dashboard: an iframe with
+  // the linked day's chart, within `?maxwidth=` and `?maxheight=`
+  .get("/oembed", ({ query, request }) => {
+    if (query.format && query.format !== "json") {
+      return Response.json(
+        { message: "Only the json format is supported" },
+        { status: 501 },
+      );
+    }
+    const linked = linkedDay(request, query.url);
+    if (!linked) {
+      return Response.json(
+        { message: "url must link to this dashboard" },
+        { status: 404 },
+      );
+    }
+
+    // The chart is 2:1, with a line for the link below it
+    let width = Math.min(800, parseInt(query.maxwidth) || 800);
+    let height = Math.round(width / 2) + 40;
+    const maxheight = parseInt(query.maxheight);
+    if (maxheight && maxheight < height) {
+      height = maxheight;
+      width = Math.min(width, (maxheight - 40) * 2);
+    }
+    const source = new URL("widget/embed", dashboardUrl(request));
+    source.searchParams.set("region", linked.region);
+    if (linked.date) source.searchParams.set("date", linked.date);
+    const title = `Strømpriser i ${linked.region}${linked.date ? ` ${linked.date}` : ""}`;
+
+    return Response.json({
+      version: "1.0",
+      type: "rich",
+      provider_name: brandingSettings().title,
+      provider_url: dashboardUrl(request),
+      title,
+      html: `<iframe src="${escape(source.href)}" width="${width}" height="${height}" frameborder="0" scrolling="no" title="${escape(title)}"></iframe>`,
+      width,
+      height,
+    });
+  })
+  // QR code linking to the dashboard (`base_url`, or where it is served
+  // from), or with `?region=` and `?date=` to that zone and day
+  .get("/qr.png", ({ query, request }) => {
+    if (query.region && !REGIONS.includes(query.region)) {
+      return Response.json(
+        { message: "Region must be NO1-NO5" },
+        { status: 400 },
+      );
+    }
+    const date = query.date ? parseDate(query.date) : null;
+    if (query.date && !date) {
+      return Response.json(
+        { message: "date must be on the form YYYY-MM-DD" },
+        { status: 400 },
+      );
+    }
+    const scale = query.scale ? parseInt(query.scale) : 8;
+    if (!(scale >= 1 && scale <= 32)) {
+      return Response.json(
+        { message: "scale must be between 1 and 32" },
+        { status: 400 },
+      );
+    }
+
+    const url = dashboardUrl(request, query.region, date && isoDate(date));
+    return new Response(qrPng(url, { scale }), {
+      headers: { "content-type": "image/png", "cache-control": CACHE },
+    });
+  });
+
+export default site;
@@ -0,0 +1,148 @@
+import { Elysia } from "elysia";
+import { formatHour, formatOre } from "../lib/locale.js";
+import { renderEmbed } from "../lib/pages.js";
+import {
+  REGIONS,
+  currentEntry,
+  getHorizon,
+  getPrices,
+  inHourRange,
+  isoDate,
+  parseDate,
+  parseHourRange,
+  priceLevel,
+} from "../lib/prices.js";
+import { dashboardUrl } from "../lib/sites.js";
+import { cheapestWindow, summarize, trend } from "../lib/stats.js";
+
+const ARROWS = { up: "↑", down: "↓", flat: "→" };
+
+const widget = new Elysia({ prefix: "/widget" })
+  // Waybar/polybar custom module: `{ text, tooltip, class }`
+  .get("/bar.json", async ({ query }) => {
+    const region = query.region || "NO2";
+    if (!REGIONS.includes(region)) {
+      return Response.json(
+        { message: "Region must be NO1-NO5" },
+        { status: 400 },
+      );
+    }
+    const hours = parseHourRange(query);
+    if (hours?.message) {
+      return Response.json({ message: hours.message }, { status: 400 });
+    }
+
+    try {
+      const now = new Date();
+      const chart = await getPrices(now, region);
+
+      const current = currentEntry(chart, now);
+      if (!current) {
+        return Response.json({ text: "–", tooltip: "Ingen data.", class: "" });
+      }
+
+      // Min./avg./max. over `?from_hour=&to_hour=`, if it has any prices
+      const window = inHourRange(chart, hours);
+      const prices = (window.length > 0 ? window : chart).map(
+        (item) => item.price,
+      );
+      const { min, avg, max } = summarize(prices);
+
+      return Response.json({
+        text: `${formatOre(current.price)} øre`,
+        tooltip:
+          `${region} ${formatHour(current.hour)}\n` +
+          `Min.: ${formatOre(min)}\n` +
+          `Gjn.: ${formatOre(avg)}\n` +
+          `Maks: ${formatOre(max)}`,
+        class: priceLevel(current.price, prices),
+      });
+    } catch (error) {
+      return Response.json(
+        { message: "Finner ikke noe data. :-(" },
+        { status: 500 },
+      );
+    }
+  })
+  // Small, stable summary for iOS Shortcuts and Android widget apps
+  .get("/summary.json", async ({ query }) => {
+    const region = query.region || "NO2";
+    if (!REGIONS.includes(region)) {
+      return Response.json(
+        { message: "Region must be NO1-NO5" },
+        { status: 400 },
+      );
+    }
+    const hours = query.hours ? parseFloat(query.hours) : 3;
+    if (!(hours > 0 && hours <= 24)) {
+      return Response.json(
+        { message: "hours must be between 0 and 24" },
+        { status: 400 },
+      );
+    }
+
+    try {
+      const now = new Date();
+      const chart = await getHorizon(region, now);
+      const current = currentEntry(chart, now);
+      if (!current) {
+        return Response.json(
+          { message: "Finner ikke noe data. :-(" },
+          { status: 404 },
+        );
+      }
+
+      const today = chart.filter(
+        (item) => item.time.slice(0, 10) === current.time.slice(0, 10),
+      );
+      const next = chart[chart.indexOf(current) + 1] || null;
+      const direction = next ? trend(current.price, next.price) : "flat";
+      const window = cheapestWindow(chart, hours, now);
+      return Response.json({
+        region,
+        time: current.time,
+        price: Math.round(current.price * 10) / 10,
+        unit: "øre/kWh",
+        level: priceLevel(current.price, today.map((item) => item.price)),
+        trend: direction,
+        arrow: ARROWS[direction],
+        next_price: next && Math.round(next.price * 10) / 10,
+        cheapest: window && {
+          hours,
+          start: window.start,
+          end: window.end,
+          average: Math.round(window.average * 10) / 10,
+        },
+      });
+    } catch (error) {
+      return Response.json(
+        { message: "Finner ikke noe data. :-(" },
+        { status: 500 },
+      );
+    }
+  })
+  // A day's chart (`?date=`, today by default) for iframes, as /oembed
+  // hands out
+  .get("/embed", ({ query, request }) => {
+    const region = query.region || "NO2";
+    if (!REGIONS.includes(region)) {
+      return Response.json(
+        { message: "Region must be NO1-NO5" },
+        { status: 400 },
+      );
+    }
+    const date = query.date ? parseDate(query.date) : new Date();
+    if (!date) {
+      return Response.json(
+        { message: "date must be on the form YYYY-MM-DD" },
+        { status: 400 },
+      );
+    }
+
+    const link = dashboardUrl(request, region, query.date && isoDate(date));
+    return new Response(renderEmbed({ region, date: isoDate(date), link }), {
+      headers: { "content-type": "text/html; charset=utf-8" },
+    });
+  });
+
+export default widget;
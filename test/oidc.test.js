@@ -0,0 +1,109 @@
+import { afterAll, beforeAll, describe, expect, test } from "bun:test";
+import { authenticate, authorize } from "../lib/auth.js";
+import { config } from "../lib/config.js";
+import {
+  completeLogin,
+  loginRedirect,
+  oidcSettings,
+  sessionUser,
+} from "../lib/oidc.js";
+
+const ISSUER = "https://id.example.com";
+
+// A provider that hands out an ID token for `claims` (with the nonce the
+// login was started with)
+function provider(claims) {
+  return async (url) => {
+    if (String(url).endsWith("/.well-known/openid-configuration")) {
+      return Response.json({
+        issuer: ISSUER,
+        authorization_endpoint: `${ISSUER}/authorize`,
+        token_endpoint: `${ISSUER}/token`,
+      });
+    }
+    const payload = Buffer.from(JSON.stringify(claims())).toString(
+      "base64url",
+    );
+    return Response.json({ id_token: `e30.${payload}.` });
+  };
+}
+
+// The `name=value` of a `set-cookie` header
+const pair = (cookie) => cookie.split(";")[0];
+
+function request(cookie = "") {
+  return new Request("http://localhost/admin/jobs", {
+    headers: { cookie },
+  });
+}
+
+let fetch;
+let oidc;
+let nonce;
+
+beforeAll(() => {
+  fetch = globalThis.fetch;
+  oidc = config.oidc;
+  config.oidc = {
+    issuer: ISSUER,
+    client_id: "elektron",
+    session_secret: "not so secret",
+  };
+  globalThis.fetch = provider(() => ({
+    iss: ISSUER,
+    aud: "elektron",
+    sub: "emma",
+    email: "emma@example.com",
+    exp: Date.now() / 1000 + 60,
+    nonce,
+  }));
+});
+
+afterAll(() => {
+  globalThis.fetch = fetch;
+  config.oidc = oidc;
+});
+
+describe("sessions", () => {
+  test("are given after a login and let the user in", async () => {
+    const settings = oidcSettings();
+    const login = await loginRedirect(settings, request(), "/admin/");
+    const url = new URL(login.url);
+    nonce = url.searchParams.get("nonce");
+
+    const { user, next, cookie } = await completeLogin(
+      settings,
+      request(pair(login.cookie)),
+      { state: url.searchParams.get("state"), code: "abc" },
+    );
+    expect(user.sub).toBe("emma");
+    expect(next).toBe("/admin/");
+
+    const session = request(pair(cookie));
+    expect(sessionUser(settings, session).sub).toBe("emma");
+    expect(authorize({ request: session })).toBe(undefined);
+    expect(authenticate({ request: session })).toBe(undefined);
+  });
+
+  test("can't be the login state", async () => {
+    const settings = oidcSettings();
+    const login = await loginRedirect(settings, request(), "/");
+    const state = pair(login.cookie).replace(/^oidc=/, "");
+    const replayed = request(`session=${state}`);
+
+    expect(sessionUser(settings, replayed)).toBeNull();
+    expect(authorize({ request: replayed }).status).toBe(401);
+    expect(authenticate({ request: replayed }).status).toBe(401);
+  });
+
+  test("are refused when tampered with", () => {
+    const settings = oidcSettings();
+    const payload = Buffer.from(
+      JSON.stringify({ sub: "emma", typ: "session", exp: 2 ** 40 }),
+    ).toString("base64url");
+    const forged = request(`session=${payload}.bm9wZQ`);
+
+    expect(sessionUser(settings, forged)).toBeNull();
+    expect(authorize({ request: forged }).status).toBe(401);
+  });
+});
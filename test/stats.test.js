@@ -0,0 +1,272 @@
+import { describe, expect, test } from "bun:test";
+import {
+  average,
+  cheapestWindow,
+  dayStatistics,
+  durations,
+  histogram,
+  maximum,
+  median,
+  minimum,
+  percentile,
+  percentileRank,
+  quantile,
+  summarize,
+  windowAt,
+} from "../lib/stats.js";
+import { HOUR, formatLocal, fromLocal } from "../lib/time.js";
+
+// Deterministic pseudo-random numbers in [0, 1), so a failing case can be
+// run again
+function random(seed) {
+  let state = seed;
+  return () => {
+    state = (state + 0x6d2b79f5) | 0;
+    let t = Math.imul(state ^ (state >>> 15), 1 | state);
+    t = (t + Math.imul(t ^ (t >>> 7), 61 | t)) ^ t;
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+}
+
+// `runs` cases of prices from -50 to 450 øre, sometimes all negative, each
+// list 1 to 100 long
+function cases(runs = 200) {
+  const next = random(runs);
+  return Array.from({ length: runs }, () => {
+    const length = 1 + Math.floor(next() * 100);
+    const offset = next() < 0.2 ? -500 : -50;
+    return Array.from({ length }, () => offset + next() * 500);
+  });
+}
+
+// A local day (YYYY-MM-DD) of `minutes`-long intervals with the prices
+// `price(i)`, 23 or 25 hours long around the DST changes
+function day(date, price = (i) => 100 + 10 * Math.sin(i), minutes = 60) {
+  const start = fromLocal(`${date}T00:00`);
+  const next = new Date(Date.parse(`${date}T12:00Z`) + 24 * HOUR)
+    .toISOString()
+    .slice(0, 10);
+  const end = fromLocal(`${next}T00:00`);
+  const chart = [];
+  for (let time = start; time < end; time += minutes * 60000) {
+    const local = formatLocal(time);
+    chart.push({
+      hour: parseInt(local.slice(11, 13)),
+      time: local,
+      price: price(chart.length),
+    });
+  }
+  return chart;
+}
+
+const close = (a, b) => Math.abs(a - b) < 1e-9;
+
+describe("summaries", () => {
+  test("are null without numbers", () => {
+    expect(summarize([])).toBeNull();
+    expect(summarize([NaN, NaN])).toBeNull();
+    expect(average([])).toBeNull();
+    expect(minimum([])).toBeNull();
+    expect(maximum([NaN])).toBeNull();
+    expect(median([])).toBeNull();
+    expect(quantile([], 0.5)).toBeNull();
+    expect(percentileRank([], 1)).toBeNull();
+  });
+
+  test("order min ≤ median ≤ max and min ≤ avg ≤ max", () => {
+    for (const prices of cases()) {
+      const { min, avg, max } = summarize(prices);
+      expect(prices).toContain(min);
+      expect(prices).toContain(max);
+      expect(min).toBeLessThanOrEqual(avg + 1e-9);
+      expect(avg).toBeLessThanOrEqual(max + 1e-9);
+      expect(median(prices)).toBeGreaterThanOrEqual(min);
+      expect(median(prices)).toBeLessThanOrEqual(max);
+    }
+  });
+
+  test("ignore missing prices", () => {
+    for (const prices of cases(50)) {
+      const holes = [NaN, ...prices, Infinity, NaN];
+      expect(summarize(holes)).toEqual(summarize(prices));
+      expect(median(holes)).toBe(median(prices));
+    }
+  });
+
+  test("move with the prices", () => {
+    for (const prices of cases(50)) {
+      const shifted = prices.map((price) => price + 42);
+      expect(close(average(shifted), average(prices) + 42)).toBe(true);
+      expect(close(median(shifted), median(prices) + 42)).toBe(true);
+    }
+  });
+
+  test("handle a constant day", () => {
+    expect(summarize([-3, -3, -3])).toEqual({ min: -3, avg: -3, max: -3 });
+    expect(percentile([7, 7], 90)).toBe(7);
+  });
+
+  test("survive lists too long to spread", () => {
+    const prices = Array.from({ length: 300000 }, (_, i) => i % 1000);
+    expect(minimum(prices)).toBe(0);
+    expect(maximum(prices)).toBe(999);
+  });
+});
+
+describe("percentiles", () => {
+  test("run from the minimum to the maximum", () => {
+    for (const prices of cases()) {
+      expect(percentile(prices, 0)).toBe(minimum(prices));
+      expect(percentile(prices, 100)).toBe(maximum(prices));
+      // Out of range is clamped
+      expect(percentile(prices, -5)).toBe(minimum(prices));
+      expect(percentile(prices, 150)).toBe(maximum(prices));
+    }
+  });
+
+  test("never decrease", () => {
+    for (const prices of cases(50)) {
+      let previous = -Infinity;
+      for (let percent = 0; percent <= 100; percent += 5) {
+        const value = percentile(prices, percent);
+        expect(value).toBeGreaterThanOrEqual(previous - 1e-9);
+        previous = value;
+      }
+    }
+  });
+
+  test("rank every price within 0–100", () => {
+    for (const prices of cases(50)) {
+      const sorted = [...prices].sort((a, b) => a - b);
+      for (const price of prices) {
+        const rank = percentileRank(sorted, price);
+        expect(rank).toBeGreaterThan(0);
+        expect(rank).toBeLessThan(100);
+      }
+      expect(percentileRank(sorted, sorted[0] - 1)).toBe(0);
+      expect(percentileRank(sorted, sorted[sorted.length - 1] + 1)).toBe(100);
+    }
+  });
+});
+
+describe("histograms", () => {
+  test("count every price once", () => {
+    for (const prices of cases(50)) {
+      const total = (bins) => bins.reduce((sum, bin) => sum + bin.count, 0);
+      expect(total(histogram(prices, { bins: 7 }))).toBe(prices.length);
+      expect(total(histogram([NaN, ...prices], { width: 25 }))).toBe(
+        prices.length,
+      );
+    }
+  });
+
+  test("put each price in the bin covering it", () => {
+    for (const prices of cases(50)) {
+      const bins = histogram(prices, { width: 10 });
+      for (const price of prices) {
+        const bin = bins.find((bin) => bin.from <= price && price < bin.to);
+        expect(bin).toBeDefined();
+      }
+      for (const bin of bins) {
+        expect(close(bin.from / 10, Math.round(bin.from / 10))).toBe(true);
+      }
+    }
+  });
+
+  test("have the asked number of bins", () => {
+    expect(histogram([1, 2, 3, 4, 5], { bins: 4 }).length).toBe(4);
+    expect(histogram([1, 2, 3, 4, 5], { bins: 4 })[3].count).toBe(2);
+    expect(histogram([7, 7, 7])).toEqual([{ from: 6.5, to: 7.5, count: 3 }]);
+    expect(histogram([])).toEqual([]);
+  });
+});
+
+describe("interval lengths", () => {
+  test("add up to the length of the day", () => {
+    const sum = (chart) => durations(chart).reduce((a, b) => a + b, 0);
+    expect(sum(day("2025-01-15"))).toBe(24);
+    expect(sum(day("2025-03-30"))).toBe(23);
+    expect(sum(day("2025-10-26"))).toBe(25);
+    expect(close(sum(day("2025-10-26", undefined, 15)), 25)).toBe(true);
+  });
+
+  test("have a 25-hour day repeat an hour", () => {
+    const hours = day("2025-10-26").map((item) => item.hour);
+    expect(hours.length).toBe(25);
+    expect(hours.filter((hour) => hour === 2).length).toBe(2);
+  });
+});
+
+describe("windows", () => {
+  test("are never dearer than any other start", () => {
+    const next = random(7);
+    for (const date of ["2025-01-15", "2025-03-30", "2025-10-26"]) {
+      for (let run = 0; run < 20; run++) {
+        const chart = day(date, () => -50 + next() * 500);
+        const hours = 1 + Math.floor(next() * 6);
+        const best = cheapestWindow(chart, hours);
+        const { min, max } = summarize(chart.map((item) => item.price));
+        expect(best.average).toBeGreaterThanOrEqual(min - 1e-9);
+        expect(best.average).toBeLessThanOrEqual(max + 1e-9);
+        for (const item of chart) {
+          const other = windowAt(chart, hours, new Date(item.time));
+          if (other) {
+            expect(best.average).toBeLessThanOrEqual(other.average + 1e-9);
+          }
+        }
+      }
+    }
+  });
+
+  test("fit the length of the day", () => {
+    expect(cheapestWindow(day("2025-10-26"), 25)).not.toBeNull();
+    expect(cheapestWindow(day("2025-10-26"), 26)).toBeNull();
+    expect(cheapestWindow(day("2025-03-30"), 23)).not.toBeNull();
+    expect(cheapestWindow(day("2025-03-30"), 24)).toBeNull();
+  });
+
+  test("are null for no time or no prices", () => {
+    const chart = day("2025-01-15");
+    expect(cheapestWindow(chart, 0)).toBeNull();
+    expect(cheapestWindow(chart, -1)).toBeNull();
+    expect(cheapestWindow(chart, NaN)).toBeNull();
+    expect(cheapestWindow([], 2)).toBeNull();
+    expect(windowAt(chart, 0, new Date())).toBeNull();
+    expect(windowAt([], 2, new Date())).toBeNull();
+  });
+
+  test("skip missing prices", () => {
+    const price = (i) => (i === 3 ? NaN : i === 4 ? -10 : 50);
+    const chart = day("2025-01-15", price);
+    const best = cheapestWindow(chart, 2);
+    expect(best.start).toBe(chart[4].time);
+    expect(Number.isFinite(best.average)).toBe(true);
+  });
+
+  test("cover the right hours", () => {
+    const chart = day("2025-01-15", (i) => (i >= 10 && i < 13 ? -5 : 80));
+    const best = cheapestWindow(chart, 3);
+    expect(best.start).toBe(chart[10].time);
+    expect(best.end).toBe(chart[13].time);
+    expect(best.average).toBe(-5);
+  });
+});
+
+describe("day statistics", () => {
+  test("are empty for an empty day", () => {
+    expect(dayStatistics([], ["min", "avg", "max"])).toEqual([]);
+  });
+
+  test("match the summary on other days than today", () => {
+    const chart = day("2025-10-26", (i) => i - 10);
+    const statistics = dayStatistics(
+      chart,
+      ["min", "avg", "max", "median", "current", "remaining"],
+      new Date("2026-01-01T12:00Z"),
+    );
+    const values = Object.fromEntries(
+      statistics.map((statistic) => [statistic.name, statistic.value]),
+    );
+    expect(values).toEqual({ min: -10, avg: 2, max: 14, median: 2 });
+  });
+});